@@ -0,0 +1,77 @@
+use crate::val::{BVal, Val};
+
+use super::Op;
+
+fn backward(child: &BVal) {
+    let child = child.borrow();
+    let parent = child.parents.0.as_ref().unwrap();
+
+    let d = parent.borrow().d;
+
+    // derivative at zero is taken as zero
+    let sign = if d > 0.0 {
+        1.0
+    } else if d < 0.0 {
+        -1.0
+    } else {
+        0.0
+    };
+
+    parent.borrow_mut().grad += sign * child.grad;
+}
+
+impl BVal {
+    pub fn abs(&self) -> Self {
+        BVal::new_val(Val {
+            d: self.borrow().d.abs(),
+            parents: (Some(self.clone()), None),
+            op: Op::Abs,
+            grad: 0.0,
+            backward,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::Op;
+
+    use super::*;
+
+    #[test]
+    fn forward() {
+        let a = BVal::new(-1.5);
+        let b = a.abs();
+
+        assert_eq!(b.borrow().d, 1.5);
+        assert_eq!(b.borrow().op, Op::Abs);
+    }
+
+    #[test]
+    fn parents() {
+        let a = BVal::new(-1.5);
+        let b = a.abs();
+
+        assert!(a.borrow().parents.0.is_none());
+        assert!(a.borrow().parents.1.is_none());
+
+        assert!(a.borrow().op == Op::None);
+
+        assert!(b.borrow().parents.0.as_ref().unwrap().as_ptr() == a.as_ptr());
+        assert!(b.borrow().parents.1.is_none());
+        assert!(b.borrow().op == Op::Abs);
+    }
+
+    #[test]
+    fn backward() {
+        for (d, expected_grad) in [(-1.5, -5.0), (1.5, 5.0), (0.0, 0.0)] {
+            let a = BVal::new(d);
+            let b = a.abs();
+
+            b.borrow_mut().grad = 5.0;
+            b.backward();
+
+            assert_eq!(a.borrow().grad, expected_grad);
+        }
+    }
+}
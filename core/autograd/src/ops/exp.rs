@@ -0,0 +1,67 @@
+use crate::val::{BVal, Val};
+
+use super::Op;
+
+fn backward(child: &BVal) {
+    let child = child.borrow();
+    let parent = child.parents.0.as_ref().unwrap();
+
+    parent.borrow_mut().grad += child.d * child.grad;
+}
+
+impl BVal {
+    pub fn exp(&self) -> Self {
+        BVal::new_val(Val {
+            d: self.borrow().d.exp(),
+            parents: (Some(self.clone()), None),
+            op: Op::Exp,
+            grad: 0.0,
+            backward,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::assert_approx_eq;
+
+    use crate::ops::Op;
+
+    use super::*;
+
+    #[test]
+    fn forward() {
+        let a = BVal::new(1.5);
+        let b = a.exp();
+
+        assert_approx_eq!(f64, b.borrow().d, 4.4816890703380645);
+        assert_eq!(b.borrow().op, Op::Exp);
+    }
+
+    #[test]
+    fn parents() {
+        let a = BVal::new(1.5);
+        let b = a.exp();
+
+        assert!(a.borrow().parents.0.is_none());
+        assert!(a.borrow().parents.1.is_none());
+
+        assert!(a.borrow().op == Op::None);
+
+        assert!(b.borrow().parents.0.as_ref().unwrap().as_ptr() == a.as_ptr());
+        assert!(b.borrow().parents.1.is_none());
+        assert!(b.borrow().op == Op::Exp);
+    }
+
+    #[test]
+    fn backward() {
+        let a = BVal::new(1.5);
+        let b = a.exp();
+
+        b.borrow_mut().grad = 5.0;
+        b.backward();
+
+        assert_approx_eq!(f64, a.borrow().grad, 22.40844535169032);
+        assert_eq!(b.borrow().grad, 5.0);
+    }
+}
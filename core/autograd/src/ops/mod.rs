@@ -1,8 +1,12 @@
+mod abs;
 mod add;
 mod div;
+mod exp;
 mod mul;
 mod neg;
 mod pow;
+mod relu;
+mod sigmoid;
 mod sub;
 mod tanh;
 
@@ -13,4 +17,8 @@ pub enum Op {
     Mul,
     Pow,
     Tanh,
+    Relu,
+    Sigmoid,
+    Abs,
+    Exp,
 }
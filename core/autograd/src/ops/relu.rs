@@ -0,0 +1,79 @@
+use crate::val::{BVal, Val};
+
+use super::Op;
+
+fn backward(child: &BVal) {
+    let child = child.borrow();
+    let parent = child.parents.0.as_ref().unwrap();
+
+    if child.d > 0.0 {
+        parent.borrow_mut().grad += child.grad;
+    }
+}
+
+impl BVal {
+    pub fn relu(&self) -> Self {
+        BVal::new_val(Val {
+            d: self.borrow().d.max(0.0),
+            parents: (Some(self.clone()), None),
+            op: Op::Relu,
+            grad: 0.0,
+            backward,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::Op;
+
+    use super::*;
+
+    #[test]
+    fn forward() {
+        let a = BVal::new(1.5);
+        let b = a.relu();
+
+        assert_eq!(b.borrow().d, 1.5);
+        assert_eq!(b.borrow().op, Op::Relu);
+
+        let c = BVal::new(-1.5);
+        let d = c.relu();
+
+        assert_eq!(d.borrow().d, 0.0);
+    }
+
+    #[test]
+    fn parents() {
+        let a = BVal::new(1.5);
+        let b = a.relu();
+
+        assert!(a.borrow().parents.0.is_none());
+        assert!(a.borrow().parents.1.is_none());
+
+        assert!(a.borrow().op == Op::None);
+
+        assert!(b.borrow().parents.0.as_ref().unwrap().as_ptr() == a.as_ptr());
+        assert!(b.borrow().parents.1.is_none());
+        assert!(b.borrow().op == Op::Relu);
+    }
+
+    #[test]
+    fn backward() {
+        let a = BVal::new(1.5);
+        let b = a.relu();
+
+        b.borrow_mut().grad = 5.0;
+        b.backward();
+
+        assert_eq!(a.borrow().grad, 5.0);
+
+        let c = BVal::new(-1.5);
+        let d = c.relu();
+
+        d.borrow_mut().grad = 5.0;
+        d.backward();
+
+        assert_eq!(c.borrow().grad, 0.0);
+    }
+}
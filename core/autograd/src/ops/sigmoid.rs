@@ -0,0 +1,69 @@
+use crate::val::{BVal, Val};
+
+use super::Op;
+
+fn backward(child: &BVal) {
+    let child = child.borrow();
+    let parent = child.parents.0.as_ref().unwrap();
+
+    parent.borrow_mut().grad += child.d * (1.0 - child.d) * child.grad;
+}
+
+impl BVal {
+    pub fn sigmoid(&self) -> Self {
+        let d = 1.0 / (1.0 + (-self.borrow().d).exp());
+
+        BVal::new_val(Val {
+            d,
+            parents: (Some(self.clone()), None),
+            op: Op::Sigmoid,
+            grad: 0.0,
+            backward,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::assert_approx_eq;
+
+    use crate::ops::Op;
+
+    use super::*;
+
+    #[test]
+    fn forward() {
+        let a = BVal::new(1.5);
+        let b = a.sigmoid();
+
+        assert_approx_eq!(f64, b.borrow().d, 0.8175744761936437);
+        assert_eq!(b.borrow().op, Op::Sigmoid);
+    }
+
+    #[test]
+    fn parents() {
+        let a = BVal::new(1.5);
+        let b = a.sigmoid();
+
+        assert!(a.borrow().parents.0.is_none());
+        assert!(a.borrow().parents.1.is_none());
+
+        assert!(a.borrow().op == Op::None);
+
+        assert!(b.borrow().parents.0.as_ref().unwrap().as_ptr() == a.as_ptr());
+        assert!(b.borrow().parents.1.is_none());
+        assert!(b.borrow().op == Op::Sigmoid);
+    }
+
+    #[test]
+    fn backward() {
+        let a = BVal::new(1.5);
+        let b = a.sigmoid();
+
+        b.borrow_mut().grad = 5.0;
+        b.backward();
+
+        assert_approx_eq!(f64, a.borrow().grad, 0.7457322603516643);
+        assert_eq!(b.borrow().grad, 5.0);
+    }
+}
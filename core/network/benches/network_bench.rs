@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use autograd::val::BVal;
 use criterion::{criterion_group, criterion_main, Criterion};
-use network::network::Network;
+use network::{dense_model::DenseModel, network::Network};
 
 #[inline]
 fn classification() {
@@ -48,6 +48,37 @@ pub fn classification_benchmark(c: &mut Criterion) {
     c.bench_function("classification_benchmark", |b| b.iter(|| classification()));
 }
 
+pub fn forward_benchmark(c: &mut Criterion) {
+    let net = Network::new(vec![784, 1000, 400, 150, 10]);
+    let input: Vec<f64> = (0..784).map(|idx| (idx as f64).sin()).collect();
+
+    c.bench_function("forward-784-1000-400-150-10", |b| {
+        b.iter(|| net.forward(&input))
+    });
+}
+
+pub fn dense_model_forward_benchmark(c: &mut Criterion) {
+    let net = Network::new(vec![784, 1000, 400, 150, 10]);
+    let input: Vec<f64> = (0..784).map(|idx| (idx as f64).sin()).collect();
+
+    let model_f64 = DenseModel::<f64>::try_from(&net).unwrap();
+    c.bench_function("dense-model-forward-f64-784-1000-400-150-10", |b| {
+        b.iter(|| model_f64.forward(&input))
+    });
+
+    let model_f32 = DenseModel::<f32>::try_from(&net).unwrap();
+    let input: Vec<f32> = input.iter().map(|d| *d as f32).collect();
+    c.bench_function("dense-model-forward-f32-784-1000-400-150-10", |b| {
+        b.iter(|| model_f32.forward(&input))
+    });
+
+    let batch = vec![input; 32];
+    c.bench_function(
+        "dense-model-forward-batch-32-f32-784-1000-400-150-10",
+        |b| b.iter(|| model_f32.forward_batch(&batch)),
+    );
+}
+
 criterion_group! {
     name = network_benches;
     config = Criterion::default()
@@ -56,4 +87,12 @@ criterion_group! {
         .noise_threshold(0.05);
     targets = classification_benchmark
 }
-criterion_main!(network_benches);
+criterion_group! {
+    name = forward_benches;
+    config = Criterion::default()
+        .measurement_time(Duration::from_secs(10))
+        .sample_size(20)
+        .noise_threshold(0.05);
+    targets = forward_benchmark, dense_model_forward_benchmark
+}
+criterion_main!(network_benches, forward_benches);
@@ -0,0 +1,365 @@
+use std::ops::{Add, AddAssign, Mul};
+
+use crate::{
+    error::NetworkError,
+    layer::{
+        activation::{Activation, ActivationKind},
+        dense::Dense,
+        dropout::Dropout,
+        flatten::Flatten,
+        reshape::Reshape,
+    },
+    network::Network,
+};
+
+// number of weight matrix rows processed at once by matrix-vector kernel, so each loaded input
+// value is reused for several outputs
+const ROWS_BLOCK: usize = 4;
+
+pub trait Scalar: Copy + Default + Add<Output = Self> + AddAssign + Mul<Output = Self> {
+    fn from_f64(d: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn tanh(self) -> Self;
+    fn relu(self) -> Self;
+    fn sigmoid(self) -> Self;
+}
+
+impl Scalar for f32 {
+    fn from_f64(d: f64) -> Self {
+        d as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn tanh(self) -> Self {
+        f32::tanh(self)
+    }
+
+    fn relu(self) -> Self {
+        self.max(0.0)
+    }
+
+    fn sigmoid(self) -> Self {
+        1.0 / (1.0 + (-self).exp())
+    }
+}
+
+impl Scalar for f64 {
+    fn from_f64(d: f64) -> Self {
+        d
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn tanh(self) -> Self {
+        f64::tanh(self)
+    }
+
+    fn relu(self) -> Self {
+        self.max(0.0)
+    }
+
+    fn sigmoid(self) -> Self {
+        1.0 / (1.0 + (-self).exp())
+    }
+}
+
+pub(crate) struct DenseLayer<T> {
+    pub(crate) inputs: usize,
+    pub(crate) outputs: usize,
+    // row-major matrix [outputs x inputs], ie. each row holds weights of one neuron
+    pub(crate) weights: Vec<T>,
+    pub(crate) biases: Vec<T>,
+    // activation layer following dense layer is applied right in its forward pass
+    pub(crate) activation: Option<ActivationKind>,
+}
+
+impl<T: Scalar> DenseLayer<T> {
+    fn forward_batch<I: AsRef<[T]>>(&self, inputs: &[I]) -> Vec<Vec<T>> {
+        let mut outputs = vec![self.biases.clone(); inputs.len()];
+
+        let row_blocks = self.weights.chunks_exact(self.inputs * ROWS_BLOCK);
+        let rows_rest = row_blocks.remainder();
+        let blocked_rows = self.outputs - rows_rest.len() / self.inputs;
+
+        // go through batch in inner loop, so block of weight rows stays in cache
+        for (block_idx, rows) in row_blocks.enumerate() {
+            for (input, output) in inputs.iter().zip(outputs.iter_mut()) {
+                let acc = dot_block(rows, input.as_ref());
+                let output = &mut output[block_idx * ROWS_BLOCK..(block_idx + 1) * ROWS_BLOCK];
+
+                for (out, acc) in output.iter_mut().zip(acc) {
+                    *out += acc;
+                }
+            }
+        }
+
+        for (row_idx, row) in rows_rest.chunks_exact(self.inputs).enumerate() {
+            for (input, output) in inputs.iter().zip(outputs.iter_mut()) {
+                output[blocked_rows + row_idx] += dot(row, input.as_ref());
+            }
+        }
+
+        if let Some(activation) = self.activation {
+            for output in &mut outputs {
+                for out in output.iter_mut() {
+                    *out = match activation {
+                        ActivationKind::Tanh => out.tanh(),
+                        ActivationKind::Relu => out.relu(),
+                        ActivationKind::Sigmoid => out.sigmoid(),
+                    };
+                }
+            }
+        }
+
+        outputs
+    }
+
+    pub(crate) fn forward(&self, input: &[T]) -> Vec<T> {
+        self.forward_batch(&[input]).pop().unwrap()
+    }
+}
+
+fn dot<T: Scalar>(row: &[T], input: &[T]) -> T {
+    assert_eq!(row.len(), input.len(), "invalid inputs size");
+
+    let mut acc = T::default();
+
+    for (w, x) in row.iter().zip(input) {
+        acc += *w * *x;
+    }
+
+    acc
+}
+
+// multiplies block of weight rows by input vector at once
+fn dot_block<T: Scalar>(rows: &[T], input: &[T]) -> [T; ROWS_BLOCK] {
+    assert_eq!(rows.len(), input.len() * ROWS_BLOCK, "invalid inputs size");
+
+    let (r0, rows) = rows.split_at(input.len());
+    let (r1, rows) = rows.split_at(input.len());
+    let (r2, r3) = rows.split_at(input.len());
+
+    let mut acc = [T::default(); ROWS_BLOCK];
+
+    for (i, x) in input.iter().enumerate() {
+        acc[0] += r0[i] * *x;
+        acc[1] += r1[i] * *x;
+        acc[2] += r2[i] * *x;
+        acc[3] += r3[i] * *x;
+    }
+
+    acc
+}
+
+// inference-only copy of the network, which keeps weights in contiguous matrices instead of
+// separate autograd values, so forward pass is cache friendly and doesn't build computation graph
+pub struct DenseModel<T> {
+    pub(crate) layers: Vec<DenseLayer<T>>,
+}
+
+impl<T: Scalar> DenseModel<T> {
+    pub fn forward(&self, inputs: &[T]) -> Vec<T> {
+        let mut res = inputs.to_vec();
+
+        for layer in &self.layers {
+            res = layer.forward(&res);
+        }
+
+        res
+    }
+
+    pub fn forward_batch(&self, inputs: &[Vec<T>]) -> Vec<Vec<T>> {
+        let mut res = self.layers[0].forward_batch(inputs);
+
+        for layer in &self.layers[1..] {
+            res = layer.forward_batch(&res);
+        }
+
+        res
+    }
+
+    pub fn inputs_count(&self) -> usize {
+        self.layers[0].inputs
+    }
+
+    pub fn outputs_count(&self) -> usize {
+        self.layers.last().unwrap().outputs
+    }
+
+    pub fn parameters_count(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| layer.weights.len() + layer.biases.len())
+            .sum()
+    }
+}
+
+// only dense layers (optionally followed by activation) are supported. layers which only change
+// shape of inputs and dropout (which does nothing in inference) are skipped
+impl<T: Scalar> TryFrom<&Network> for DenseModel<T> {
+    type Error = NetworkError;
+
+    fn try_from(net: &Network) -> Result<Self, Self::Error> {
+        let mut layers: Vec<DenseLayer<T>> = Vec::new();
+
+        for layer in &net.layers {
+            if let Some(dense) = layer.as_any().downcast_ref::<Dense>() {
+                let outputs = dense.outputs_count();
+                let inputs = dense.inputs_count();
+
+                let mut weights = Vec::with_capacity(outputs * inputs);
+                let mut biases = Vec::with_capacity(outputs);
+
+                for neuron in &dense.neurons {
+                    weights.extend(neuron.weights.iter().map(|w| T::from_f64(w.borrow().d)));
+                    biases.push(T::from_f64(neuron.bias.borrow().d));
+                }
+
+                layers.push(DenseLayer {
+                    inputs,
+                    outputs,
+                    weights,
+                    biases,
+                    activation: None,
+                });
+            } else if let Some(activation) = layer.as_any().downcast_ref::<Activation>() {
+                match layers.last_mut() {
+                    Some(last) if last.activation.is_none() => {
+                        last.activation = Some(activation.kind)
+                    }
+                    _ => return Err(NetworkError::UnsupportedLayer(String::from(layer.tag()))),
+                }
+            } else if !(layer.as_any().is::<Dropout>()
+                || layer.as_any().is::<Flatten>()
+                || layer.as_any().is::<Reshape>())
+            {
+                return Err(NetworkError::UnsupportedLayer(String::from(layer.tag())));
+            }
+        }
+
+        if layers.is_empty() {
+            return Err(NetworkError::UnsupportedLayer(String::from(
+                "network without dense layers",
+            )));
+        }
+
+        Ok(DenseModel { layers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen_inputs(count: usize, size: usize) -> Vec<Vec<f64>> {
+        (0..count)
+            .map(|i| {
+                (0..size)
+                    .map(|j| ((i * size + j) as f64 * 0.37).sin())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn forward() {
+        // layer sizes are not multiple of rows block, so remainder rows are covered too
+        let net = Network::new(vec![7, 9, 6, 3]);
+        let model = DenseModel::<f64>::try_from(&net).unwrap();
+
+        assert_eq!(model.inputs_count(), 7);
+        assert_eq!(model.outputs_count(), 3);
+        assert_eq!(model.parameters_count(), net.parameters().len());
+
+        for input in gen_inputs(10, 7) {
+            let expected: Vec<f64> = net.forward(&input).iter().map(|v| v.borrow().d).collect();
+            let actual = model.forward(&input);
+
+            for (expected, actual) in expected.iter().zip(actual) {
+                assert!((expected - actual).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn forward_f32() {
+        let net = Network::new(vec![7, 8, 3]);
+        let model = DenseModel::<f32>::try_from(&net).unwrap();
+
+        for input in gen_inputs(10, 7) {
+            let expected: Vec<f64> = net.forward(&input).iter().map(|v| v.borrow().d).collect();
+
+            let input: Vec<f32> = input.iter().map(|d| *d as f32).collect();
+            let actual = model.forward(&input);
+
+            for (expected, actual) in expected.iter().zip(actual) {
+                assert!((expected - actual as f64).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn forward_batch() {
+        let net = Network::new(vec![5, 10, 4]);
+        let model = DenseModel::<f64>::try_from(&net).unwrap();
+
+        let inputs = gen_inputs(6, 5);
+        let outputs = model.forward_batch(&inputs);
+
+        assert_eq!(outputs.len(), inputs.len());
+
+        for (input, output) in inputs.iter().zip(outputs) {
+            assert_eq!(model.forward(input), output);
+        }
+    }
+
+    #[test]
+    fn forward_mixed_layers() {
+        let net = Network::from_layers(
+            vec![2, 3],
+            vec![
+                Box::new(Flatten::new()),
+                Box::new(Dense::new(6, 5)),
+                Box::new(Activation::relu()),
+                Box::new(Dropout::new(0.5)),
+                Box::new(Dense::new(5, 3)),
+                Box::new(Activation::sigmoid()),
+                Box::new(Dense::new(3, 2)),
+            ],
+        )
+        .unwrap();
+
+        let model = DenseModel::<f64>::try_from(&net).unwrap();
+
+        for input in gen_inputs(10, 6) {
+            let expected: Vec<f64> = net.forward(&input).iter().map(|v| v.borrow().d).collect();
+            let actual = model.forward(&input);
+
+            for (expected, actual) in expected.iter().zip(actual) {
+                assert!((expected - actual).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_layer() {
+        let net = Network::from_layers(
+            vec![3],
+            vec![
+                Box::new(Dense::new(3, 2)),
+                Box::new(Activation::relu()),
+                Box::new(Activation::tanh()),
+            ],
+        )
+        .unwrap();
+
+        let res = DenseModel::<f64>::try_from(&net);
+
+        assert!(matches!(res, Err(NetworkError::UnsupportedLayer(tag)) if tag == Activation::TAG));
+    }
+}
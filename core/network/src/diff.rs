@@ -0,0 +1,520 @@
+use std::fmt;
+
+use autograd::val::BVal;
+
+use crate::{
+    error::NetworkError,
+    network::Network,
+    summary::{LayerSummary, NetworkSummary},
+};
+
+// parameter changes between two networks of the same architecture, e.g. checkpoints of
+// different epochs, see Network::diff()
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkDiff {
+    // why networks can't be compared parameter by parameter, or None if they can
+    pub incompatibility: Option<String>,
+    // empty for incompatible networks
+    pub layers: Vec<LayerDiff>,
+    // changes of all parameters, or None for incompatible networks or networks without parameters
+    pub total: Option<ParametersDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerDiff {
+    pub tag: &'static str,
+    pub name: String,
+    pub parameters_count: usize,
+    // None for layers without parameters
+    pub parameters_diff: Option<ParametersDiff>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParametersDiff {
+    // euclidean distance between base and other parameters
+    pub l2_distance: f64,
+    // distance relative to the norm of base parameters, NaN if all of them are zeros
+    pub relative_change: f64,
+    // cosine of angle between base and other parameters, NaN if either of them are all zeros
+    pub cosine_similarity: f64,
+}
+
+// how often two networks predict the same class (index of the max output) on labeled samples,
+// see Network::agreement()
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputAgreement {
+    // indexed by label
+    pub classes: Vec<ClassAgreement>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ClassAgreement {
+    pub samples_count: usize,
+    // samples for which both networks predicted the same class
+    pub agreed_count: usize,
+    pub base_correct_count: usize,
+    pub other_correct_count: usize,
+}
+
+impl Network {
+    // compares parameters of this (base) network with other one layer by layer
+    pub fn diff(&self, other: &Network) -> NetworkDiff {
+        NetworkDiff::new(self, other)
+    }
+
+    // predictions of both networks on labeled samples, grouped by label
+    pub fn agreement(
+        &self,
+        other: &Network,
+        samples: impl IntoIterator<Item = (Vec<f64>, usize)>,
+    ) -> Result<OutputAgreement, NetworkError> {
+        OutputAgreement::new(self, other, samples)
+    }
+}
+
+impl NetworkDiff {
+    fn new(base: &Network, other: &Network) -> Self {
+        let base_summary = base.summary();
+        let other_summary = other.summary();
+
+        if let Some(incompatibility) = get_incompatibility(&base_summary, &other_summary) {
+            return NetworkDiff {
+                incompatibility: Some(incompatibility),
+                layers: Vec::new(),
+                total: None,
+            };
+        }
+
+        let layers = base
+            .layers
+            .iter()
+            .zip(&other.layers)
+            .zip(&base_summary.layers)
+            .map(|((base_layer, other_layer), summary)| LayerDiff {
+                tag: summary.tag,
+                name: summary.name(),
+                parameters_count: summary.parameters_count,
+                parameters_diff: ParametersDiff::from_parameters(
+                    &base_layer.parameters(),
+                    &other_layer.parameters(),
+                ),
+            })
+            .collect();
+
+        NetworkDiff {
+            incompatibility: None,
+            layers,
+            total: ParametersDiff::from_parameters(base.parameters(), other.parameters()),
+        }
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        self.incompatibility.is_none()
+    }
+}
+
+impl ParametersDiff {
+    // None for empty lists of parameters
+    pub fn from_parameters(base: &[BVal], other: &[BVal]) -> Option<Self> {
+        assert_eq!(base.len(), other.len(), "parameters counts should match");
+
+        if base.is_empty() {
+            return None;
+        }
+
+        let mut distance_sq = 0.0;
+        let mut base_norm_sq = 0.0;
+        let mut other_norm_sq = 0.0;
+        let mut dot = 0.0;
+
+        for (base, other) in base.iter().zip(other) {
+            let base = base.borrow().d;
+            let other = other.borrow().d;
+
+            distance_sq += (other - base).powi(2);
+            base_norm_sq += base * base;
+            other_norm_sq += other * other;
+            dot += base * other;
+        }
+
+        let l2_distance = f64::sqrt(distance_sq);
+        let base_norm = f64::sqrt(base_norm_sq);
+
+        Some(ParametersDiff {
+            l2_distance,
+            relative_change: l2_distance / base_norm,
+            cosine_similarity: dot / (base_norm * f64::sqrt(other_norm_sq)),
+        })
+    }
+}
+
+// networks are compatible if they have the same layers with the same shapes, so their
+// parameters correspond to each other
+fn get_incompatibility(base: &NetworkSummary, other: &NetworkSummary) -> Option<String> {
+    if base.input_shape != other.input_shape {
+        return Some(format!(
+            "input shapes differ: {:?} vs {:?}",
+            base.input_shape, other.input_shape
+        ));
+    }
+
+    if base.layers.len() != other.layers.len() {
+        return Some(format!(
+            "layers counts differ: {} vs {}",
+            base.layers.len(),
+            other.layers.len()
+        ));
+    }
+
+    for (idx, (base, other)) in base.layers.iter().zip(&other.layers).enumerate() {
+        let describe = |layer: &LayerSummary| {
+            format!(
+                "{} {:?} ({} params)",
+                layer.name(),
+                layer.output_shape,
+                layer.parameters_count
+            )
+        };
+
+        if base.tag != other.tag
+            || base.activation != other.activation
+            || base.output_shape != other.output_shape
+            || base.parameters_count != other.parameters_count
+        {
+            return Some(format!(
+                "layer {idx} differs: {} vs {}",
+                describe(base),
+                describe(other)
+            ));
+        }
+    }
+
+    None
+}
+
+impl OutputAgreement {
+    fn new(
+        base: &Network,
+        other: &Network,
+        samples: impl IntoIterator<Item = (Vec<f64>, usize)>,
+    ) -> Result<Self, NetworkError> {
+        let classes_count = base.output_shape().iter().product();
+        let other_classes_count = other.output_shape().iter().product();
+
+        if base.input_shape() != other.input_shape() || classes_count != other_classes_count {
+            return Err(NetworkError::ShapeMismatch {
+                expected: vec![base.input_shape().iter().product(), classes_count],
+                actual: vec![other.input_shape().iter().product(), other_classes_count],
+            });
+        }
+
+        let mut classes = vec![ClassAgreement::default(); classes_count];
+
+        for (inputs, label) in samples {
+            if label >= classes_count {
+                return Err(NetworkError::InvalidLabel {
+                    label,
+                    classes_count,
+                });
+            }
+
+            let base_prediction = predict(&base.forward(&inputs));
+            let other_prediction = predict(&other.forward(&inputs));
+
+            let class = &mut classes[label];
+            class.samples_count += 1;
+            class.agreed_count += (base_prediction == other_prediction) as usize;
+            class.base_correct_count += (base_prediction == label) as usize;
+            class.other_correct_count += (other_prediction == label) as usize;
+        }
+
+        Ok(OutputAgreement { classes })
+    }
+
+    // all classes together
+    pub fn total(&self) -> ClassAgreement {
+        self.classes
+            .iter()
+            .fold(ClassAgreement::default(), |total, class| ClassAgreement {
+                samples_count: total.samples_count + class.samples_count,
+                agreed_count: total.agreed_count + class.agreed_count,
+                base_correct_count: total.base_correct_count + class.base_correct_count,
+                other_correct_count: total.other_correct_count + class.other_correct_count,
+            })
+    }
+}
+
+impl ClassAgreement {
+    // fraction of samples for which both networks predicted the same class, NaN if there are
+    // no samples
+    pub fn agreement(&self) -> f64 {
+        self.agreed_count as f64 / self.samples_count as f64
+    }
+
+    pub fn base_accuracy(&self) -> f64 {
+        self.base_correct_count as f64 / self.samples_count as f64
+    }
+
+    pub fn other_accuracy(&self) -> f64 {
+        self.other_correct_count as f64 / self.samples_count as f64
+    }
+}
+
+// index of the max output
+fn predict(outputs: &[BVal]) -> usize {
+    let mut max_idx = 0;
+    let mut max_out = f64::NEG_INFINITY;
+
+    for (idx, out) in outputs.iter().enumerate() {
+        let out = out.borrow().d;
+
+        if out > max_out {
+            max_idx = idx;
+            max_out = out;
+        }
+    }
+
+    max_idx
+}
+
+impl fmt::Display for NetworkDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(incompatibility) = &self.incompatibility {
+            return write!(f, "incompatible architectures: {incompatibility}");
+        }
+
+        writeln!(
+            f,
+            "{:>3}  {:<20} {:>10}  {:>12} {:>12} {:>12}",
+            "#", "layer", "params", "l2 distance", "rel change", "cosine"
+        )?;
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            write!(
+                f,
+                "{:>3}  {:<20} {:>10}",
+                idx, layer.name, layer.parameters_count
+            )?;
+
+            if let Some(diff) = &layer.parameters_diff {
+                write!(f, "  {diff}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        match &self.total {
+            Some(diff) => write!(f, "{:<36}  {diff}", "total"),
+            None => write!(f, "no parameters"),
+        }
+    }
+}
+
+impl fmt::Display for ParametersDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>12.6} {:>11.4}% {:>12.6}",
+            self.l2_distance,
+            self.relative_change * 100.0,
+            self.cosine_similarity
+        )
+    }
+}
+
+impl fmt::Display for OutputAgreement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>5}  {:>8} {:>10} {:>10} {:>10}",
+            "class", "samples", "agreement", "base acc", "other acc"
+        )?;
+
+        for (label, class) in self.classes.iter().enumerate() {
+            writeln!(f, "{label:>5}  {class}")?;
+        }
+
+        write!(f, "{:>5}  {}", "total", self.total())
+    }
+}
+
+impl fmt::Display for ClassAgreement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>8} {:>9.2}% {:>9.2}% {:>9.2}%",
+            self.samples_count,
+            self.agreement() * 100.0,
+            self.base_accuracy() * 100.0,
+            self.other_accuracy() * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layer::{activation::Activation, dense::Dense, Layer};
+
+    use super::*;
+
+    fn set_params(net: &Network, values: &[f64]) {
+        for (param, d) in net.parameters().iter().zip(values) {
+            param.borrow_mut().d = *d;
+        }
+    }
+
+    #[test]
+    fn diff() {
+        let base = Network::new(vec![2, 1, 1]);
+        let other = Network::new(vec![2, 1, 1]);
+
+        set_params(&base, &[1.0, 0.0, 0.0, 2.0, 0.0]);
+        set_params(&other, &[0.0, 1.0, 0.0, 2.0, 0.0]);
+
+        let diff = base.diff(&other);
+
+        assert!(diff.is_compatible());
+        assert_eq!(diff.layers.len(), 4);
+        assert_eq!(diff.layers[0].tag, "dense");
+        assert_eq!(diff.layers[1].name, "activation (tanh)");
+        assert_eq!(diff.layers[1].parameters_diff, None);
+
+        // orthogonal weights of the same norm
+        let first = diff.layers[0].parameters_diff.unwrap();
+        assert!((first.l2_distance - 2.0_f64.sqrt()).abs() < 1e-12);
+        assert!((first.relative_change - 2.0_f64.sqrt()).abs() < 1e-12);
+        assert_eq!(first.cosine_similarity, 0.0);
+
+        // unchanged layer
+        let second = diff.layers[2].parameters_diff.unwrap();
+        assert_eq!(second.l2_distance, 0.0);
+        assert_eq!(second.relative_change, 0.0);
+        assert_eq!(second.cosine_similarity, 1.0);
+
+        let total = diff.total.unwrap();
+        assert!((total.l2_distance - 2.0_f64.sqrt()).abs() < 1e-12);
+        assert!((total.relative_change - 2.0_f64.sqrt() / 5.0_f64.sqrt()).abs() < 1e-12);
+        assert!((total.cosine_similarity - 0.8).abs() < 1e-12);
+
+        let self_diff = base.diff(&base).total.unwrap();
+        assert_eq!(self_diff.l2_distance, 0.0);
+        assert!((self_diff.cosine_similarity - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn diff_incompatible() {
+        let base = Network::new(vec![2, 3, 1]);
+
+        let cases: Vec<(Network, &str)> = vec![
+            (
+                Network::new(vec![3, 3, 1]),
+                "input shapes differ: [2] vs [3]",
+            ),
+            (
+                Network::new(vec![2, 3, 2, 1]),
+                "layers counts differ: 4 vs 6",
+            ),
+            (
+                Network::new(vec![2, 4, 1]),
+                "layer 0 differs: dense [3] (9 params) vs dense [4] (12 params)",
+            ),
+            (
+                Network::from_layers(
+                    vec![2],
+                    vec![
+                        Box::new(Dense::new(2, 3)) as Box<dyn Layer>,
+                        Box::new(Activation::relu()),
+                        Box::new(Dense::new(3, 1)),
+                        Box::new(Activation::tanh()),
+                    ],
+                )
+                .unwrap(),
+                "layer 1 differs: activation (tanh) [3] (0 params) \
+                vs activation (relu) [3] (0 params)",
+            ),
+        ];
+
+        for (other, expected) in cases {
+            let diff = base.diff(&other);
+
+            assert!(!diff.is_compatible());
+            assert_eq!(diff.incompatibility.as_deref(), Some(expected));
+            assert!(diff.layers.is_empty());
+            assert_eq!(diff.total, None);
+            assert_eq!(
+                diff.to_string(),
+                format!("incompatible architectures: {expected}")
+            );
+        }
+    }
+
+    #[test]
+    fn agreement() {
+        // the first network always predicts class 0, the second one predicts class of the
+        // bigger input
+        let base = Network::new(vec![2, 2]);
+        let other = Network::new(vec![2, 2]);
+
+        set_params(&base, &[0.0, 0.0, 1.0, 0.0, 0.0, 0.0]);
+        set_params(&other, &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+
+        let samples = vec![
+            (vec![1.0, 0.0], 0),
+            (vec![1.0, 0.0], 1),
+            (vec![0.0, 1.0], 1),
+            (vec![0.0, 1.0], 1),
+        ];
+
+        let agreement = base.agreement(&other, samples).unwrap();
+
+        assert_eq!(
+            agreement.classes,
+            vec![
+                ClassAgreement {
+                    samples_count: 1,
+                    agreed_count: 1,
+                    base_correct_count: 1,
+                    other_correct_count: 1,
+                },
+                ClassAgreement {
+                    samples_count: 3,
+                    agreed_count: 1,
+                    base_correct_count: 0,
+                    other_correct_count: 2,
+                },
+            ]
+        );
+
+        let total = agreement.total();
+        assert_eq!(total.samples_count, 4);
+        assert_eq!(total.agreement(), 0.5);
+        assert_eq!(total.base_accuracy(), 0.25);
+        assert_eq!(total.other_accuracy(), 0.75);
+
+        assert!(matches!(
+            base.agreement(&Network::new(vec![2, 3]), Vec::new()),
+            Err(NetworkError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            base.agreement(&other, vec![(vec![1.0, 0.0], 2)]),
+            Err(NetworkError::InvalidLabel {
+                label: 2,
+                classes_count: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn display() {
+        let net = Network::new(vec![3, 2]);
+        let diff = net.diff(&net).to_string();
+        let lines: Vec<&str> = diff.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("l2 distance"));
+        assert!(lines[1].contains("dense"));
+        assert!(lines[2].contains("activation (tanh)"));
+        assert!(lines[3].starts_with("total"));
+        assert!(lines[3].contains("0.0000%"));
+    }
+}
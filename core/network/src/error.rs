@@ -0,0 +1,127 @@
+use std::{error::Error, fmt, io};
+
+#[derive(Debug)]
+pub enum NetworkError {
+    Io(io::Error),
+    // model data doesn't start with known file header
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u32),
+    // layers sizes read from model data can't describe valid network
+    InvalidShape(Vec<usize>),
+    ShapeMismatch {
+        expected: Vec<usize>,
+        actual: Vec<usize>,
+    },
+    UnexpectedEof,
+    TrailingBytes,
+    InvalidNpz(String),
+    // model data contains layer of unknown type
+    UnknownLayer(String),
+    // layer config read from model data is corrupted
+    InvalidLayer(String),
+    // layer can't take outputs of previous layer as inputs
+    LayerShapeMismatch {
+        layer: String,
+        input_shape: Vec<usize>,
+    },
+    // layer type is not supported by export format or inference model
+    UnsupportedLayer(String),
+    // checkpoint contains learning rate scheduler of unknown type
+    UnknownScheduler(String),
+    // scheduler state read from checkpoint is corrupted
+    InvalidScheduler(String),
+    // model registry has no model with given id
+    ModelNotFound(String),
+    // manifest of model registry can't be parsed
+    InvalidManifest(String),
+    // metric value can't be stored in manifest of model registry, e.g. nan loss of diverged run
+    InvalidMetric(String),
+    // model name can't be used as part of file name in model registry
+    InvalidModelName(String),
+    // quantized model data is corrupted
+    InvalidQuantizedModel(String),
+    // checkpoint contains optimizer of unknown type
+    UnknownOptimizer(String),
+    // optimizer state read from checkpoint is corrupted
+    InvalidOptimizer(String),
+    // training checkpoint doesn't match the trainer or network
+    InvalidCheckpoint(String),
+    // graph model refers to unknown nodes or has no inputs or outputs
+    InvalidGraph(String),
+    // json model can't be parsed
+    InvalidJson(String),
+    // sample label doesn't correspond to any of network outputs
+    InvalidLabel {
+        label: usize,
+        classes_count: usize,
+    },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Io(err) => write!(f, "i/o error: {err}"),
+            NetworkError::BadMagic(magic) => write!(f, "bad magic bytes: {magic:?}"),
+            NetworkError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version: {version}")
+            }
+            NetworkError::InvalidShape(layers_sizes) => {
+                write!(f, "invalid layers sizes: {layers_sizes:?}")
+            }
+            NetworkError::ShapeMismatch { expected, actual } => write!(
+                f,
+                "layers sizes mismatch: expected {expected:?}, actual {actual:?}"
+            ),
+            NetworkError::UnexpectedEof => write!(f, "unexpected end of model data"),
+            NetworkError::TrailingBytes => write!(f, "trailing bytes after model data"),
+            NetworkError::InvalidNpz(reason) => write!(f, "invalid npz: {reason}"),
+            NetworkError::UnknownLayer(tag) => write!(f, "unknown layer: {tag}"),
+            NetworkError::InvalidLayer(reason) => write!(f, "invalid layer: {reason}"),
+            NetworkError::LayerShapeMismatch { layer, input_shape } => write!(
+                f,
+                "layer {layer} can't take inputs of shape {input_shape:?}"
+            ),
+            NetworkError::UnsupportedLayer(tag) => write!(f, "unsupported layer: {tag}"),
+            NetworkError::UnknownScheduler(tag) => write!(f, "unknown scheduler: {tag}"),
+            NetworkError::InvalidScheduler(reason) => write!(f, "invalid scheduler: {reason}"),
+            NetworkError::ModelNotFound(id) => write!(f, "model not found: {id}"),
+            NetworkError::InvalidManifest(reason) => write!(f, "invalid manifest: {reason}"),
+            NetworkError::InvalidMetric(reason) => write!(f, "invalid metric: {reason}"),
+            NetworkError::InvalidModelName(name) => write!(f, "invalid model name: {name:?}"),
+            NetworkError::InvalidQuantizedModel(reason) => {
+                write!(f, "invalid quantized model: {reason}")
+            }
+            NetworkError::UnknownOptimizer(tag) => write!(f, "unknown optimizer: {tag}"),
+            NetworkError::InvalidOptimizer(reason) => write!(f, "invalid optimizer: {reason}"),
+            NetworkError::InvalidCheckpoint(reason) => write!(f, "invalid checkpoint: {reason}"),
+            NetworkError::InvalidGraph(reason) => write!(f, "invalid graph: {reason}"),
+            NetworkError::InvalidJson(reason) => write!(f, "invalid json: {reason}"),
+            NetworkError::InvalidLabel {
+                label,
+                classes_count,
+            } => write!(
+                f,
+                "label {label} is out of range of {classes_count} classes"
+            ),
+        }
+    }
+}
+
+impl Error for NetworkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NetworkError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetworkError {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            NetworkError::UnexpectedEof
+        } else {
+            NetworkError::Io(err)
+        }
+    }
+}
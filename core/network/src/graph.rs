@@ -0,0 +1,797 @@
+use std::io::{Read, Write};
+
+use autograd::val::BVal;
+
+use crate::{
+    error::NetworkError,
+    layer::{self, Layer},
+    utils,
+};
+
+// graph model file has its own magic bytes, since its topology can't be read as network
+const MAGIC: [u8; 4] = *b"NNGF";
+const FORMAT_VERSION: u32 = 1;
+
+// graph nodes count is never big in practice, so bigger one can only come from corrupted data
+const MAX_NODES_COUNT: u32 = 1 << 16;
+
+// node kinds in model file
+const INPUT_NODE: u32 = 0;
+const LAYER_NODE: u32 = 1;
+const ADD_NODE: u32 = 2;
+const CONCAT_NODE: u32 = 3;
+
+// handle of node added to graph builder, which is used to connect nodes with each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+enum NodeOp {
+    // takes values passed to forward()
+    Input,
+    Layer(Box<dyn Layer>),
+    // elementwise sum of inputs of the same shape, e.g. for residual connections
+    Add,
+    // concatenation of inputs along the first dimension
+    Concat,
+}
+
+struct Node {
+    op: NodeOp,
+    inputs: Vec<NodeId>,
+    output_shape: Vec<usize>,
+}
+
+// builds graph model from nodes, where each node takes outputs of previously added nodes as
+// inputs. nodes can only refer to existing nodes, so graph can't have cycles
+#[derive(Default)]
+pub struct GraphBuilder {
+    nodes: Vec<Node>,
+    inputs: Vec<NodeId>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        GraphBuilder::default()
+    }
+
+    // adds graph input, values for inputs are passed to forward() in order of adding them
+    pub fn input(&mut self, shape: Vec<usize>) -> Result<NodeId, NetworkError> {
+        if shape.is_empty() || shape.contains(&0) {
+            return Err(NetworkError::InvalidShape(shape));
+        }
+
+        let id = self.push(NodeOp::Input, Vec::new(), shape);
+        self.inputs.push(id);
+
+        Ok(id)
+    }
+
+    pub fn layer(&mut self, layer: Box<dyn Layer>, input: NodeId) -> Result<NodeId, NetworkError> {
+        let input_shape = self.node(input)?.output_shape.clone();
+
+        let output_shape =
+            layer
+                .output_shape(&input_shape)
+                .ok_or_else(|| NetworkError::LayerShapeMismatch {
+                    layer: String::from(layer.tag()),
+                    input_shape,
+                })?;
+
+        Ok(self.push(NodeOp::Layer(layer), vec![input], output_shape))
+    }
+
+    pub fn add(&mut self, inputs: &[NodeId]) -> Result<NodeId, NetworkError> {
+        let shape = self.inputs_shape(inputs)?;
+
+        for input in &inputs[1..] {
+            let input_shape = &self.node(*input)?.output_shape;
+
+            if *input_shape != shape {
+                return Err(NetworkError::ShapeMismatch {
+                    expected: shape,
+                    actual: input_shape.clone(),
+                });
+            }
+        }
+
+        Ok(self.push(NodeOp::Add, inputs.to_vec(), shape))
+    }
+
+    // inputs should have the same shape except for the first dimension, e.g. the same image size
+    // with different numbers of channels. values are stored flat with the first dimension being
+    // the outermost, so concatenation just appends values of inputs
+    pub fn concat(&mut self, inputs: &[NodeId]) -> Result<NodeId, NetworkError> {
+        let mut shape = self.inputs_shape(inputs)?;
+
+        for input in &inputs[1..] {
+            let input_shape = &self.node(*input)?.output_shape;
+
+            if input_shape.len() != shape.len() || input_shape[1..] != shape[1..] {
+                return Err(NetworkError::ShapeMismatch {
+                    expected: shape,
+                    actual: input_shape.clone(),
+                });
+            }
+
+            shape[0] += input_shape[0];
+        }
+
+        Ok(self.push(NodeOp::Concat, inputs.to_vec(), shape))
+    }
+
+    pub fn output_shape(&self, node: NodeId) -> Result<&[usize], NetworkError> {
+        Ok(&self.node(node)?.output_shape)
+    }
+
+    // outputs of forward() are values of given nodes, nodes which outputs don't depend on are
+    // never executed
+    pub fn build(self, outputs: &[NodeId]) -> Result<Graph, NetworkError> {
+        if self.inputs.is_empty() {
+            return Err(NetworkError::InvalidGraph(String::from(
+                "graph has no inputs",
+            )));
+        }
+
+        if outputs.is_empty() {
+            return Err(NetworkError::InvalidGraph(String::from(
+                "graph has no outputs",
+            )));
+        }
+
+        for output in outputs {
+            self.node(*output)?;
+        }
+
+        let order = get_execution_order(&self.nodes, outputs);
+
+        let mut parameters = Vec::new();
+        for id in &order {
+            if let NodeOp::Layer(layer) = &self.nodes[id.0].op {
+                parameters.extend(layer.parameters());
+            }
+        }
+
+        let mut graph = Graph {
+            nodes: self.nodes,
+            inputs: self.inputs,
+            outputs: outputs.to_vec(),
+            order,
+            parameters,
+            training: false,
+        };
+
+        // graph starts in eval mode like network
+        graph.eval();
+
+        Ok(graph)
+    }
+
+    fn node(&self, id: NodeId) -> Result<&Node, NetworkError> {
+        self.nodes
+            .get(id.0)
+            .ok_or_else(|| NetworkError::InvalidGraph(format!("unknown node: {}", id.0)))
+    }
+
+    // shape of the first input, checking that there is one
+    fn inputs_shape(&self, inputs: &[NodeId]) -> Result<Vec<usize>, NetworkError> {
+        let first = inputs
+            .first()
+            .ok_or_else(|| NetworkError::InvalidGraph(String::from("node has no inputs")))?;
+
+        Ok(self.node(*first)?.output_shape.clone())
+    }
+
+    fn push(&mut self, op: NodeOp, inputs: Vec<NodeId>, output_shape: Vec<usize>) -> NodeId {
+        self.nodes.push(Node {
+            op,
+            inputs,
+            output_shape,
+        });
+
+        NodeId(self.nodes.len() - 1)
+    }
+}
+
+// model where layers are nodes of directed acyclic graph, so it can have skip connections,
+// several inputs and several outputs
+pub struct Graph {
+    nodes: Vec<Node>,
+    inputs: Vec<NodeId>,
+    outputs: Vec<NodeId>,
+    // nodes needed for outputs in topological order, ie. each node goes after its inputs
+    order: Vec<NodeId>,
+    parameters: Vec<BVal>,
+    training: bool,
+}
+
+impl Graph {
+    // takes values of each graph input, returns values of each graph output
+    pub fn forward(&self, inputs: &[Vec<f64>]) -> Vec<Vec<BVal>> {
+        let inputs = inputs
+            .iter()
+            .map(|input| vec![input.iter().map(|d| BVal::new(*d)).collect()])
+            .collect();
+
+        self.execute(inputs, |layer, inputs| {
+            inputs
+                .into_iter()
+                .map(|inputs| layer.forward(inputs))
+                .collect()
+        })
+        .into_iter()
+        .map(|mut output| output.pop().unwrap())
+        .collect()
+    }
+
+    // takes values of each graph input for each sample, returns values of each graph output for
+    // each sample
+    pub fn forward_batch(&self, inputs: &[Vec<Vec<f64>>]) -> Vec<Vec<Vec<BVal>>> {
+        // batch of values for each graph input
+        let mut batches = vec![Vec::new(); self.inputs.len()];
+
+        for sample in inputs {
+            assert_eq!(sample.len(), self.inputs.len(), "invalid inputs count");
+
+            for (batch, input) in batches.iter_mut().zip(sample) {
+                batch.push(input.iter().map(|d| BVal::new(*d)).collect());
+            }
+        }
+
+        let outputs = self.execute(batches, |layer, inputs| layer.forward_batch(inputs));
+
+        (0..inputs.len())
+            .map(|idx| outputs.iter().map(|batch| batch[idx].clone()).collect())
+            .collect()
+    }
+
+    // runs nodes in topological order, where each value is batch of samples
+    fn execute(
+        &self,
+        inputs: Vec<Vec<Vec<BVal>>>,
+        forward_layer: impl Fn(&dyn Layer, Vec<Vec<BVal>>) -> Vec<Vec<BVal>>,
+    ) -> Vec<Vec<Vec<BVal>>> {
+        assert_eq!(inputs.len(), self.inputs.len(), "invalid inputs count");
+
+        let mut values: Vec<Option<Vec<Vec<BVal>>>> = vec![None; self.nodes.len()];
+
+        for (id, input) in self.inputs.iter().zip(inputs) {
+            values[id.0] = Some(input);
+        }
+
+        for id in &self.order {
+            let node = &self.nodes[id.0];
+
+            let node_inputs: Vec<&Vec<Vec<BVal>>> = node
+                .inputs
+                .iter()
+                .map(|input| {
+                    values[input.0]
+                        .as_ref()
+                        .expect("node inputs go before the node")
+                })
+                .collect();
+
+            let outputs = match &node.op {
+                NodeOp::Input => continue,
+                NodeOp::Layer(layer) => forward_layer(layer.as_ref(), node_inputs[0].clone()),
+                NodeOp::Add => (0..node_inputs[0].len())
+                    .map(|sample_idx| {
+                        let mut sum = node_inputs[0][sample_idx].clone();
+
+                        for input in &node_inputs[1..] {
+                            for (acc, val) in sum.iter_mut().zip(&input[sample_idx]) {
+                                *acc = &*acc + val;
+                            }
+                        }
+
+                        sum
+                    })
+                    .collect(),
+                NodeOp::Concat => (0..node_inputs[0].len())
+                    .map(|sample_idx| {
+                        node_inputs
+                            .iter()
+                            .flat_map(|input| input[sample_idx].iter().cloned())
+                            .collect()
+                    })
+                    .collect(),
+            };
+
+            values[id.0] = Some(outputs);
+        }
+
+        self.outputs
+            .iter()
+            .map(|id| values[id.0].clone().expect("outputs are executed"))
+            .collect()
+    }
+
+    pub fn parameters(&self) -> &Vec<BVal> {
+        &self.parameters
+    }
+
+    pub fn train(&mut self) {
+        self.set_training(true);
+    }
+
+    pub fn eval(&mut self) {
+        self.set_training(false);
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+
+        for node in &mut self.nodes {
+            if let NodeOp::Layer(layer) = &mut node.op {
+                layer.set_training(training);
+            }
+        }
+    }
+
+    pub fn reset_grad(&self) {
+        for param in self.parameters() {
+            param.borrow_mut().grad = 0.0;
+        }
+    }
+
+    pub fn input_shapes(&self) -> Vec<Vec<usize>> {
+        self.node_shapes(&self.inputs)
+    }
+
+    pub fn output_shapes(&self) -> Vec<Vec<usize>> {
+        self.node_shapes(&self.outputs)
+    }
+
+    fn node_shapes(&self, ids: &[NodeId]) -> Vec<Vec<usize>> {
+        ids.iter()
+            .map(|id| self.nodes[id.0].output_shape.clone())
+            .collect()
+    }
+
+    // writes all nodes in order of adding them, so reading them replays graph builder calls
+    pub fn serialize_to_writer(&self, mut writer: impl Write) -> Result<(), NetworkError> {
+        writer.write_all(&MAGIC)?;
+        utils::write_u32(&mut writer, FORMAT_VERSION)?;
+
+        utils::write_u32(&mut writer, self.nodes.len() as u32)?;
+
+        for node in &self.nodes {
+            let kind = match node.op {
+                NodeOp::Input => INPUT_NODE,
+                NodeOp::Layer(_) => LAYER_NODE,
+                NodeOp::Add => ADD_NODE,
+                NodeOp::Concat => CONCAT_NODE,
+            };
+
+            utils::write_u32(&mut writer, kind)?;
+            write_node_ids(&mut writer, &node.inputs)?;
+
+            match &node.op {
+                NodeOp::Input => {
+                    utils::write_u32(&mut writer, node.output_shape.len() as u32)?;
+
+                    for size in &node.output_shape {
+                        utils::write_u32(&mut writer, *size as u32)?;
+                    }
+                }
+                NodeOp::Layer(layer) => layer::serialize_layer(layer.as_ref(), &mut writer)?,
+                NodeOp::Add | NodeOp::Concat => {}
+            }
+        }
+
+        write_node_ids(&mut writer, &self.outputs)?;
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn deserialize_from_reader(mut reader: impl Read) -> Result<Self, NetworkError> {
+        let magic = utils::read_magic(&mut reader)?;
+
+        if magic != MAGIC {
+            return Err(NetworkError::BadMagic(magic));
+        }
+
+        let version = utils::read_u32(&mut reader)?;
+
+        if version != FORMAT_VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+
+        let nodes_count = utils::read_u32(&mut reader)?;
+
+        if nodes_count > MAX_NODES_COUNT {
+            return Err(NetworkError::InvalidGraph(format!(
+                "too many nodes: {nodes_count}"
+            )));
+        }
+
+        let mut builder = GraphBuilder::new();
+
+        for _ in 0..nodes_count {
+            let kind = utils::read_u32(&mut reader)?;
+            let inputs = read_node_ids(&mut reader)?;
+
+            match kind {
+                INPUT_NODE if inputs.is_empty() => {
+                    let dims = utils::read_u32(&mut reader)?;
+
+                    let mut shape = Vec::new();
+                    for _ in 0..dims {
+                        shape.push(utils::read_u32(&mut reader)? as usize);
+                    }
+
+                    builder.input(shape)?;
+                }
+                LAYER_NODE if inputs.len() == 1 => {
+                    let layer = layer::deserialize_layer(&mut reader)?;
+                    builder.layer(layer, inputs[0])?;
+                }
+                ADD_NODE => {
+                    builder.add(&inputs)?;
+                }
+                CONCAT_NODE => {
+                    builder.concat(&inputs)?;
+                }
+                _ => {
+                    return Err(NetworkError::InvalidGraph(format!(
+                        "invalid node kind {kind} with {} inputs",
+                        inputs.len()
+                    )))
+                }
+            }
+        }
+
+        let outputs = read_node_ids(&mut reader)?;
+
+        utils::ensure_eof(&mut reader)?;
+
+        builder.build(&outputs)
+    }
+}
+
+fn write_node_ids(writer: &mut dyn Write, ids: &[NodeId]) -> Result<(), NetworkError> {
+    utils::write_u32(writer, ids.len() as u32)?;
+
+    for id in ids {
+        utils::write_u32(writer, id.0 as u32)?;
+    }
+
+    Ok(())
+}
+
+// ids are checked by graph builder
+fn read_node_ids(reader: &mut dyn Read) -> Result<Vec<NodeId>, NetworkError> {
+    let count = utils::read_u32(reader)?;
+
+    let mut ids = Vec::new();
+    for _ in 0..count {
+        ids.push(NodeId(utils::read_u32(reader)? as usize));
+    }
+
+    Ok(ids)
+}
+
+// depth-first search from outputs, where each node is added after all its inputs
+fn get_execution_order(nodes: &[Node], outputs: &[NodeId]) -> Vec<NodeId> {
+    let mut order = Vec::new();
+    let mut visited = vec![false; nodes.len()];
+
+    for output in outputs {
+        // stack of nodes with flag whether their inputs are already visited
+        let mut stack = vec![(*output, false)];
+
+        while let Some((id, inputs_visited)) = stack.pop() {
+            if inputs_visited {
+                order.push(id);
+                continue;
+            }
+
+            if visited[id.0] {
+                continue;
+            }
+
+            visited[id.0] = true;
+            stack.push((id, true));
+
+            for input in nodes[id.0].inputs.iter().rev() {
+                if !visited[input.0] {
+                    stack.push((*input, false));
+                }
+            }
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layer::{activation::Activation, dense::Dense, dropout::Dropout, reshape::Reshape};
+
+    use super::*;
+
+    fn values(outputs: &[BVal]) -> Vec<f64> {
+        outputs.iter().map(|v| v.borrow().d).collect()
+    }
+
+    fn serialize_to_bytes(graph: &Graph) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        graph
+            .serialize_to_writer(&mut bytes)
+            .expect("failed to serialize graph");
+        bytes
+    }
+
+    // two inputs, residual block over the first one, concatenation with the second one and two
+    // heads
+    fn create_graph() -> Graph {
+        let mut b = GraphBuilder::new();
+
+        let x = b.input(vec![3]).unwrap();
+        let y = b.input(vec![2]).unwrap();
+
+        let h = b.layer(Box::new(Dense::new(3, 3)), x).unwrap();
+        let h = b.layer(Box::new(Activation::relu()), h).unwrap();
+        let h = b.add(&[x, h]).unwrap();
+
+        let c = b.concat(&[h, y]).unwrap();
+
+        let out1 = b.layer(Box::new(Dense::new(5, 1)), c).unwrap();
+        let out1 = b.layer(Box::new(Activation::tanh()), out1).unwrap();
+        let out2 = b.layer(Box::new(Dense::new(5, 2)), c).unwrap();
+
+        b.build(&[out1, out2]).unwrap()
+    }
+
+    #[test]
+    fn residual() {
+        let dense = Dense::new(3, 3);
+
+        let input = vec![1.0, -2.0, 0.5];
+        let dense_outputs = values(&dense.forward(input.iter().map(|d| BVal::new(*d)).collect()));
+
+        let mut b = GraphBuilder::new();
+        let x = b.input(vec![3]).unwrap();
+        let h = b.layer(Box::new(dense), x).unwrap();
+        let out = b.add(&[x, h]).unwrap();
+        let graph = b.build(&[out]).unwrap();
+
+        assert_eq!(graph.parameters().len(), 12);
+
+        let outputs = graph.forward(&[input.clone()]);
+
+        assert_eq!(outputs.len(), 1);
+
+        for ((out, x), h) in values(&outputs[0]).iter().zip(&input).zip(dense_outputs) {
+            assert_eq!(*out, x + h);
+        }
+    }
+
+    #[test]
+    fn concat() {
+        let mut b = GraphBuilder::new();
+        let x = b.input(vec![2]).unwrap();
+        let y = b.input(vec![4]).unwrap();
+        let y = b.layer(Box::new(Reshape::new(vec![2, 2])), y).unwrap();
+
+        // shapes differ in more than the first dimension
+        assert!(matches!(
+            b.concat(&[x, y]),
+            Err(NetworkError::ShapeMismatch { .. })
+        ));
+
+        let x = b.layer(Box::new(Reshape::new(vec![1, 2])), x).unwrap();
+        let c = b.concat(&[y, x, y]).unwrap();
+
+        assert_eq!(b.output_shape(c).unwrap(), &[5, 2]);
+
+        let graph = b.build(&[c]).unwrap();
+        let outputs = graph.forward(&[vec![1.0, 2.0], vec![3.0, 4.0, 5.0, 6.0]]);
+
+        assert_eq!(
+            values(&outputs[0]),
+            vec![3.0, 4.0, 5.0, 6.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+        );
+    }
+
+    #[test]
+    fn multiple_inputs_and_outputs() {
+        let graph = create_graph();
+
+        assert_eq!(graph.input_shapes(), vec![vec![3], vec![2]]);
+        assert_eq!(graph.output_shapes(), vec![vec![1], vec![2]]);
+        assert_eq!(graph.parameters().len(), 12 + 6 + 12);
+
+        let inputs = vec![vec![1.0, -2.0, 0.5], vec![0.3, 0.7]];
+        let outputs = graph.forward(&inputs);
+
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].len(), 1);
+        assert_eq!(outputs[1].len(), 2);
+
+        // batch gives the same outputs for each sample
+        let batch = vec![inputs.clone(), vec![vec![0.0, 1.0, 2.0], vec![-1.0, 1.0]]];
+        let batch_outputs = graph.forward_batch(&batch);
+
+        assert_eq!(batch_outputs.len(), 2);
+
+        for (sample, sample_outputs) in batch.iter().zip(&batch_outputs) {
+            let expected = graph.forward(sample);
+
+            for (output, expected) in sample_outputs.iter().zip(&expected) {
+                assert_eq!(values(output), values(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn unused_nodes() {
+        let mut b = GraphBuilder::new();
+        let x = b.input(vec![2]).unwrap();
+        let unused = b.layer(Box::new(Dense::new(2, 4)), x).unwrap();
+        let out = b.layer(Box::new(Dense::new(2, 1)), x).unwrap();
+
+        let graph = b.build(&[out]).unwrap();
+
+        // parameters of nodes which outputs don't depend on are not trained
+        assert_eq!(graph.parameters().len(), 3);
+        assert_ne!(unused, out);
+    }
+
+    #[test]
+    fn training() {
+        let samples: Vec<(Vec<f64>, f64)> = vec![
+            (vec![2.0, 3.0, -1.0], 1.0),
+            (vec![3.0, -1.0, 0.5], -1.0),
+            (vec![0.5, 1.0, 1.0], -1.0),
+            (vec![1.0, 1.0, -1.0], 1.0),
+        ];
+
+        let mut b = GraphBuilder::new();
+        let x = b.input(vec![3]).unwrap();
+        let h = b.layer(Box::new(Dense::new(3, 3)), x).unwrap();
+        let h = b.layer(Box::new(Activation::tanh()), h).unwrap();
+        let h = b.add(&[x, h]).unwrap();
+        let out = b.layer(Box::new(Dense::new(3, 1)), h).unwrap();
+        let out = b.layer(Box::new(Activation::tanh()), out).unwrap();
+        let graph = b.build(&[out]).unwrap();
+
+        let mut last_total_loss = BVal::new(0.0);
+
+        for _ in 0..100 {
+            let mut total_loss = BVal::new(0.0);
+            for (input, expected) in &samples {
+                let output = &graph.forward(&[input.clone()])[0][0];
+                let loss = (*expected - output).pow(2.0);
+                total_loss = &total_loss + &loss;
+            }
+            last_total_loss = total_loss.clone();
+
+            graph.reset_grad();
+
+            total_loss.borrow_mut().grad = 1.0;
+            total_loss.backward();
+
+            for param in graph.parameters() {
+                let grad = param.borrow().grad;
+                param.borrow_mut().d -= 0.05 * grad;
+            }
+        }
+
+        assert!(last_total_loss.borrow().d < 0.1);
+    }
+
+    #[test]
+    fn train_eval() {
+        let mut b = GraphBuilder::new();
+        let x = b.input(vec![100]).unwrap();
+        let d = b.layer(Box::new(Dropout::with_seed(0.5, 42)), x).unwrap();
+        let out = b.add(&[x, d]).unwrap();
+        let mut graph = b.build(&[out]).unwrap();
+
+        let input: Vec<f64> = (0..100).map(|n| n as f64).collect();
+        let doubled: Vec<f64> = input.iter().map(|d| d * 2.0).collect();
+
+        assert!(!graph.is_training());
+        assert_eq!(values(&graph.forward(&[input.clone()])[0]), doubled);
+
+        graph.train();
+        assert_ne!(values(&graph.forward(&[input.clone()])[0]), doubled);
+
+        graph.eval();
+        assert_eq!(values(&graph.forward(&[input.clone()])[0]), doubled);
+    }
+
+    #[test]
+    fn serialization() {
+        let graph1 = create_graph();
+        let bytes = serialize_to_bytes(&graph1);
+
+        let graph2 =
+            Graph::deserialize_from_reader(bytes.as_slice()).expect("failed to deserialize graph");
+
+        assert_eq!(graph2.input_shapes(), graph1.input_shapes());
+        assert_eq!(graph2.output_shapes(), graph1.output_shapes());
+
+        let inputs = vec![vec![1.0, -2.0, 0.5], vec![0.3, 0.7]];
+
+        for (out1, out2) in graph1.forward(&inputs).iter().zip(graph2.forward(&inputs)) {
+            assert_eq!(values(out1), values(&out2));
+        }
+
+        // trailing and missing bytes
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(matches!(
+            Graph::deserialize_from_reader(extended.as_slice()),
+            Err(NetworkError::TrailingBytes)
+        ));
+        assert!(matches!(
+            Graph::deserialize_from_reader(&bytes[..bytes.len() - 1]),
+            Err(NetworkError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn deserialization_unknown_node() {
+        // input node followed by add node which refers to node 5
+        let mut bytes = MAGIC.to_vec();
+        for n in [
+            FORMAT_VERSION,
+            2,
+            INPUT_NODE,
+            0,
+            1,
+            3,
+            ADD_NODE,
+            2,
+            0,
+            5,
+            1,
+            1,
+        ] {
+            bytes.extend(n.to_ne_bytes());
+        }
+
+        let res = Graph::deserialize_from_reader(bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::InvalidGraph(_))));
+    }
+
+    #[test]
+    fn builder_errors() {
+        let mut b = GraphBuilder::new();
+
+        assert!(matches!(
+            b.input(vec![2, 0]),
+            Err(NetworkError::InvalidShape(_))
+        ));
+
+        let x = b.input(vec![3]).unwrap();
+        let y = b.input(vec![2]).unwrap();
+
+        assert!(matches!(
+            b.layer(Box::new(Dense::new(2, 2)), x),
+            Err(NetworkError::LayerShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            b.add(&[x, y]),
+            Err(NetworkError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(b.add(&[]), Err(NetworkError::InvalidGraph(_))));
+        assert!(matches!(
+            b.add(&[x, NodeId(10)]),
+            Err(NetworkError::InvalidGraph(_))
+        ));
+        assert!(matches!(b.build(&[]), Err(NetworkError::InvalidGraph(_))));
+        assert!(matches!(
+            GraphBuilder::new().build(&[x]),
+            Err(NetworkError::InvalidGraph(_))
+        ));
+    }
+}
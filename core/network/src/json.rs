@@ -0,0 +1,767 @@
+use std::io::Read;
+
+use autograd::val::BVal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::NetworkError,
+    layer::{
+        activation::{Activation, ActivationKind},
+        avg_pool2d::AvgPool2D,
+        batch_norm1d::BatchNorm1D,
+        conv2d::Conv2D,
+        dense::Dense,
+        dropout::Dropout,
+        flatten::Flatten,
+        gru::Gru,
+        lstm::Lstm,
+        max_pool2d::MaxPool2D,
+        recurrent::RecurrentConfig,
+        reshape::Reshape,
+        rnn::Rnn,
+        Layer,
+    },
+    network::Network,
+    neuron::Neuron,
+    utils,
+};
+
+const JSON_FORMAT_VERSION: u32 = 1;
+
+// text counterpart of model file, which can be read and diffed by humans. floats are written in
+// shortest form which parses back to the same value, so export and import are lossless. json
+// can't represent NaN and infinity, so networks with such values aren't exported
+#[derive(Serialize, Deserialize)]
+struct NetworkJson {
+    version: u32,
+    input_shape: Vec<usize>,
+    layers: Vec<LayerJson>,
+    // indices of pruned parameters (see Network::parameters()), only written for pruned networks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pruned: Option<Vec<usize>>,
+}
+
+// layer type is stored under the same tag as in model file
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum LayerJson {
+    #[serde(rename = "dense")]
+    Dense(NeuronsJson),
+    #[serde(rename = "activation")]
+    Activation { activation: String },
+    #[serde(rename = "dropout")]
+    Dropout { rate: f64 },
+    #[serde(rename = "flatten")]
+    Flatten,
+    #[serde(rename = "reshape")]
+    Reshape { shape: Vec<usize> },
+    #[serde(rename = "conv2d")]
+    Conv2D {
+        input_shape: [usize; 3],
+        kernel_size: usize,
+        stride: usize,
+        padding: usize,
+        // one row of [channels, kernel_size, kernel_size] weights per output channel
+        weights: Vec<Vec<f64>>,
+        biases: Vec<f64>,
+    },
+    #[serde(rename = "max_pool2d")]
+    MaxPool2D {
+        input_shape: [usize; 3],
+        kernel_size: usize,
+        stride: usize,
+    },
+    #[serde(rename = "avg_pool2d")]
+    AvgPool2D {
+        input_shape: [usize; 3],
+        kernel_size: usize,
+        stride: usize,
+    },
+    #[serde(rename = "batch_norm1d")]
+    BatchNorm1D {
+        momentum: f64,
+        epsilon: f64,
+        gamma: Vec<f64>,
+        beta: Vec<f64>,
+        running_mean: Vec<f64>,
+        running_var: Vec<f64>,
+    },
+    #[serde(rename = "rnn")]
+    Rnn(RecurrentJson),
+    #[serde(rename = "gru")]
+    Gru(RecurrentJson),
+    #[serde(rename = "lstm")]
+    Lstm(RecurrentJson),
+}
+
+// weights matrix with one row per neuron, and neuron biases
+#[derive(Serialize, Deserialize)]
+struct NeuronsJson {
+    weights: Vec<Vec<f64>>,
+    biases: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecurrentJson {
+    input_size: usize,
+    hidden_size: usize,
+    return_sequences: bool,
+    bptt_steps: Option<usize>,
+    // gates in the order of layer parameters, each one is dense layer from concatenated input
+    // vector and hidden state to hidden size
+    gates: Vec<NeuronsJson>,
+}
+
+impl Network {
+    pub fn to_json(&self) -> Result<String, NetworkError> {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer_to_json(layer.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let pruned: Vec<usize> = self
+            .pruning_mask()
+            .iter()
+            .enumerate()
+            .filter(|(_, pruned)| **pruned)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let net = NetworkJson {
+            version: JSON_FORMAT_VERSION,
+            input_shape: self.input_shape().to_vec(),
+            layers,
+            pruned: (!pruned.is_empty()).then_some(pruned),
+        };
+
+        serde_json::to_string_pretty(&net).map_err(|err| NetworkError::InvalidJson(err.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, NetworkError> {
+        let net: NetworkJson =
+            serde_json::from_str(json).map_err(|err| NetworkError::InvalidJson(err.to_string()))?;
+
+        if net.version != JSON_FORMAT_VERSION {
+            return Err(NetworkError::UnsupportedVersion(net.version));
+        }
+
+        let layers = net
+            .layers
+            .into_iter()
+            .map(layer_from_json)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut network = Network::from_layers(net.input_shape, layers)?;
+
+        if let Some(pruned) = net.pruned {
+            let mut mask = vec![false; network.parameters().len()];
+
+            for idx in pruned {
+                if idx >= mask.len() {
+                    return Err(NetworkError::InvalidJson(format!(
+                        "pruned parameter {idx} is out of {} parameters",
+                        mask.len()
+                    )));
+                }
+
+                mask[idx] = true;
+            }
+
+            network.set_pruning_mask(mask);
+            network.apply_pruning_mask();
+        }
+
+        Ok(network)
+    }
+}
+
+fn layer_to_json(layer: &dyn Layer) -> Result<LayerJson, NetworkError> {
+    let params: Vec<f64> = layer.parameters().iter().map(|p| p.borrow().d).collect();
+    ensure_finite(layer.tag(), &params)?;
+
+    let layer_any = layer.as_any();
+
+    let json = if let Some(dense) = layer_any.downcast_ref::<Dense>() {
+        LayerJson::Dense(neurons_to_json(&dense.neurons))
+    } else if let Some(activation) = layer_any.downcast_ref::<Activation>() {
+        LayerJson::Activation {
+            activation: String::from(activation.kind.name()),
+        }
+    } else if let Some(dropout) = layer_any.downcast_ref::<Dropout>() {
+        ensure_finite(Dropout::TAG, &[dropout.rate])?;
+
+        LayerJson::Dropout { rate: dropout.rate }
+    } else if layer_any.is::<Flatten>() {
+        LayerJson::Flatten
+    } else if let Some(reshape) = layer_any.downcast_ref::<Reshape>() {
+        LayerJson::Reshape {
+            shape: reshape.shape.clone(),
+        }
+    } else if let Some(conv) = layer_any.downcast_ref::<Conv2D>() {
+        let filters = neurons_to_json(&conv.filters);
+
+        LayerJson::Conv2D {
+            input_shape: conv.input_shape,
+            kernel_size: conv.kernel_size,
+            stride: conv.stride,
+            padding: conv.padding,
+            weights: filters.weights,
+            biases: filters.biases,
+        }
+    } else if let Some(pool) = layer_any.downcast_ref::<MaxPool2D>() {
+        LayerJson::MaxPool2D {
+            input_shape: pool.input_shape,
+            kernel_size: pool.kernel_size,
+            stride: pool.stride,
+        }
+    } else if let Some(pool) = layer_any.downcast_ref::<AvgPool2D>() {
+        LayerJson::AvgPool2D {
+            input_shape: pool.input_shape,
+            kernel_size: pool.kernel_size,
+            stride: pool.stride,
+        }
+    } else if let Some(norm) = layer_any.downcast_ref::<BatchNorm1D>() {
+        let get_values = |params: &[BVal]| params.iter().map(|p| p.borrow().d).collect();
+
+        ensure_finite(BatchNorm1D::TAG, &[norm.momentum, norm.epsilon])?;
+        ensure_finite(BatchNorm1D::TAG, &norm.running_mean.borrow())?;
+        ensure_finite(BatchNorm1D::TAG, &norm.running_var.borrow())?;
+
+        LayerJson::BatchNorm1D {
+            momentum: norm.momentum,
+            epsilon: norm.epsilon,
+            gamma: get_values(&norm.gamma),
+            beta: get_values(&norm.beta),
+            running_mean: norm.running_mean.borrow().clone(),
+            running_var: norm.running_var.borrow().clone(),
+        }
+    } else if let Some(rnn) = layer_any.downcast_ref::<Rnn>() {
+        LayerJson::Rnn(recurrent_to_json(rnn.config(), layer))
+    } else if let Some(gru) = layer_any.downcast_ref::<Gru>() {
+        LayerJson::Gru(recurrent_to_json(gru.config(), layer))
+    } else if let Some(lstm) = layer_any.downcast_ref::<Lstm>() {
+        LayerJson::Lstm(recurrent_to_json(lstm.config(), layer))
+    } else {
+        return Err(NetworkError::UnsupportedLayer(String::from(layer.tag())));
+    };
+
+    Ok(json)
+}
+
+// serde writes NaN and infinity as null, which can't be imported back
+fn ensure_finite(tag: &str, values: &[f64]) -> Result<(), NetworkError> {
+    match values.iter().find(|d| !d.is_finite()) {
+        Some(d) => Err(NetworkError::InvalidJson(format!(
+            "{tag} has {d} value, which json can't represent"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn neurons_to_json(neurons: &[Neuron]) -> NeuronsJson {
+    NeuronsJson {
+        weights: neurons
+            .iter()
+            .map(|n| n.weights.iter().map(|w| w.borrow().d).collect())
+            .collect(),
+        biases: neurons.iter().map(|n| n.bias.borrow().d).collect(),
+    }
+}
+
+// gates are private to recurrent layers, so they are restored from flat list of layer params,
+// which are laid out gate by gate and neuron by neuron (weights followed by bias)
+fn recurrent_to_json(config: &RecurrentConfig, layer: &dyn Layer) -> RecurrentJson {
+    let params: Vec<f64> = layer.parameters().iter().map(|p| p.borrow().d).collect();
+
+    let inputs_count = config.input_size + config.hidden_size;
+    let gate_size = (inputs_count + 1) * config.hidden_size;
+
+    let gates = params
+        .chunks(gate_size)
+        .map(|gate| NeuronsJson {
+            weights: gate
+                .chunks(inputs_count + 1)
+                .map(|neuron| neuron[..inputs_count].to_vec())
+                .collect(),
+            biases: gate
+                .chunks(inputs_count + 1)
+                .map(|neuron| neuron[inputs_count])
+                .collect(),
+        })
+        .collect();
+
+    RecurrentJson {
+        input_size: config.input_size,
+        hidden_size: config.hidden_size,
+        return_sequences: config.return_sequences,
+        bptt_steps: config.bptt_steps,
+        gates,
+    }
+}
+
+// layer is encoded the same way as in model file and read back by the layer itself, so json
+// import validates layer configs exactly like model file import does
+fn layer_from_json(json: LayerJson) -> Result<Box<dyn Layer>, NetworkError> {
+    let mut buf = Vec::new();
+
+    let layer: Box<dyn Layer> = match json {
+        LayerJson::Dense(neurons) => {
+            let inputs_count = neurons.weights.first().map_or(0, |row| row.len());
+
+            utils::write_u32(&mut buf, inputs_count as u32)?;
+            utils::write_u32(&mut buf, neurons.weights.len() as u32)?;
+            write_neurons(&mut buf, &neurons, inputs_count, Dense::TAG)?;
+
+            Box::new(read_layer(&buf, Dense::deserialize)?)
+        }
+        LayerJson::Activation { activation } => {
+            let kind = [
+                ActivationKind::Tanh,
+                ActivationKind::Relu,
+                ActivationKind::Sigmoid,
+            ]
+            .into_iter()
+            .find(|kind| kind.name() == activation)
+            .ok_or_else(|| {
+                NetworkError::InvalidLayer(format!("unknown activation: {activation}"))
+            })?;
+
+            Box::new(Activation::new(kind))
+        }
+        LayerJson::Dropout { rate } => {
+            utils::write_f64(&mut buf, rate)?;
+            Box::new(read_layer(&buf, Dropout::deserialize)?)
+        }
+        LayerJson::Flatten => Box::new(Flatten::new()),
+        LayerJson::Reshape { shape } => {
+            utils::write_u32(&mut buf, shape.len() as u32)?;
+            for size in shape {
+                utils::write_u32(&mut buf, size as u32)?;
+            }
+
+            Box::new(read_layer(&buf, Reshape::deserialize)?)
+        }
+        LayerJson::Conv2D {
+            input_shape,
+            kernel_size,
+            stride,
+            padding,
+            weights,
+            biases,
+        } => {
+            let [channels, height, width] = input_shape;
+            let out_channels = weights.len();
+
+            for n in [
+                channels,
+                height,
+                width,
+                out_channels,
+                kernel_size,
+                stride,
+                padding,
+            ] {
+                utils::write_u32(&mut buf, n as u32)?;
+            }
+
+            let inputs_count = channels.saturating_mul(kernel_size.saturating_mul(kernel_size));
+            let filters = NeuronsJson { weights, biases };
+            write_neurons(&mut buf, &filters, inputs_count, Conv2D::TAG)?;
+
+            Box::new(read_layer(&buf, Conv2D::deserialize)?)
+        }
+        LayerJson::MaxPool2D {
+            input_shape,
+            kernel_size,
+            stride,
+        } => {
+            write_pool_config(&mut buf, input_shape, kernel_size, stride)?;
+            Box::new(read_layer(&buf, MaxPool2D::deserialize)?)
+        }
+        LayerJson::AvgPool2D {
+            input_shape,
+            kernel_size,
+            stride,
+        } => {
+            write_pool_config(&mut buf, input_shape, kernel_size, stride)?;
+            Box::new(read_layer(&buf, AvgPool2D::deserialize)?)
+        }
+        LayerJson::BatchNorm1D {
+            momentum,
+            epsilon,
+            gamma,
+            beta,
+            running_mean,
+            running_var,
+        } => {
+            let features_count = gamma.len();
+
+            utils::write_u32(&mut buf, features_count as u32)?;
+            utils::write_f64(&mut buf, momentum)?;
+            utils::write_f64(&mut buf, epsilon)?;
+
+            for values in [gamma, beta, running_mean, running_var] {
+                if values.len() != features_count {
+                    return Err(NetworkError::InvalidLayer(format!(
+                        "invalid {}: expected {features_count} values per feature state, got {}",
+                        BatchNorm1D::TAG,
+                        values.len()
+                    )));
+                }
+
+                for d in values {
+                    utils::write_f64(&mut buf, d)?;
+                }
+            }
+
+            Box::new(read_layer(&buf, BatchNorm1D::deserialize)?)
+        }
+        LayerJson::Rnn(rnn) => {
+            write_recurrent(&mut buf, &rnn, 1, Rnn::TAG)?;
+            Box::new(read_layer(&buf, Rnn::deserialize)?)
+        }
+        LayerJson::Gru(gru) => {
+            write_recurrent(&mut buf, &gru, 3, Gru::TAG)?;
+            Box::new(read_layer(&buf, Gru::deserialize)?)
+        }
+        LayerJson::Lstm(lstm) => {
+            write_recurrent(&mut buf, &lstm, 4, Lstm::TAG)?;
+            Box::new(read_layer(&buf, Lstm::deserialize)?)
+        }
+    };
+
+    Ok(layer)
+}
+
+fn read_layer<T>(
+    buf: &[u8],
+    deserialize: impl FnOnce(&mut dyn Read) -> Result<T, NetworkError>,
+) -> Result<T, NetworkError> {
+    let mut reader = buf;
+    let layer = deserialize(&mut reader)?;
+    utils::ensure_eof(&mut reader)?;
+
+    Ok(layer)
+}
+
+// writes params of each neuron (weights followed by bias), checking that all neurons have
+// the same inputs count
+fn write_neurons(
+    buf: &mut Vec<u8>,
+    neurons: &NeuronsJson,
+    inputs_count: usize,
+    tag: &str,
+) -> Result<(), NetworkError> {
+    if neurons.biases.len() != neurons.weights.len() {
+        return Err(NetworkError::InvalidLayer(format!(
+            "invalid {tag}: {} weights rows, but {} biases",
+            neurons.weights.len(),
+            neurons.biases.len()
+        )));
+    }
+
+    for (weights, bias) in neurons.weights.iter().zip(&neurons.biases) {
+        if weights.len() != inputs_count {
+            return Err(NetworkError::InvalidLayer(format!(
+                "invalid {tag}: expected {inputs_count} weights per neuron, got {}",
+                weights.len()
+            )));
+        }
+
+        for w in weights {
+            utils::write_f64(buf, *w)?;
+        }
+        utils::write_f64(buf, *bias)?;
+    }
+
+    Ok(())
+}
+
+fn write_pool_config(
+    buf: &mut Vec<u8>,
+    input_shape: [usize; 3],
+    kernel_size: usize,
+    stride: usize,
+) -> Result<(), NetworkError> {
+    let [channels, height, width] = input_shape;
+
+    for n in [channels, height, width, kernel_size, stride] {
+        utils::write_u32(buf, n as u32)?;
+    }
+
+    Ok(())
+}
+
+fn write_recurrent(
+    buf: &mut Vec<u8>,
+    json: &RecurrentJson,
+    gates_count: usize,
+    tag: &str,
+) -> Result<(), NetworkError> {
+    // zero steps is written as no truncation in model file, so it's rejected explicitly
+    if json.gates.len() != gates_count || json.bptt_steps == Some(0) {
+        return Err(NetworkError::InvalidLayer(format!(
+            "invalid {tag}: {} gates, bptt steps {:?}",
+            json.gates.len(),
+            json.bptt_steps
+        )));
+    }
+
+    let config = RecurrentConfig {
+        input_size: json.input_size,
+        hidden_size: json.hidden_size,
+        return_sequences: json.return_sequences,
+        bptt_steps: json.bptt_steps,
+    };
+    config.serialize(buf)?;
+
+    let inputs_count = json.input_size.saturating_add(json.hidden_size);
+
+    for gate in &json.gates {
+        if gate.weights.len() != json.hidden_size {
+            return Err(NetworkError::InvalidLayer(format!(
+                "invalid {tag}: expected {} neurons per gate, got {}",
+                json.hidden_size,
+                gate.weights.len()
+            )));
+        }
+
+        write_neurons(buf, gate, inputs_count, tag)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prune::PruneMethod, test_utils::get_params};
+
+    use super::*;
+
+    fn assert_round_trip(net: &Network, inputs: &[f64]) {
+        let json = net.to_json().unwrap();
+        let restored = Network::from_json(&json).unwrap();
+
+        assert_eq!(restored.input_shape(), net.input_shape());
+        assert_eq!(restored.output_shape(), net.output_shape());
+
+        // floats are restored bit by bit
+        let bits = |net: &Network| {
+            get_params(net)
+                .iter()
+                .map(|d| d.to_bits())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bits(&restored), bits(net));
+
+        let outputs = |net: &Network| -> Vec<f64> {
+            net.forward(inputs)
+                .iter()
+                .map(|out| out.borrow().d)
+                .collect()
+        };
+        assert_eq!(outputs(&restored), outputs(net));
+
+        assert_eq!(restored.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn golden() {
+        let net = Network::new(vec![2, 1]);
+
+        for (param, d) in net.parameters().iter().zip([0.1, -2.5e-7, 1.0 / 3.0]) {
+            param.borrow_mut().d = d;
+        }
+
+        let expected = r#"{
+  "version": 1,
+  "input_shape": [
+    2
+  ],
+  "layers": [
+    {
+      "type": "dense",
+      "weights": [
+        [
+          0.1,
+          -2.5e-7
+        ]
+      ],
+      "biases": [
+        0.3333333333333333
+      ]
+    },
+    {
+      "type": "activation",
+      "activation": "tanh"
+    }
+  ]
+}"#;
+
+        assert_eq!(net.to_json().unwrap(), expected);
+        assert_eq!(
+            get_params(&Network::from_json(expected).unwrap()),
+            vec![0.1, -2.5e-7, 1.0 / 3.0]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let net = Network::new(vec![3, 4, 4, 1]);
+        assert_round_trip(&net, &[0.5, -1.0, 2.0]);
+    }
+
+    #[test]
+    fn round_trip_cnn() {
+        let norm = BatchNorm1D::new(2);
+        *norm.running_mean.borrow_mut() = vec![0.1, -0.2];
+        *norm.running_var.borrow_mut() = vec![1.5, 0.7];
+
+        let net = Network::from_layers(
+            vec![1, 4, 4],
+            vec![
+                Box::new(Conv2D::new([1, 4, 4], 2, 3, 1, 1)),
+                Box::new(Activation::relu()),
+                Box::new(MaxPool2D::new([2, 4, 4], 2, 2)),
+                Box::new(AvgPool2D::new([2, 2, 2], 2, 2)),
+                Box::new(Flatten::new()),
+                Box::new(norm),
+                Box::new(Dense::new(2, 3)),
+                Box::new(Dropout::new(0.1)),
+                Box::new(Reshape::new(vec![3, 1])),
+                Box::new(Activation::sigmoid()),
+            ],
+        )
+        .unwrap();
+
+        let inputs: Vec<f64> = (0..16).map(|i| (i as f64 * 0.3).sin()).collect();
+        assert_round_trip(&net, &inputs);
+    }
+
+    #[test]
+    fn round_trip_rnn() {
+        let net = Network::from_layers(
+            vec![5, 3],
+            vec![
+                Box::new(
+                    Lstm::new(3, 4)
+                        .with_return_sequences(true)
+                        .with_bptt_steps(2),
+                ),
+                Box::new(Gru::new(4, 3).with_return_sequences(true)),
+                Box::new(Rnn::new(3, 2)),
+            ],
+        )
+        .unwrap();
+
+        let inputs: Vec<f64> = (0..15).map(|i| (i as f64 * 0.7).cos()).collect();
+        assert_round_trip(&net, &inputs);
+    }
+
+    #[test]
+    fn round_trip_pruned() {
+        let mut net = Network::new(vec![4, 6, 2]);
+        net.prune(PruneMethod::Unstructured, 0.5);
+
+        assert_round_trip(&net, &[0.5, -1.0, 2.0, 0.1]);
+
+        // mask is restored too, so pruned parameters stay pruned during fine-tuning
+        let restored = Network::from_json(&net.to_json().unwrap()).unwrap();
+
+        assert_eq!(restored.pruning_mask(), net.pruning_mask());
+
+        let json = net.to_json().unwrap();
+        let pruned_idx = net
+            .pruning_mask()
+            .iter()
+            .position(|pruned| *pruned)
+            .unwrap();
+        let json = json.replacen(
+            &format!("\"pruned\": [\n    {pruned_idx}"),
+            "\"pruned\": [\n    100",
+            1,
+        );
+
+        assert!(matches!(
+            Network::from_json(&json),
+            Err(NetworkError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn non_finite() {
+        for d in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let net = Network::new(vec![2, 3, 1]);
+            net.parameters()[4].borrow_mut().d = d;
+
+            assert!(matches!(net.to_json(), Err(NetworkError::InvalidJson(_))));
+
+            let norm = BatchNorm1D::new(2);
+            norm.running_var.borrow_mut()[1] = d;
+
+            let net = Network::from_layers(vec![2], vec![Box::new(norm)]).unwrap();
+
+            assert!(matches!(net.to_json(), Err(NetworkError::InvalidJson(_))));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let json = Network::new(vec![2, 3, 1]).to_json().unwrap();
+
+        let assert_err = |json: &str, expected: &str| {
+            let err = Network::from_json(json).err().expect("expected error");
+            assert!(
+                err.to_string().starts_with(expected),
+                "{err} doesn't start with {expected}"
+            );
+        };
+
+        assert_err("{", "invalid json");
+        assert_err(
+            &json.replacen("\"version\": 1", "\"version\": 7", 1),
+            "unsupported format version: 7",
+        );
+        assert_err(
+            &json.replacen("\"tanh\"", "\"softmax\"", 1),
+            "invalid layer: unknown activation: softmax",
+        );
+        assert_err(
+            &json.replacen("\"dense\"", "\"attention\"", 1),
+            "invalid json",
+        );
+
+        // rows of different lengths
+        let json = json.replacen("[\n          ", "[\n          1.0,\n          ", 1);
+        assert_err(
+            &json,
+            "invalid layer: invalid dense: expected 3 weights per neuron",
+        );
+
+        let json = r#"{
+            "version": 1,
+            "input_shape": [2],
+            "layers": [{"type": "dense", "weights": [[1.0, 2.0]], "biases": []}]
+        }"#;
+        assert_err(
+            json,
+            "invalid layer: invalid dense: 1 weights rows, but 0 biases",
+        );
+
+        // layers don't fit each other
+        let json = r#"{
+            "version": 1,
+            "input_shape": [3],
+            "layers": [{"type": "dense", "weights": [[1.0, 2.0]], "biases": [0.0]}]
+        }"#;
+        assert_err(json, "layer dense can't take inputs of shape [3]");
+
+        let json = r#"{
+            "version": 1,
+            "input_shape": [2],
+            "layers": [{"type": "dropout", "rate": 1.5}]
+        }"#;
+        assert_err(json, "invalid layer");
+    }
+}
@@ -1,39 +1,196 @@
+pub mod activation;
+pub mod avg_pool2d;
+pub mod batch_norm1d;
+pub mod conv2d;
+pub mod dense;
+pub mod dropout;
+pub mod flatten;
+pub mod gru;
+pub mod lstm;
+pub mod max_pool2d;
+pub mod recurrent;
+pub mod reshape;
+pub mod rnn;
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    io::{Read, Write},
+};
+
 use autograd::val::BVal;
+use network_format::MAX_TAG_LEN;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{error::NetworkError, utils};
+
+use self::{
+    activation::Activation, avg_pool2d::AvgPool2D, batch_norm1d::BatchNorm1D, conv2d::Conv2D,
+    dense::Dense, dropout::Dropout, flatten::Flatten, gru::Gru, lstm::Lstm, max_pool2d::MaxPool2D,
+    reshape::Reshape, rnn::Rnn,
+};
 
-use crate::neuron::Neuron;
+// building block of the network. each layer takes flat list of values produced by previous layer
+// (or network inputs) and produces flat list of values for the next one. shapes are only used to
+// check that layers are compatible and to let layers like convolutions interpret flat values
+pub trait Layer {
+    // unique name of layer type, which identifies layer in model file
+    fn tag(&self) -> &'static str;
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal>;
+
+    // forward pass over batch of samples. layers which don't mix samples (most of them) can
+    // process each sample separately
+    fn forward_batch(&self, inputs: Vec<Vec<BVal>>) -> Vec<Vec<BVal>> {
+        inputs
+            .into_iter()
+            .map(|inputs| self.forward(inputs))
+            .collect()
+    }
+
+    // trainable parameters of the layer
+    fn parameters(&self) -> Vec<BVal> {
+        Vec::new()
+    }
+
+    // shape of layer outputs for given shape of inputs, or None if layer can't take such inputs
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>>;
+
+    // switches between training and inference behavior, e.g. dropout is only applied in training
+    fn set_training(&mut self, _training: bool) {}
+
+    // random generator used in training (e.g. for dropout masks), so its state can be saved to
+    // checkpoint and resumed training gets the same random numbers
+    fn rng(&self) -> Option<&RefCell<ChaCha8Rng>> {
+        None
+    }
 
-pub struct Layer {
-    pub neurons: Vec<Neuron>,
+    // writes layer config and params, so layer can be restored with deserialize_layer().
+    // layer tag is written by the caller
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError>;
+
+    // allows to get concrete layer type, e.g. for exporting to other formats
+    fn as_any(&self) -> &dyn Any;
+}
+
+pub(crate) fn serialize_layer(
+    layer: &dyn Layer,
+    writer: &mut dyn Write,
+) -> Result<(), NetworkError> {
+    utils::write_string(writer, layer.tag())?;
+    layer.serialize(writer)
+}
+
+// new layer types should be registered here, so they can be read from model file
+pub(crate) fn deserialize_layer(reader: &mut dyn Read) -> Result<Box<dyn Layer>, NetworkError> {
+    let tag = utils::read_string(reader, MAX_TAG_LEN)?
+        .ok_or_else(|| NetworkError::InvalidLayer(String::from("invalid layer tag")))?;
+
+    let layer: Box<dyn Layer> = match tag.as_str() {
+        Dense::TAG => Box::new(Dense::deserialize(reader)?),
+        Activation::TAG => Box::new(Activation::deserialize(reader)?),
+        Dropout::TAG => Box::new(Dropout::deserialize(reader)?),
+        Flatten::TAG => Box::new(Flatten::deserialize(reader)?),
+        Reshape::TAG => Box::new(Reshape::deserialize(reader)?),
+        Conv2D::TAG => Box::new(Conv2D::deserialize(reader)?),
+        MaxPool2D::TAG => Box::new(MaxPool2D::deserialize(reader)?),
+        AvgPool2D::TAG => Box::new(AvgPool2D::deserialize(reader)?),
+        BatchNorm1D::TAG => Box::new(BatchNorm1D::deserialize(reader)?),
+        Rnn::TAG => Box::new(Rnn::deserialize(reader)?),
+        Gru::TAG => Box::new(Gru::deserialize(reader)?),
+        Lstm::TAG => Box::new(Lstm::deserialize(reader)?),
+        _ => return Err(NetworkError::UnknownLayer(tag)),
+    };
+
+    Ok(layer)
 }
 
-impl Layer {
-    pub fn new(inputs_count: usize, outputs_count: usize) -> Self {
-        let mut neurons = Vec::new();
-        neurons.resize_with(outputs_count, || Neuron::new(inputs_count));
+// size of sliding window output along one dimension (e.g. for convolution or pooling),
+// or None if window doesn't fit the input
+pub(crate) fn get_window_output_size(
+    input_size: usize,
+    kernel_size: usize,
+    stride: usize,
+    padding: usize,
+) -> Option<usize> {
+    let padded_size = input_size.checked_add(padding.checked_mul(2)?)?;
 
-        Layer { neurons }
+    if input_size == 0 || kernel_size == 0 || stride == 0 || padded_size < kernel_size {
+        return None;
     }
 
-    pub fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
-        let mut outputs = Vec::new();
+    Some((padded_size - kernel_size) / stride + 1)
+}
 
-        for n in &self.neurons {
-            outputs.push(n.forward(&inputs))
-        }
+// compares gradients of layer inputs and parameters computed by backward pass with numerical
+// ones, taking weighted sum of layer outputs as loss
+#[cfg(test)]
+pub(crate) fn check_gradients(layer: &dyn Layer, inputs: &[f64]) {
+    const EPSILON: f64 = 1e-6;
+
+    let calc_loss = |inputs: &[BVal]| {
+        let outputs = layer.forward(inputs.to_vec());
 
         outputs
+            .iter()
+            .enumerate()
+            .fold(BVal::new(0.0), |loss, (idx, out)| {
+                let weighted = out * (idx as f64 * 0.7).cos();
+                &loss + &weighted
+            })
+    };
+
+    let calc_loss_value = |inputs: &[f64]| {
+        let inputs: Vec<BVal> = inputs.iter().map(|d| BVal::new(*d)).collect();
+        let loss = calc_loss(&inputs).borrow().d;
+        loss
+    };
+
+    let assert_grad = |name: &str, grad: f64, numerical: f64| {
+        assert!(
+            (grad - numerical).abs() < 1e-5 * numerical.abs().max(1.0),
+            "{name}: gradient {grad}, numerical gradient {numerical}"
+        );
+    };
+
+    // autograd
+    let input_vals: Vec<BVal> = inputs.iter().map(|d| BVal::new(*d)).collect();
+
+    for param in layer.parameters() {
+        param.borrow_mut().grad = 0.0;
     }
 
-    pub fn parameters(&self) -> Vec<BVal> {
-        let mut res = Vec::new();
+    let loss = calc_loss(&input_vals);
+    loss.borrow_mut().grad = 1.0;
+    loss.backward();
 
-        for neuron in &self.neurons {
-            for param in neuron.parameters() {
-                res.push(param);
-            }
-        }
+    // numerical
+    for (idx, input) in input_vals.iter().enumerate() {
+        let mut inputs = inputs.to_vec();
+
+        inputs[idx] += EPSILON;
+        let loss_plus = calc_loss_value(&inputs);
+
+        inputs[idx] -= 2.0 * EPSILON;
+        let loss_minus = calc_loss_value(&inputs);
+
+        let numerical = (loss_plus - loss_minus) / (2.0 * EPSILON);
+        assert_grad(&format!("input {idx}"), input.borrow().grad, numerical);
+    }
+
+    for (idx, param) in layer.parameters().iter().enumerate() {
+        let d = param.borrow().d;
+
+        param.borrow_mut().d = d + EPSILON;
+        let loss_plus = calc_loss_value(inputs);
+
+        param.borrow_mut().d = d - EPSILON;
+        let loss_minus = calc_loss_value(inputs);
+
+        param.borrow_mut().d = d;
 
-        res
+        let numerical = (loss_plus - loss_minus) / (2.0 * EPSILON);
+        assert_grad(&format!("param {idx}"), param.borrow().grad, numerical);
     }
 }
 
@@ -42,13 +199,71 @@ mod tests {
     use super::*;
 
     #[test]
-    fn forward() {
-        let l = Layer::new(3, 3);
+    fn serialization() {
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(3, 4)),
+            Box::new(Activation::relu()),
+            Box::new(Dropout::new(0.5)),
+            Box::new(Reshape::new(vec![2, 2])),
+            Box::new(Flatten::new()),
+            Box::new(Conv2D::new([1, 2, 2], 2, 2, 1, 1)),
+            Box::new(MaxPool2D::new([1, 2, 2], 2, 1)),
+            Box::new(AvgPool2D::new([1, 2, 2], 1, 1)),
+            Box::new(BatchNorm1D::new(4)),
+            Box::new(Rnn::new(2, 3).with_bptt_steps(2)),
+            Box::new(Gru::new(2, 3).with_return_sequences(true)),
+            Box::new(Lstm::new(2, 3)),
+        ];
 
-        let outputs = l.forward(vec![BVal::new(1.0), BVal::new(2.0), BVal::new(3.0)]);
+        let mut bytes = Vec::new();
+        for layer in &layers {
+            serialize_layer(layer.as_ref(), &mut bytes).unwrap();
+        }
+
+        let mut reader = bytes.as_slice();
+        for layer in &layers {
+            let restored = deserialize_layer(&mut reader).unwrap();
+
+            assert_eq!(restored.tag(), layer.tag());
+            assert_eq!(
+                restored.output_shape(&[4]),
+                layer.output_shape(&[4]),
+                "layer: {}",
+                layer.tag()
+            );
+            assert_eq!(
+                restored.output_shape(&[1, 2, 2]),
+                layer.output_shape(&[1, 2, 2]),
+                "layer: {}",
+                layer.tag()
+            );
+            assert_eq!(
+                restored.output_shape(&[3, 2]),
+                layer.output_shape(&[3, 2]),
+                "layer: {}",
+                layer.tag()
+            );
 
-        for out in &outputs {
-            assert!((out.borrow().d > -1.0) && (out.borrow().d < 1.0));
+            let params1 = layer.parameters();
+            let params2 = restored.parameters();
+
+            assert_eq!(params1.len(), params2.len());
+
+            for (param1, param2) in params1.iter().zip(params2.iter()) {
+                assert_eq!(param1.borrow().d, param2.borrow().d);
+            }
         }
+
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn deserialization_unknown_layer() {
+        let mut bytes = Vec::new();
+        utils::write_string(&mut bytes, "unknown").unwrap();
+
+        let res = deserialize_layer(&mut bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::UnknownLayer(tag)) if tag == "unknown"));
     }
 }
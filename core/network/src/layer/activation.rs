@@ -0,0 +1,140 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::{error::NetworkError, utils};
+
+use super::Layer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivationKind {
+    Tanh,
+    Relu,
+    Sigmoid,
+}
+
+impl ActivationKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ActivationKind::Tanh => "tanh",
+            ActivationKind::Relu => "relu",
+            ActivationKind::Sigmoid => "sigmoid",
+        }
+    }
+
+    pub(crate) fn to_u32(self) -> u32 {
+        match self {
+            ActivationKind::Tanh => 0,
+            ActivationKind::Relu => 1,
+            ActivationKind::Sigmoid => 2,
+        }
+    }
+
+    pub(crate) fn from_u32(n: u32) -> Option<Self> {
+        match n {
+            0 => Some(ActivationKind::Tanh),
+            1 => Some(ActivationKind::Relu),
+            2 => Some(ActivationKind::Sigmoid),
+            _ => None,
+        }
+    }
+}
+
+// applies activation function to each input separately
+pub struct Activation {
+    pub kind: ActivationKind,
+}
+
+impl Activation {
+    pub const TAG: &'static str = tags::ACTIVATION;
+
+    pub fn new(kind: ActivationKind) -> Self {
+        Activation { kind }
+    }
+
+    pub fn tanh() -> Self {
+        Activation::new(ActivationKind::Tanh)
+    }
+
+    pub fn relu() -> Self {
+        Activation::new(ActivationKind::Relu)
+    }
+
+    pub fn sigmoid() -> Self {
+        Activation::new(ActivationKind::Sigmoid)
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let kind = utils::read_u32(reader)?;
+
+        ActivationKind::from_u32(kind)
+            .map(Activation::new)
+            .ok_or_else(|| NetworkError::InvalidLayer(format!("unknown activation: {kind}")))
+    }
+}
+
+impl Layer for Activation {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        inputs
+            .iter()
+            .map(|input| match self.kind {
+                ActivationKind::Tanh => input.tanh(),
+                ActivationKind::Relu => input.relu(),
+                ActivationKind::Sigmoid => input.sigmoid(),
+            })
+            .collect()
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        Some(input_shape.to_vec())
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_u32(writer, self.kind.to_u32())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward() {
+        let inputs = || vec![BVal::new(-2.0), BVal::new(0.5)];
+
+        let outputs: Vec<f64> = Activation::relu()
+            .forward(inputs())
+            .iter()
+            .map(|v| v.borrow().d)
+            .collect();
+        assert_eq!(outputs, vec![0.0, 0.5]);
+
+        let outputs = Activation::tanh().forward(inputs());
+        assert!((outputs[1].borrow().d - 0.5f64.tanh()).abs() < 1e-12);
+
+        let outputs = Activation::sigmoid().forward(inputs());
+        assert!((outputs[0].borrow().d - 1.0 / (1.0 + 2f64.exp())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn deserialization_unknown_activation() {
+        let mut bytes = Vec::new();
+        utils::write_u32(&mut bytes, 99).unwrap();
+
+        let res = Activation::deserialize(&mut bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::InvalidLayer(_))));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::{error::NetworkError, utils};
+
+use super::{max_pool2d::get_output_shape, Layer};
+
+// takes average value of each window over inputs of shape [channels, height, width]
+pub struct AvgPool2D {
+    pub input_shape: [usize; 3],
+    pub kernel_size: usize,
+    pub stride: usize,
+}
+
+impl AvgPool2D {
+    pub const TAG: &'static str = tags::AVG_POOL2D;
+
+    pub fn new(input_shape: [usize; 3], kernel_size: usize, stride: usize) -> Self {
+        assert!(
+            get_output_shape(input_shape, kernel_size, stride).is_some(),
+            "pooling doesn't fit input shape {input_shape:?}"
+        );
+
+        AvgPool2D {
+            input_shape,
+            kernel_size,
+            stride,
+        }
+    }
+
+    pub fn get_output_shape(&self) -> [usize; 3] {
+        get_output_shape(self.input_shape, self.kernel_size, self.stride)
+            .expect("shapes are checked on layer creation")
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let mut config = [0; 5];
+        for n in &mut config {
+            *n = utils::read_u32(reader)? as usize;
+        }
+
+        let [channels, height, width, kernel_size, stride] = config;
+        let input_shape = [channels, height, width];
+
+        get_output_shape(input_shape, kernel_size, stride)
+            .ok_or_else(|| NetworkError::InvalidLayer(format!("invalid avg_pool2d: {config:?}")))?;
+
+        Ok(AvgPool2D::new(input_shape, kernel_size, stride))
+    }
+}
+
+impl Layer for AvgPool2D {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        let [channels, height, width] = self.input_shape;
+        let [_, out_height, out_width] = self.get_output_shape();
+
+        assert_eq!(
+            inputs.len(),
+            channels * height * width,
+            "inputs should match input shape of pooling"
+        );
+
+        let window_size = (self.kernel_size * self.kernel_size) as f64;
+        let mut outputs = Vec::with_capacity(channels * out_height * out_width);
+
+        for channel in 0..channels {
+            for out_y in 0..out_height {
+                for out_x in 0..out_width {
+                    let window_y = out_y * self.stride;
+                    let window_x = out_x * self.stride;
+
+                    let mut sum = BVal::new(0.0);
+
+                    for y in window_y..(window_y + self.kernel_size) {
+                        for x in window_x..(window_x + self.kernel_size) {
+                            let input = &inputs[(channel * height + y) * width + x];
+                            sum = &sum + input;
+                        }
+                    }
+
+                    outputs.push(&sum / window_size);
+                }
+            }
+        }
+
+        outputs
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        if input_shape == self.input_shape {
+            Some(self.get_output_shape().to_vec())
+        } else {
+            None
+        }
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        let [channels, height, width] = self.input_shape;
+
+        for n in [channels, height, width, self.kernel_size, self.stride] {
+            utils::write_u32(writer, n as u32)?;
+        }
+
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::layer::check_gradients;
+
+    #[test]
+    fn forward() {
+        let l = AvgPool2D::new([1, 4, 4], 2, 2);
+
+        #[rustfmt::skip]
+        let inputs = vec![
+            1.0, 2.0, 5.0, 0.0,
+            3.0, 4.0, 1.0, 1.0,
+            0.0, 0.0, -1.0, -2.0,
+            0.0, 9.0, -3.0, -4.0,
+        ];
+
+        let outputs = l.forward(inputs.iter().map(|d| BVal::new(*d)).collect());
+        let outputs: Vec<f64> = outputs.iter().map(|v| v.borrow().d).collect();
+
+        assert_eq!(outputs, vec![2.5, 1.75, 2.25, -2.5]);
+    }
+
+    #[test]
+    fn output_shape() {
+        let l = AvgPool2D::new([6, 28, 28], 2, 2);
+        assert_eq!(l.output_shape(&[6, 28, 28]), Some(vec![6, 14, 14]));
+
+        let l = AvgPool2D::new([3, 5, 5], 3, 1);
+        assert_eq!(l.output_shape(&[3, 5, 5]), Some(vec![3, 3, 3]));
+        assert_eq!(l.output_shape(&[75]), None);
+    }
+
+    #[test]
+    fn backward() {
+        // overlapping windows, so same input goes to several windows
+        let l = AvgPool2D::new([2, 5, 4], 3, 1);
+        let inputs: Vec<f64> = (0..40).map(|n| (n as f64 * 0.37).sin()).collect();
+
+        check_gradients(&l, &inputs);
+    }
+}
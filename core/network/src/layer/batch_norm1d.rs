@@ -0,0 +1,429 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::{error::NetworkError, utils};
+
+use super::Layer;
+
+const DEFAULT_MOMENTUM: f64 = 0.1;
+const DEFAULT_EPSILON: f64 = 1e-5;
+
+// normalizes each feature over the batch to zero mean and unit variance, then scales and shifts
+// it with learnable gamma and beta. running mean and variance are collected while training and
+// used instead of batch statistics in eval mode
+pub struct BatchNorm1D {
+    pub gamma: Vec<BVal>,
+    pub beta: Vec<BVal>,
+    pub running_mean: RefCell<Vec<f64>>,
+    pub running_var: RefCell<Vec<f64>>,
+    // weight of batch statistics when updating running ones
+    pub momentum: f64,
+    // added to variance to avoid division by zero
+    pub epsilon: f64,
+    training: bool,
+    // mean and unbiased variance of the last training batch, so data-parallel training can apply
+    // running statistics updates of replicas to the network (see parallel::compute())
+    batch_stats: RefCell<Option<(Vec<f64>, Vec<f64>)>>,
+}
+
+impl BatchNorm1D {
+    pub const TAG: &'static str = tags::BATCH_NORM1D;
+
+    pub fn new(features_count: usize) -> Self {
+        Self::with_params(features_count, DEFAULT_MOMENTUM, DEFAULT_EPSILON)
+    }
+
+    pub fn with_params(features_count: usize, momentum: f64, epsilon: f64) -> Self {
+        assert!(features_count > 0, "features count should be positive");
+        assert!(
+            (0.0..=1.0).contains(&momentum),
+            "momentum should be in [0, 1] range"
+        );
+        assert!(epsilon > 0.0, "epsilon should be positive");
+
+        let mut gamma = Vec::new();
+        gamma.resize_with(features_count, || BVal::new(1.0));
+
+        let mut beta = Vec::new();
+        beta.resize_with(features_count, || BVal::new(0.0));
+
+        BatchNorm1D {
+            gamma,
+            beta,
+            running_mean: RefCell::new(vec![0.0; features_count]),
+            running_var: RefCell::new(vec![1.0; features_count]),
+            momentum,
+            epsilon,
+            training: false,
+            batch_stats: RefCell::new(None),
+        }
+    }
+
+    pub fn features_count(&self) -> usize {
+        self.gamma.len()
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let features_count = utils::read_u32(reader)? as usize;
+        let momentum = utils::read_f64(reader)?;
+        let epsilon = utils::read_f64(reader)?;
+
+        if features_count == 0
+            || !(0.0..=1.0).contains(&momentum)
+            || !epsilon.is_finite()
+            || epsilon <= 0.0
+        {
+            return Err(NetworkError::InvalidLayer(format!(
+                "invalid batch_norm1d: features {features_count}, \
+                momentum {momentum}, epsilon {epsilon}"
+            )));
+        }
+
+        // gamma, beta, running mean and running variance
+        let state = utils::read_params(reader, features_count.saturating_mul(4))?;
+
+        let layer = BatchNorm1D::with_params(features_count, momentum, epsilon);
+
+        let mut state = state.chunks_exact(features_count);
+
+        for (param, d) in layer.gamma.iter().zip(state.next().unwrap()) {
+            param.borrow_mut().d = *d;
+        }
+        for (param, d) in layer.beta.iter().zip(state.next().unwrap()) {
+            param.borrow_mut().d = *d;
+        }
+
+        *layer.running_mean.borrow_mut() = state.next().unwrap().to_vec();
+        *layer.running_var.borrow_mut() = state.next().unwrap().to_vec();
+
+        Ok(layer)
+    }
+
+    fn normalize(&self, input: &BVal, mean: &BVal, var: &BVal, feature_idx: usize) -> BVal {
+        let centered = input - mean;
+        let std = (var + self.epsilon).pow(0.5);
+        let normalized = &centered / &std;
+        let scaled = &normalized * &self.gamma[feature_idx];
+        &scaled + &self.beta[feature_idx]
+    }
+
+    pub(crate) fn take_batch_stats(&self) -> Option<(Vec<f64>, Vec<f64>)> {
+        self.batch_stats.borrow_mut().take()
+    }
+
+    pub(crate) fn update_running_stats(&self, mean: &[f64], var: &[f64]) {
+        let mut running_mean = self.running_mean.borrow_mut();
+        let mut running_var = self.running_var.borrow_mut();
+
+        for (running, d) in running_mean.iter_mut().zip(mean) {
+            *running = (1.0 - self.momentum) * *running + self.momentum * d;
+        }
+
+        for (running, d) in running_var.iter_mut().zip(var) {
+            *running = (1.0 - self.momentum) * *running + self.momentum * d;
+        }
+    }
+
+    fn forward_running(&self, inputs: &[BVal]) -> Vec<BVal> {
+        let running_mean = self.running_mean.borrow();
+        let running_var = self.running_var.borrow();
+
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| {
+                let mean = BVal::new(running_mean[idx]);
+                let var = BVal::new(running_var[idx]);
+                self.normalize(input, &mean, &var, idx)
+            })
+            .collect()
+    }
+}
+
+impl Layer for BatchNorm1D {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    // batch statistics need a batch, so single sample is always normalized with running ones
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        assert_eq!(
+            inputs.len(),
+            self.features_count(),
+            "inputs should match features count of batch norm"
+        );
+
+        self.forward_running(&inputs)
+    }
+
+    // variance of single sample is always zero, so such batch (e.g. the last one of dataset) is
+    // normalized with running statistics, and they aren't updated
+    fn forward_batch(&self, inputs: Vec<Vec<BVal>>) -> Vec<Vec<BVal>> {
+        let batch_size = inputs.len();
+
+        if !self.training || batch_size < 2 {
+            return inputs
+                .into_iter()
+                .map(|inputs| self.forward(inputs))
+                .collect();
+        }
+
+        for sample in &inputs {
+            assert_eq!(
+                sample.len(),
+                self.features_count(),
+                "inputs should match features count of batch norm"
+            );
+        }
+
+        let mut outputs: Vec<Vec<BVal>> = vec![Vec::new(); batch_size];
+        let mut batch_mean = Vec::with_capacity(self.features_count());
+        let mut batch_var = Vec::with_capacity(self.features_count());
+
+        for feature_idx in 0..self.features_count() {
+            // batch statistics are part of computation graph, so gradients go through them too
+            let mut sum = BVal::new(0.0);
+            for sample in &inputs {
+                sum = &sum + &sample[feature_idx];
+            }
+            let mean = &sum / batch_size as f64;
+
+            let mut squares_sum = BVal::new(0.0);
+            for sample in &inputs {
+                let centered = &sample[feature_idx] - &mean;
+                let square = centered.pow(2.0);
+                squares_sum = &squares_sum + &square;
+            }
+            let var = &squares_sum / batch_size as f64;
+
+            for (sample, output) in inputs.iter().zip(outputs.iter_mut()) {
+                output.push(self.normalize(&sample[feature_idx], &mean, &var, feature_idx));
+            }
+
+            // running variance is unbiased, same as in pytorch and dfdx
+            batch_mean.push(mean.borrow().d);
+            batch_var.push(var.borrow().d * batch_size as f64 / (batch_size - 1) as f64);
+        }
+
+        self.update_running_stats(&batch_mean, &batch_var);
+        *self.batch_stats.borrow_mut() = Some((batch_mean, batch_var));
+
+        outputs
+    }
+
+    fn parameters(&self) -> Vec<BVal> {
+        let mut res = self.gamma.clone();
+        res.extend(self.beta.iter().cloned());
+        res
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        if input_shape == [self.features_count()] {
+            Some(input_shape.to_vec())
+        } else {
+            None
+        }
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_u32(writer, self.features_count() as u32)?;
+        utils::write_f64(writer, self.momentum)?;
+        utils::write_f64(writer, self.epsilon)?;
+
+        for param in self.parameters() {
+            let d = param.borrow().d;
+            utils::write_f64(writer, d)?;
+        }
+
+        for d in self.running_mean.borrow().iter() {
+            utils::write_f64(writer, *d)?;
+        }
+
+        for d in self.running_var.borrow().iter() {
+            utils::write_f64(writer, *d)?;
+        }
+
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_batch() -> Vec<Vec<f64>> {
+        vec![
+            vec![1.0, -2.0, 0.5],
+            vec![3.0, 0.0, 0.7],
+            vec![-1.0, 4.0, 0.1],
+            vec![2.0, 1.0, -0.3],
+        ]
+    }
+
+    fn to_vals(batch: &[Vec<f64>]) -> Vec<Vec<BVal>> {
+        batch
+            .iter()
+            .map(|sample| sample.iter().map(|d| BVal::new(*d)).collect())
+            .collect()
+    }
+
+    fn to_values(batch: &[Vec<BVal>]) -> Vec<Vec<f64>> {
+        batch
+            .iter()
+            .map(|sample| sample.iter().map(|v| v.borrow().d).collect())
+            .collect()
+    }
+
+    #[test]
+    fn forward_batch_training() {
+        let mut l = BatchNorm1D::new(3);
+        l.set_training(true);
+
+        let outputs = to_values(&l.forward_batch(to_vals(&create_batch())));
+
+        // each feature has zero mean and unit variance over the batch
+        for feature_idx in 0..3 {
+            let column: Vec<f64> = outputs.iter().map(|out| out[feature_idx]).collect();
+
+            let mean = column.iter().sum::<f64>() / 4.0;
+            let var = column.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / 4.0;
+
+            assert!(mean.abs() < 1e-9);
+            assert!((var - 1.0).abs() < 1e-3);
+        }
+
+        // running statistics moved towards batch ones
+        let running_mean = l.running_mean.borrow();
+        assert!((running_mean[0] - 0.1 * 1.25).abs() < 1e-12);
+
+        let running_var = l.running_var.borrow();
+        let unbiased_var = (0.0625 + 3.0625 + 5.0625 + 0.5625) / 3.0;
+        assert!((running_var[0] - (0.9 + 0.1 * unbiased_var)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn forward_batch_eval() {
+        let l = BatchNorm1D::new(3);
+
+        *l.running_mean.borrow_mut() = vec![1.0, 2.0, 3.0];
+        *l.running_var.borrow_mut() = vec![4.0, 1.0, 0.25];
+        l.gamma[0].borrow_mut().d = 2.0;
+        l.beta[0].borrow_mut().d = 0.5;
+
+        let outputs = to_values(&l.forward_batch(to_vals(&[vec![3.0, 2.0, 2.0]])));
+
+        let expected = [
+            2.0 * (3.0 - 1.0) / (4.0 + DEFAULT_EPSILON).sqrt() + 0.5,
+            0.0,
+            (2.0 - 3.0) / (0.25 + DEFAULT_EPSILON).sqrt(),
+        ];
+
+        for (out, expected) in outputs[0].iter().zip(expected) {
+            assert!((out - expected).abs() < 1e-12);
+        }
+
+        // running statistics don't change in eval mode
+        assert_eq!(*l.running_mean.borrow(), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn backward() {
+        const EPSILON: f64 = 1e-6;
+
+        let mut l = BatchNorm1D::new(3);
+        l.set_training(true);
+
+        l.gamma[1].borrow_mut().d = 1.5;
+        l.beta[2].borrow_mut().d = -0.5;
+
+        let batch = create_batch();
+
+        // weighted sum of outputs, so each output gets different gradient
+        let calc_loss = |inputs: Vec<Vec<BVal>>| {
+            let outputs = l.forward_batch(inputs);
+
+            let mut loss = BVal::new(0.0);
+            for (idx, out) in outputs.iter().flatten().enumerate() {
+                let weighted = out * (idx as f64 * 0.7).cos();
+                loss = &loss + &weighted;
+            }
+            loss
+        };
+
+        let inputs = to_vals(&batch);
+        let loss = calc_loss(inputs.clone());
+        loss.borrow_mut().grad = 1.0;
+        loss.backward();
+
+        for sample_idx in 0..batch.len() {
+            for feature_idx in 0..3 {
+                let mut batch_plus = batch.clone();
+                batch_plus[sample_idx][feature_idx] += EPSILON;
+
+                let mut batch_minus = batch.clone();
+                batch_minus[sample_idx][feature_idx] -= EPSILON;
+
+                let loss_plus = calc_loss(to_vals(&batch_plus)).borrow().d;
+                let loss_minus = calc_loss(to_vals(&batch_minus)).borrow().d;
+                let numerical = (loss_plus - loss_minus) / (2.0 * EPSILON);
+
+                let grad = inputs[sample_idx][feature_idx].borrow().grad;
+                assert!(
+                    (grad - numerical).abs() < 1e-5,
+                    "gradient {grad}, numerical {numerical}"
+                );
+            }
+        }
+
+        for param in l.parameters() {
+            let d = param.borrow().d;
+
+            param.borrow_mut().d = d + EPSILON;
+            let loss_plus = calc_loss(to_vals(&batch)).borrow().d;
+
+            param.borrow_mut().d = d - EPSILON;
+            let loss_minus = calc_loss(to_vals(&batch)).borrow().d;
+
+            param.borrow_mut().d = d;
+
+            let numerical = (loss_plus - loss_minus) / (2.0 * EPSILON);
+            assert!((param.borrow().grad - numerical).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn serialization() {
+        let mut l1 = BatchNorm1D::with_params(3, 0.2, 1e-3);
+        l1.set_training(true);
+        l1.forward_batch(to_vals(&create_batch()));
+        l1.gamma[0].borrow_mut().d = 1.5;
+
+        let mut bytes = Vec::new();
+        l1.serialize(&mut bytes).unwrap();
+
+        let l2 = BatchNorm1D::deserialize(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(l2.momentum, 0.2);
+        assert_eq!(l2.epsilon, 1e-3);
+        assert_eq!(*l2.running_mean.borrow(), *l1.running_mean.borrow());
+        assert_eq!(*l2.running_var.borrow(), *l1.running_var.borrow());
+
+        for (param1, param2) in l1.parameters().iter().zip(l2.parameters().iter()) {
+            assert_eq!(param1.borrow().d, param2.borrow().d);
+        }
+    }
+}
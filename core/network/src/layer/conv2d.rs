@@ -0,0 +1,295 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::{error::NetworkError, neuron::Neuron, utils};
+
+use super::{get_window_output_size, Layer};
+
+// 2D convolution over inputs of shape [channels, height, width], without activation.
+// produces outputs of shape [out_channels, out_height, out_width]
+pub struct Conv2D {
+    pub input_shape: [usize; 3],
+    pub out_channels: usize,
+    pub kernel_size: usize,
+    pub stride: usize,
+    pub padding: usize,
+    // one filter per output channel, with weights laid out as [channels, kernel_size, kernel_size]
+    pub filters: Vec<Neuron>,
+}
+
+impl Conv2D {
+    pub const TAG: &'static str = tags::CONV2D;
+
+    pub fn new(
+        input_shape: [usize; 3],
+        out_channels: usize,
+        kernel_size: usize,
+        stride: usize,
+        padding: usize,
+    ) -> Self {
+        assert!(
+            get_output_shape(input_shape, out_channels, kernel_size, stride, padding).is_some(),
+            "convolution doesn't fit input shape {input_shape:?}"
+        );
+
+        let [channels, _, _] = input_shape;
+
+        let mut filters = Vec::new();
+        filters.resize_with(out_channels, || {
+            Neuron::new(channels * kernel_size * kernel_size)
+        });
+
+        Conv2D {
+            input_shape,
+            out_channels,
+            kernel_size,
+            stride,
+            padding,
+            filters,
+        }
+    }
+
+    pub fn get_output_shape(&self) -> [usize; 3] {
+        get_output_shape(
+            self.input_shape,
+            self.out_channels,
+            self.kernel_size,
+            self.stride,
+            self.padding,
+        )
+        .expect("shapes are checked on layer creation")
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let mut config = [0; 7];
+        for n in &mut config {
+            *n = utils::read_u32(reader)? as usize;
+        }
+
+        let [channels, height, width, out_channels, kernel_size, stride, padding] = config;
+        let input_shape = [channels, height, width];
+
+        get_output_shape(input_shape, out_channels, kernel_size, stride, padding)
+            .ok_or_else(|| NetworkError::InvalidLayer(format!("invalid conv2d: {config:?}")))?;
+
+        let params_count = channels
+            .checked_mul(kernel_size * kernel_size)
+            .and_then(|n| n.checked_add(1))
+            .and_then(|n| n.checked_mul(out_channels))
+            .ok_or_else(|| NetworkError::InvalidLayer(format!("invalid conv2d: {config:?}")))?;
+
+        let params = utils::read_params(reader, params_count)?;
+
+        let layer = Conv2D::new(input_shape, out_channels, kernel_size, stride, padding);
+
+        for (param, d) in layer.parameters().iter().zip(params) {
+            param.borrow_mut().d = d;
+        }
+
+        Ok(layer)
+    }
+}
+
+impl Layer for Conv2D {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        let [channels, height, width] = self.input_shape;
+        let [_, out_height, out_width] = self.get_output_shape();
+
+        assert_eq!(
+            inputs.len(),
+            channels * height * width,
+            "inputs should match input shape of convolution"
+        );
+
+        let kernel_size = self.kernel_size;
+        let mut outputs = Vec::with_capacity(self.out_channels * out_height * out_width);
+
+        for filter in &self.filters {
+            for out_y in 0..out_height {
+                for out_x in 0..out_width {
+                    let mut sum = filter.bias.clone();
+
+                    for channel in 0..channels {
+                        for kernel_y in 0..kernel_size {
+                            // skip padding, since it's zeros anyway
+                            let y = out_y * self.stride + kernel_y;
+                            if y < self.padding || y - self.padding >= height {
+                                continue;
+                            }
+                            let y = y - self.padding;
+
+                            for kernel_x in 0..kernel_size {
+                                let x = out_x * self.stride + kernel_x;
+                                if x < self.padding || x - self.padding >= width {
+                                    continue;
+                                }
+                                let x = x - self.padding;
+
+                                let input = &inputs[(channel * height + y) * width + x];
+                                let weight = &filter.weights
+                                    [(channel * kernel_size + kernel_y) * kernel_size + kernel_x];
+
+                                let mul = input * weight;
+                                sum = &sum + &mul;
+                            }
+                        }
+                    }
+
+                    outputs.push(sum);
+                }
+            }
+        }
+
+        outputs
+    }
+
+    fn parameters(&self) -> Vec<BVal> {
+        let mut res = Vec::new();
+
+        for filter in &self.filters {
+            for param in filter.parameters() {
+                res.push(param);
+            }
+        }
+
+        res
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        if input_shape == self.input_shape {
+            Some(self.get_output_shape().to_vec())
+        } else {
+            None
+        }
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        let [channels, height, width] = self.input_shape;
+
+        for n in [
+            channels,
+            height,
+            width,
+            self.out_channels,
+            self.kernel_size,
+            self.stride,
+            self.padding,
+        ] {
+            utils::write_u32(writer, n as u32)?;
+        }
+
+        for param in self.parameters() {
+            let d = param.borrow().d;
+            utils::write_f64(writer, d)?;
+        }
+
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn get_output_shape(
+    input_shape: [usize; 3],
+    out_channels: usize,
+    kernel_size: usize,
+    stride: usize,
+    padding: usize,
+) -> Option<[usize; 3]> {
+    let [channels, height, width] = input_shape;
+
+    if channels == 0 || out_channels == 0 {
+        return None;
+    }
+
+    Some([
+        out_channels,
+        get_window_output_size(height, kernel_size, stride, padding)?,
+        get_window_output_size(width, kernel_size, stride, padding)?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::layer::check_gradients;
+
+    #[test]
+    fn forward() {
+        let l = Conv2D::new([1, 3, 3], 1, 2, 1, 0);
+
+        for (weight, d) in l.filters[0].weights.iter().zip([1.0, 2.0, 3.0, 4.0]) {
+            weight.borrow_mut().d = d;
+        }
+        l.filters[0].bias.borrow_mut().d = 0.5;
+
+        let inputs = (1..=9).map(|n| BVal::new(n as f64)).collect();
+        let outputs: Vec<f64> = l.forward(inputs).iter().map(|v| v.borrow().d).collect();
+
+        // [1 2 3]   [1 2]
+        // [4 5 6] * [3 4] + 0.5
+        // [7 8 9]
+        assert_eq!(outputs, vec![37.5, 47.5, 67.5, 77.5]);
+    }
+
+    #[test]
+    fn forward_stride_padding() {
+        let l = Conv2D::new([1, 3, 3], 1, 3, 2, 1);
+
+        for weight in &l.filters[0].weights {
+            weight.borrow_mut().d = 1.0;
+        }
+        l.filters[0].bias.borrow_mut().d = 0.0;
+
+        let inputs = (1..=9).map(|n| BVal::new(n as f64)).collect();
+        let outputs: Vec<f64> = l.forward(inputs).iter().map(|v| v.borrow().d).collect();
+
+        // sums of 2x2 corners, since rest of each window is padding
+        assert_eq!(outputs, vec![12.0, 16.0, 24.0, 28.0]);
+    }
+
+    #[test]
+    fn output_shape() {
+        let l = Conv2D::new([1, 28, 28], 6, 5, 1, 2);
+        assert_eq!(l.output_shape(&[1, 28, 28]), Some(vec![6, 28, 28]));
+
+        let l = Conv2D::new([6, 14, 14], 16, 5, 1, 0);
+        assert_eq!(l.output_shape(&[6, 14, 14]), Some(vec![16, 10, 10]));
+        assert_eq!(l.output_shape(&[1176]), None);
+
+        let l = Conv2D::new([32, 24, 24], 32, 5, 2, 2);
+        assert_eq!(l.output_shape(&[32, 24, 24]), Some(vec![32, 12, 12]));
+    }
+
+    #[test]
+    fn backward() {
+        let l = Conv2D::new([2, 4, 5], 3, 3, 2, 1);
+        let inputs: Vec<f64> = (0..40).map(|n| (n as f64 * 0.37).sin()).collect();
+
+        check_gradients(&l, &inputs);
+    }
+
+    #[test]
+    fn deserialization_invalid_config() {
+        let mut bytes = Vec::new();
+        for n in [1, 3, 3, 1, 5, 1, 0] {
+            utils::write_u32(&mut bytes, n).unwrap();
+        }
+
+        let res = Conv2D::deserialize(&mut bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::InvalidLayer(_))));
+    }
+}
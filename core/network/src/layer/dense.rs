@@ -0,0 +1,168 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::{error::NetworkError, neuron::Neuron, utils};
+
+use super::Layer;
+
+// fully-connected layer without activation, ie. each output is weighted sum of all inputs plus
+// bias. activation goes as separate layer
+pub struct Dense {
+    pub neurons: Vec<Neuron>,
+}
+
+impl Dense {
+    pub const TAG: &'static str = tags::DENSE;
+
+    pub fn new(inputs_count: usize, outputs_count: usize) -> Self {
+        let mut neurons = Vec::new();
+        neurons.resize_with(outputs_count, || Neuron::new(inputs_count));
+
+        Dense { neurons }
+    }
+
+    pub fn inputs_count(&self) -> usize {
+        self.neurons[0].weights.len()
+    }
+
+    pub fn outputs_count(&self) -> usize {
+        self.neurons.len()
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let inputs_count = utils::read_u32(reader)? as usize;
+        let outputs_count = utils::read_u32(reader)? as usize;
+
+        let params_count = get_parameters_count(inputs_count, outputs_count).ok_or(
+            NetworkError::InvalidShape(vec![inputs_count, outputs_count]),
+        )?;
+
+        let params = utils::read_params(reader, params_count)?;
+
+        let layer = Dense::new(inputs_count, outputs_count);
+
+        for (param, d) in layer.parameters().iter().zip(params) {
+            param.borrow_mut().d = d;
+        }
+
+        Ok(layer)
+    }
+}
+
+impl Layer for Dense {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        let mut outputs = Vec::new();
+
+        for n in &self.neurons {
+            outputs.push(n.forward(&inputs))
+        }
+
+        outputs
+    }
+
+    fn parameters(&self) -> Vec<BVal> {
+        let mut res = Vec::new();
+
+        for neuron in &self.neurons {
+            for param in neuron.parameters() {
+                res.push(param);
+            }
+        }
+
+        res
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        if input_shape == [self.inputs_count()] {
+            Some(vec![self.outputs_count()])
+        } else {
+            None
+        }
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_u32(writer, self.inputs_count() as u32)?;
+        utils::write_u32(writer, self.outputs_count() as u32)?;
+
+        for param in self.parameters() {
+            let d = param.borrow().d;
+            utils::write_f64(writer, d)?;
+        }
+
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// weights + bias of each neuron
+pub(crate) fn get_parameters_count(inputs_count: usize, outputs_count: usize) -> Option<usize> {
+    if inputs_count == 0 || outputs_count == 0 {
+        return None;
+    }
+
+    inputs_count.checked_add(1)?.checked_mul(outputs_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::layer::check_gradients;
+
+    #[test]
+    fn forward() {
+        let l = Dense::new(3, 2);
+
+        let inputs = vec![BVal::new(1.0), BVal::new(2.0), BVal::new(3.0)];
+        let outputs = l.forward(inputs);
+
+        assert_eq!(outputs.len(), 2);
+
+        for (out, neuron) in outputs.iter().zip(&l.neurons) {
+            let expected = neuron.bias.borrow().d
+                + neuron.weights[0].borrow().d
+                + neuron.weights[1].borrow().d * 2.0
+                + neuron.weights[2].borrow().d * 3.0;
+
+            assert!((out.borrow().d - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn backward() {
+        let l = Dense::new(5, 3);
+        check_gradients(&l, &[0.5, -1.0, 2.0, 0.0, 0.3]);
+    }
+
+    #[test]
+    fn output_shape() {
+        let l = Dense::new(3, 2);
+
+        assert_eq!(l.output_shape(&[3]), Some(vec![2]));
+        assert_eq!(l.output_shape(&[4]), None);
+        assert_eq!(l.output_shape(&[1, 3]), None);
+    }
+
+    #[test]
+    fn deserialization_invalid_shape() {
+        let mut bytes = Vec::new();
+        utils::write_u32(&mut bytes, 3).unwrap();
+        utils::write_u32(&mut bytes, 0).unwrap();
+
+        let res = Dense::deserialize(&mut bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::InvalidShape(sizes)) if sizes == vec![3, 0]));
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{error::NetworkError, rng, utils};
+
+use super::Layer;
+
+// zeroes random inputs with given rate while training and scales the rest up, so expected
+// sum of outputs stays the same. passes inputs as is in inference
+pub struct Dropout {
+    pub rate: f64,
+    training: bool,
+    // generates dropout masks. it's seeded from global generator (see rng::seed()), unless seed
+    // is fixed for this layer
+    rng: RefCell<ChaCha8Rng>,
+}
+
+impl Dropout {
+    pub const TAG: &'static str = tags::DROPOUT;
+
+    pub fn new(rate: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&rate),
+            "dropout rate should be in [0, 1) range"
+        );
+
+        Dropout {
+            rate,
+            training: false,
+            rng: RefCell::new(ChaCha8Rng::seed_from_u64(rng::gen_seed())),
+        }
+    }
+
+    pub fn with_seed(rate: f64, seed: u64) -> Self {
+        let mut layer = Dropout::new(rate);
+        layer.set_seed(seed);
+        layer
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = RefCell::new(ChaCha8Rng::seed_from_u64(seed));
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let rate = utils::read_f64(reader)?;
+
+        if !(0.0..1.0).contains(&rate) {
+            return Err(NetworkError::InvalidLayer(format!(
+                "invalid dropout rate: {rate}"
+            )));
+        }
+
+        Ok(Dropout::new(rate))
+    }
+}
+
+impl Layer for Dropout {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        if !self.training || self.rate == 0.0 {
+            return inputs;
+        }
+
+        let scale = 1.0 / (1.0 - self.rate);
+        let mut rng = self.rng.borrow_mut();
+
+        inputs
+            .iter()
+            .map(|input| {
+                if rng.gen_bool(self.rate) {
+                    BVal::new(0.0)
+                } else {
+                    input * scale
+                }
+            })
+            .collect()
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        Some(input_shape.to_vec())
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    fn rng(&self) -> Option<&RefCell<ChaCha8Rng>> {
+        Some(&self.rng)
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.rate)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward() {
+        let inputs = || (0..1000).map(|_| BVal::new(1.0)).collect::<Vec<_>>();
+
+        let mut l = Dropout::new(0.5);
+
+        // inference
+        let outputs = l.forward(inputs());
+        assert!(outputs.iter().all(|out| out.borrow().d == 1.0));
+
+        // training
+        l.set_training(true);
+
+        let outputs = l.forward(inputs());
+        let dropped = outputs.iter().filter(|out| out.borrow().d == 0.0).count();
+
+        assert!(dropped > 400 && dropped < 600);
+        assert!(outputs
+            .iter()
+            .all(|out| out.borrow().d == 0.0 || out.borrow().d == 2.0));
+    }
+
+    #[test]
+    fn forward_seeded() {
+        const SEED: u64 = 42;
+
+        let inputs = || (0..100).map(|n| BVal::new(n as f64)).collect::<Vec<_>>();
+
+        let mut l = Dropout::with_seed(0.3, SEED);
+        l.set_training(true);
+
+        let outputs: Vec<f64> = l.forward(inputs()).iter().map(|v| v.borrow().d).collect();
+
+        // mask is drawn from seeded generator, one value per input
+        let mut rng = ChaCha8Rng::seed_from_u64(SEED);
+        let expected: Vec<f64> = (0..100)
+            .map(|n| {
+                if rng.gen_bool(0.3) {
+                    0.0
+                } else {
+                    n as f64 * (1.0 / 0.7)
+                }
+            })
+            .collect();
+
+        assert_eq!(outputs, expected);
+
+        // same seed gives same mask
+        let mut l2 = Dropout::with_seed(0.3, SEED);
+        l2.set_training(true);
+
+        let outputs2: Vec<f64> = l2.forward(inputs()).iter().map(|v| v.borrow().d).collect();
+
+        assert_eq!(outputs, outputs2);
+    }
+}
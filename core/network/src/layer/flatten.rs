@@ -0,0 +1,63 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::error::NetworkError;
+
+use super::Layer;
+
+// turns inputs of any shape into one-dimensional list, e.g. to pass them to dense layer.
+// values are stored flat anyway, so only shape changes
+#[derive(Default)]
+pub struct Flatten {}
+
+impl Flatten {
+    pub const TAG: &'static str = tags::FLATTEN;
+
+    pub fn new() -> Self {
+        Flatten {}
+    }
+
+    pub(crate) fn deserialize(_reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        Ok(Flatten::new())
+    }
+}
+
+impl Layer for Flatten {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        inputs
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        Some(vec![input_shape.iter().product()])
+    }
+
+    fn serialize(&self, _writer: &mut dyn Write) -> Result<(), NetworkError> {
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_shape() {
+        let l = Flatten::new();
+
+        assert_eq!(l.output_shape(&[2, 3, 4]), Some(vec![24]));
+        assert_eq!(l.output_shape(&[5]), Some(vec![5]));
+    }
+}
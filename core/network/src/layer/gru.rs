@@ -0,0 +1,156 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::error::NetworkError;
+
+use super::{
+    dense::Dense,
+    recurrent::{self, RecurrentConfig},
+    Layer,
+};
+
+// gated recurrent unit:
+// z = sigmoid(Wz * [x, h] + bz), r = sigmoid(Wr * [x, h] + br),
+// n = tanh(Wn * [x, r * h] + bn), h = (1 - z) * n + z * h
+pub struct Gru {
+    config: RecurrentConfig,
+    update_gate: Dense,
+    reset_gate: Dense,
+    candidate: Dense,
+}
+
+impl Gru {
+    pub const TAG: &'static str = tags::GRU;
+
+    pub fn new(input_size: usize, hidden_size: usize) -> Self {
+        let config = RecurrentConfig::new(input_size, hidden_size);
+
+        Gru {
+            update_gate: config.new_gate(),
+            reset_gate: config.new_gate(),
+            candidate: config.new_gate(),
+            config,
+        }
+    }
+
+    // outputs hidden state after each step instead of the last one only
+    pub fn with_return_sequences(mut self, return_sequences: bool) -> Self {
+        self.config.return_sequences = return_sequences;
+        self
+    }
+
+    // truncates backprop through time to given number of steps
+    pub fn with_bptt_steps(mut self, steps: usize) -> Self {
+        assert!(steps > 0, "bptt steps should be positive");
+        self.config.bptt_steps = Some(steps);
+        self
+    }
+
+    pub fn config(&self) -> &RecurrentConfig {
+        &self.config
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let config = RecurrentConfig::deserialize(reader)?;
+        let mut gates = recurrent::deserialize_gates(&config, 3, reader)?.into_iter();
+
+        Ok(Gru {
+            config,
+            update_gate: gates.next().unwrap(),
+            reset_gate: gates.next().unwrap(),
+            candidate: gates.next().unwrap(),
+        })
+    }
+}
+
+impl Layer for Gru {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        self.config.unroll(inputs, 1, |input, states| {
+            let h = &states[0];
+            let input_state = recurrent::concat(input, h);
+
+            let z = self.update_gate.forward(input_state.clone());
+            let r = self.reset_gate.forward(input_state);
+
+            let reset_h: Vec<BVal> = r.iter().zip(h).map(|(r, h)| &r.sigmoid() * h).collect();
+            let n = self.candidate.forward(recurrent::concat(input, &reset_h));
+
+            let h = z
+                .iter()
+                .zip(n)
+                .zip(h)
+                .map(|((z, n), h)| {
+                    let z = z.sigmoid();
+                    let new = &(1.0 - &z) * &n.tanh();
+                    let old = &z * h;
+                    &new + &old
+                })
+                .collect();
+
+            vec![h]
+        })
+    }
+
+    fn parameters(&self) -> Vec<BVal> {
+        let mut res = self.update_gate.parameters();
+        res.extend(self.reset_gate.parameters());
+        res.extend(self.candidate.parameters());
+        res
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        self.config.output_shape(input_shape)
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        self.config.serialize(writer)?;
+        recurrent::serialize_gates(
+            &[&self.update_gate, &self.reset_gate, &self.candidate],
+            writer,
+        )
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::layer::check_gradients;
+
+    #[test]
+    fn forward() {
+        let l = Gru::new(2, 3).with_return_sequences(true);
+
+        let inputs: Vec<BVal> = [1.0, -1.0, 0.5, 2.0]
+            .iter()
+            .map(|d| BVal::new(*d))
+            .collect();
+        let outputs = l.forward(inputs);
+
+        assert_eq!(outputs.len(), 6);
+
+        // hidden state stays in (-1, 1), since it mixes previous state with tanh output
+        for out in outputs {
+            assert!(out.borrow().d.abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn backward() {
+        let l = Gru::new(2, 3).with_return_sequences(true);
+        check_gradients(&l, &[0.5, -1.0, 2.0, 0.0, 0.3, -0.7]);
+    }
+}
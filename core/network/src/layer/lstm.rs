@@ -0,0 +1,170 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::error::NetworkError;
+
+use super::{
+    dense::Dense,
+    recurrent::{self, RecurrentConfig},
+    Layer,
+};
+
+// long short-term memory layer:
+// i = sigmoid(Wi * [x, h] + bi), f = sigmoid(Wf * [x, h] + bf), o = sigmoid(Wo * [x, h] + bo),
+// g = tanh(Wg * [x, h] + bg), c = f * c + i * g, h = o * tanh(c)
+pub struct Lstm {
+    config: RecurrentConfig,
+    input_gate: Dense,
+    forget_gate: Dense,
+    output_gate: Dense,
+    candidate: Dense,
+}
+
+impl Lstm {
+    pub const TAG: &'static str = tags::LSTM;
+
+    pub fn new(input_size: usize, hidden_size: usize) -> Self {
+        let config = RecurrentConfig::new(input_size, hidden_size);
+
+        let forget_gate = config.new_gate();
+
+        // forget gate starts mostly open, so cell state is kept from the beginning of training
+        for neuron in &forget_gate.neurons {
+            neuron.bias.borrow_mut().d = 1.0;
+        }
+
+        Lstm {
+            input_gate: config.new_gate(),
+            forget_gate,
+            output_gate: config.new_gate(),
+            candidate: config.new_gate(),
+            config,
+        }
+    }
+
+    // outputs hidden state after each step instead of the last one only
+    pub fn with_return_sequences(mut self, return_sequences: bool) -> Self {
+        self.config.return_sequences = return_sequences;
+        self
+    }
+
+    // truncates backprop through time to given number of steps
+    pub fn with_bptt_steps(mut self, steps: usize) -> Self {
+        assert!(steps > 0, "bptt steps should be positive");
+        self.config.bptt_steps = Some(steps);
+        self
+    }
+
+    pub fn config(&self) -> &RecurrentConfig {
+        &self.config
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let config = RecurrentConfig::deserialize(reader)?;
+        let mut gates = recurrent::deserialize_gates(&config, 4, reader)?.into_iter();
+
+        Ok(Lstm {
+            config,
+            input_gate: gates.next().unwrap(),
+            forget_gate: gates.next().unwrap(),
+            output_gate: gates.next().unwrap(),
+            candidate: gates.next().unwrap(),
+        })
+    }
+}
+
+impl Layer for Lstm {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    // states are hidden state (output) and cell state
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        self.config.unroll(inputs, 2, |input, states| {
+            let input_state = recurrent::concat(input, &states[0]);
+
+            let i = self.input_gate.forward(input_state.clone());
+            let f = self.forget_gate.forward(input_state.clone());
+            let o = self.output_gate.forward(input_state.clone());
+            let g = self.candidate.forward(input_state);
+
+            let mut h = Vec::new();
+            let mut c = Vec::new();
+
+            for idx in 0..self.config.hidden_size {
+                let kept = &f[idx].sigmoid() * &states[1][idx];
+                let added = &i[idx].sigmoid() * &g[idx].tanh();
+                let cell = &kept + &added;
+
+                h.push(&o[idx].sigmoid() * &cell.tanh());
+                c.push(cell);
+            }
+
+            vec![h, c]
+        })
+    }
+
+    fn parameters(&self) -> Vec<BVal> {
+        let mut res = self.input_gate.parameters();
+        res.extend(self.forget_gate.parameters());
+        res.extend(self.output_gate.parameters());
+        res.extend(self.candidate.parameters());
+        res
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        self.config.output_shape(input_shape)
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        self.config.serialize(writer)?;
+        recurrent::serialize_gates(
+            &[
+                &self.input_gate,
+                &self.forget_gate,
+                &self.output_gate,
+                &self.candidate,
+            ],
+            writer,
+        )
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::layer::check_gradients;
+
+    #[test]
+    fn forward() {
+        let l = Lstm::new(2, 3);
+
+        let inputs: Vec<BVal> = [1.0, -1.0, 0.5, 2.0]
+            .iter()
+            .map(|d| BVal::new(*d))
+            .collect();
+        let outputs = l.forward(inputs);
+
+        assert_eq!(outputs.len(), 3);
+
+        for out in outputs {
+            assert!(out.borrow().d.abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn backward() {
+        let l = Lstm::new(2, 3).with_return_sequences(true);
+        check_gradients(&l, &[0.5, -1.0, 2.0, 0.0, 0.3, -0.7]);
+    }
+}
@@ -0,0 +1,219 @@
+use std::io::{Read, Write};
+
+use autograd::val::BVal;
+
+use crate::{error::NetworkError, utils};
+
+use super::{dense::Dense, Layer};
+
+// settings shared by recurrent layers. recurrent layer takes inputs of shape
+// [steps, input_size], ie. input vector for each step of the sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecurrentConfig {
+    pub input_size: usize,
+    pub hidden_size: usize,
+    // whether layer outputs hidden state after each step or only after the last one
+    pub return_sequences: bool,
+    // truncated backprop through time: state is detached from the graph each given number of
+    // steps, so gradients don't flow further back and long sequences don't build deep graphs
+    pub bptt_steps: Option<usize>,
+}
+
+impl RecurrentConfig {
+    pub(crate) fn new(input_size: usize, hidden_size: usize) -> Self {
+        assert!(
+            input_size > 0 && hidden_size > 0,
+            "recurrent layer sizes should be positive"
+        );
+
+        RecurrentConfig {
+            input_size,
+            hidden_size,
+            return_sequences: false,
+            bptt_steps: None,
+        }
+    }
+
+    pub(crate) fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        match input_shape {
+            [steps, input_size] if *steps > 0 && *input_size == self.input_size => {
+                if self.return_sequences {
+                    Some(vec![*steps, self.hidden_size])
+                } else {
+                    Some(vec![self.hidden_size])
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // runs step function over each input vector of the sequence, starting with zero states.
+    // step takes input vector and states (e.g. hidden state, and cell state for lstm) and returns
+    // new states, where the first one is the output of the step
+    pub(crate) fn unroll(
+        &self,
+        inputs: Vec<BVal>,
+        states_count: usize,
+        step: impl Fn(&[BVal], &[Vec<BVal>]) -> Vec<Vec<BVal>>,
+    ) -> Vec<BVal> {
+        assert_eq!(
+            inputs.len() % self.input_size,
+            0,
+            "invalid inputs size for recurrent layer"
+        );
+
+        let mut states = vec![vec![BVal::new(0.0); self.hidden_size]; states_count];
+        let mut outputs = Vec::new();
+
+        for (idx, input) in inputs.chunks_exact(self.input_size).enumerate() {
+            let truncate = matches!(
+                self.bptt_steps,
+                Some(steps) if idx > 0 && idx.checked_rem(steps) == Some(0)
+            );
+
+            if truncate {
+                states = states
+                    .iter()
+                    .map(|state| state.iter().map(|v| BVal::new(v.borrow().d)).collect())
+                    .collect();
+            }
+
+            states = step(input, &states);
+
+            if self.return_sequences {
+                outputs.extend(states[0].iter().cloned());
+            }
+        }
+
+        if self.return_sequences {
+            outputs
+        } else {
+            states.swap_remove(0)
+        }
+    }
+
+    pub(crate) fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_u32(writer, self.input_size as u32)?;
+        utils::write_u32(writer, self.hidden_size as u32)?;
+        utils::write_u32(writer, self.return_sequences as u32)?;
+        // zero means no truncation
+        utils::write_u32(writer, self.bptt_steps.unwrap_or(0) as u32)
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let input_size = utils::read_u32(reader)? as usize;
+        let hidden_size = utils::read_u32(reader)? as usize;
+
+        if input_size == 0 || hidden_size == 0 {
+            return Err(NetworkError::InvalidShape(vec![input_size, hidden_size]));
+        }
+
+        let return_sequences = match utils::read_u32(reader)? {
+            0 => false,
+            1 => true,
+            flag => {
+                return Err(NetworkError::InvalidLayer(format!(
+                    "invalid return sequences flag: {flag}"
+                )))
+            }
+        };
+
+        let bptt_steps = match utils::read_u32(reader)? {
+            0 => None,
+            steps => Some(steps as usize),
+        };
+
+        Ok(RecurrentConfig {
+            input_size,
+            hidden_size,
+            return_sequences,
+            bptt_steps,
+        })
+    }
+
+    // gate is dense layer from concatenated input vector and hidden state to hidden size
+    pub(crate) fn new_gate(&self) -> Dense {
+        Dense::new(self.input_size + self.hidden_size, self.hidden_size)
+    }
+}
+
+pub(crate) fn concat(input: &[BVal], state: &[BVal]) -> Vec<BVal> {
+    input.iter().chain(state).cloned().collect()
+}
+
+pub(crate) fn serialize_gates(
+    gates: &[&Dense],
+    writer: &mut dyn Write,
+) -> Result<(), NetworkError> {
+    for gate in gates {
+        for param in gate.parameters() {
+            let d = param.borrow().d;
+            utils::write_f64(writer, d)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn deserialize_gates(
+    config: &RecurrentConfig,
+    gates_count: usize,
+    reader: &mut dyn Read,
+) -> Result<Vec<Dense>, NetworkError> {
+    let inputs_count = config.input_size.checked_add(config.hidden_size);
+
+    let params_count = inputs_count
+        .and_then(|inputs_count| {
+            super::dense::get_parameters_count(inputs_count, config.hidden_size)
+        })
+        .and_then(|count| count.checked_mul(gates_count))
+        .ok_or_else(|| NetworkError::InvalidShape(vec![config.input_size, config.hidden_size]))?;
+
+    let params = utils::read_params(reader, params_count)?;
+
+    let gates: Vec<Dense> = (0..gates_count).map(|_| config.new_gate()).collect();
+
+    let gates_params = gates.iter().flat_map(|gate| gate.parameters());
+
+    for (param, d) in gates_params.zip(params) {
+        param.borrow_mut().d = d;
+    }
+
+    Ok(gates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_shape() {
+        let mut config = RecurrentConfig::new(3, 4);
+
+        assert_eq!(config.output_shape(&[5, 3]), Some(vec![4]));
+        assert_eq!(config.output_shape(&[5, 2]), None);
+        assert_eq!(config.output_shape(&[15]), None);
+        assert_eq!(config.output_shape(&[0, 3]), None);
+
+        config.return_sequences = true;
+        assert_eq!(config.output_shape(&[5, 3]), Some(vec![5, 4]));
+    }
+
+    #[test]
+    fn serialization() {
+        let mut config = RecurrentConfig::new(3, 4);
+        config.return_sequences = true;
+        config.bptt_steps = Some(5);
+
+        let mut bytes = Vec::new();
+        config.serialize(&mut bytes).unwrap();
+
+        let restored = RecurrentConfig::deserialize(&mut bytes.as_slice()).unwrap();
+        assert_eq!(restored, config);
+
+        // invalid return sequences flag
+        bytes[8] = 2;
+        let res = RecurrentConfig::deserialize(&mut bytes.as_slice());
+        assert!(matches!(res, Err(NetworkError::InvalidLayer(_))));
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::{get_shape_size, tags, MAX_DIMS};
+
+use crate::{error::NetworkError, utils};
+
+use super::Layer;
+
+// changes shape of inputs without changing their order, e.g. to pass flat image into
+// convolution layer as [channels, height, width]
+pub struct Reshape {
+    pub shape: Vec<usize>,
+}
+
+impl Reshape {
+    pub const TAG: &'static str = tags::RESHAPE;
+
+    pub fn new(shape: Vec<usize>) -> Self {
+        Reshape { shape }
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let dims = utils::read_u32(reader)? as usize;
+
+        if dims > MAX_DIMS {
+            return Err(NetworkError::InvalidLayer(format!(
+                "too many reshape dimensions: {dims}"
+            )));
+        }
+
+        let mut shape = Vec::new();
+        for _ in 0..dims {
+            shape.push(utils::read_u32(reader)? as usize);
+        }
+
+        // the same check as in inference crate, so both read the same files
+        if get_shape_size(&shape).is_none() {
+            return Err(NetworkError::InvalidLayer(format!(
+                "invalid reshape shape: {shape:?}"
+            )));
+        }
+
+        Ok(Reshape::new(shape))
+    }
+}
+
+impl Layer for Reshape {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        inputs
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        match get_shape_size(&self.shape) {
+            Some(size) if size == input_shape.iter().product::<usize>() => Some(self.shape.clone()),
+            _ => None,
+        }
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_u32(writer, self.shape.len() as u32)?;
+
+        for size in &self.shape {
+            utils::write_u32(writer, *size as u32)?;
+        }
+
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_shape() {
+        let l = Reshape::new(vec![1, 2, 3]);
+
+        assert_eq!(l.output_shape(&[6]), Some(vec![1, 2, 3]));
+        assert_eq!(l.output_shape(&[3, 2]), Some(vec![1, 2, 3]));
+        assert_eq!(l.output_shape(&[7]), None);
+
+        assert_eq!(Reshape::new(vec![]).output_shape(&[1]), None);
+        assert_eq!(Reshape::new(vec![2, 0]).output_shape(&[0]), None);
+    }
+
+    #[test]
+    fn deserialize_invalid_shape() {
+        let read = |shape: &[u32]| {
+            let mut bytes = Vec::new();
+            utils::write_u32(&mut bytes, shape.len() as u32).unwrap();
+            for size in shape {
+                utils::write_u32(&mut bytes, *size).unwrap();
+            }
+            Reshape::deserialize(&mut bytes.as_slice())
+        };
+
+        assert_eq!(read(&[2, 3]).unwrap().shape, vec![2, 3]);
+
+        for shape in [&[][..], &[2, 0], &[1; MAX_DIMS + 1]] {
+            assert!(matches!(read(shape), Err(NetworkError::InvalidLayer(_))));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+};
+
+use autograd::val::BVal;
+use network_format::tags;
+
+use crate::error::NetworkError;
+
+use super::{
+    dense::Dense,
+    recurrent::{self, RecurrentConfig},
+    Layer,
+};
+
+// vanilla (elman) recurrent layer: h = tanh(W * [x, h] + b)
+pub struct Rnn {
+    config: RecurrentConfig,
+    gate: Dense,
+}
+
+impl Rnn {
+    pub const TAG: &'static str = tags::RNN;
+
+    pub fn new(input_size: usize, hidden_size: usize) -> Self {
+        let config = RecurrentConfig::new(input_size, hidden_size);
+
+        Rnn {
+            gate: config.new_gate(),
+            config,
+        }
+    }
+
+    // outputs hidden state after each step instead of the last one only
+    pub fn with_return_sequences(mut self, return_sequences: bool) -> Self {
+        self.config.return_sequences = return_sequences;
+        self
+    }
+
+    // truncates backprop through time to given number of steps
+    pub fn with_bptt_steps(mut self, steps: usize) -> Self {
+        assert!(steps > 0, "bptt steps should be positive");
+        self.config.bptt_steps = Some(steps);
+        self
+    }
+
+    pub fn config(&self) -> &RecurrentConfig {
+        &self.config
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let config = RecurrentConfig::deserialize(reader)?;
+        let gate = recurrent::deserialize_gates(&config, 1, reader)?.swap_remove(0);
+
+        Ok(Rnn { config, gate })
+    }
+}
+
+impl Layer for Rnn {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn forward(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        self.config.unroll(inputs, 1, |input, states| {
+            let h = self
+                .gate
+                .forward(recurrent::concat(input, &states[0]))
+                .iter()
+                .map(|v| v.tanh())
+                .collect();
+
+            vec![h]
+        })
+    }
+
+    fn parameters(&self) -> Vec<BVal> {
+        self.gate.parameters()
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Option<Vec<usize>> {
+        self.config.output_shape(input_shape)
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        self.config.serialize(writer)?;
+        recurrent::serialize_gates(&[&self.gate], writer)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::layer::check_gradients;
+
+    #[test]
+    fn forward() {
+        let l = Rnn::new(2, 3);
+
+        let inputs: Vec<BVal> = [1.0, -1.0, 0.5, 2.0]
+            .iter()
+            .map(|d| BVal::new(*d))
+            .collect();
+
+        // second step takes hidden state of the first one
+        let h1 = l.forward(inputs[..2].to_vec());
+        let mut expected = inputs[2..].to_vec();
+        expected.extend(h1.iter().cloned());
+        let expected: Vec<f64> = l
+            .gate
+            .forward(expected)
+            .iter()
+            .map(|v| v.tanh().borrow().d)
+            .collect();
+
+        let outputs: Vec<f64> = l
+            .forward(inputs.clone())
+            .iter()
+            .map(|v| v.borrow().d)
+            .collect();
+        assert_eq!(outputs, expected);
+
+        let l = l.with_return_sequences(true);
+        let outputs = l.forward(inputs);
+        assert_eq!(outputs.len(), 6);
+        assert_eq!(outputs[0].borrow().d, h1[0].borrow().d);
+    }
+
+    #[test]
+    fn backward() {
+        let l = Rnn::new(2, 3).with_return_sequences(true);
+        check_gradients(&l, &[0.5, -1.0, 2.0, 0.0, 0.3, -0.7]);
+    }
+
+    #[test]
+    fn truncated_backward() {
+        let l = Rnn::new(1, 2).with_bptt_steps(2);
+
+        let inputs: Vec<BVal> = [0.5, -1.0, 2.0].iter().map(|d| BVal::new(*d)).collect();
+        let outputs = l.forward(inputs.clone());
+
+        let loss = &outputs[0] + &outputs[1];
+        loss.borrow_mut().grad = 1.0;
+        loss.backward();
+
+        // state is detached before the third step, so only its input gets gradient
+        assert_eq!(inputs[0].borrow().grad, 0.0);
+        assert_eq!(inputs[1].borrow().grad, 0.0);
+        assert_ne!(inputs[2].borrow().grad, 0.0);
+    }
+}
@@ -1,5 +1,26 @@
+pub mod dense_model;
+pub mod diff;
+pub mod error;
+pub mod graph;
+pub mod layer;
 pub mod network;
+pub mod online;
+pub mod optim;
+pub mod parallel;
+pub mod prune;
+pub mod quantized_model;
+pub mod registry;
+pub mod regularization;
+pub mod rng;
+pub mod scheduler;
+pub mod summary;
+pub mod trainer;
 
-mod layer;
+mod json;
 mod neuron;
+mod npz;
+mod onnx;
 mod utils;
+
+#[cfg(test)]
+mod test_utils;
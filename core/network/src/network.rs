@@ -1,23 +1,71 @@
 use std::{
+    fmt,
     fs::{self, File},
-    io::{BufReader, BufWriter, Read},
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
 };
 
 use autograd::val::BVal;
+use network_format::{
+    DENSE_FORMAT_VERSION, FORMAT_VERSION, MAGIC, MAX_LEGACY_LAYERS_COUNT, SPARSE_FORMAT_VERSION,
+};
 
-use crate::{layer::Layer, utils};
+use crate::{
+    error::NetworkError,
+    layer::{
+        self,
+        activation::Activation,
+        dense::{self, Dense},
+        Layer,
+    },
+    summary::NetworkSummary,
+    utils,
+};
 
+// sequential container of layers, where each layer takes outputs of previous one as inputs
 pub struct Network {
-    pub layers: Vec<Layer>,
+    input_shape: Vec<usize>,
+    pub layers: Vec<Box<dyn Layer>>,
     parameters: Vec<BVal>,
+    // whether each layer is updated by training, see set_trainable()
+    trainable: Vec<bool>,
+    trainable_parameters: Vec<BVal>,
+    // whether each parameter is pruned, see Network::prune()
+    pruned: Vec<bool>,
+    training: bool,
 }
 
 impl Network {
+    // creates fully-connected network with tanh activations
     pub fn new(layers_sizes: Vec<usize>) -> Network {
-        let mut layers = Vec::new();
+        let mut layers: Vec<Box<dyn Layer>> = Vec::new();
 
         for i in 0..(layers_sizes.len() - 1) {
-            layers.push(Layer::new(layers_sizes[i], layers_sizes[i + 1]))
+            layers.push(Box::new(Dense::new(layers_sizes[i], layers_sizes[i + 1])));
+            layers.push(Box::new(Activation::tanh()));
+        }
+
+        Network::from_layers(vec![layers_sizes[0]], layers).expect("failed to create network")
+    }
+
+    pub fn from_layers(
+        input_shape: Vec<usize>,
+        layers: Vec<Box<dyn Layer>>,
+    ) -> Result<Network, NetworkError> {
+        if input_shape.is_empty() || input_shape.contains(&0) || layers.is_empty() {
+            return Err(NetworkError::InvalidShape(input_shape));
+        }
+
+        // check that each layer can take outputs of previous one
+        let mut shape = input_shape.clone();
+
+        for layer in &layers {
+            shape = layer
+                .output_shape(&shape)
+                .ok_or_else(|| NetworkError::LayerShapeMismatch {
+                    layer: String::from(layer.tag()),
+                    input_shape: shape.clone(),
+                })?;
         }
 
         let mut parameters = Vec::new();
@@ -27,11 +75,30 @@ impl Network {
             }
         }
 
-        Network { layers, parameters }
+        let mut net = Network {
+            input_shape,
+            trainable: vec![true; layers.len()],
+            trainable_parameters: parameters.clone(),
+            pruned: vec![false; parameters.len()],
+            layers,
+            parameters,
+            training: false,
+        };
+
+        // network starts in eval mode, so loaded model can be used for inference right away
+        net.eval();
+
+        Ok(net)
     }
 
     pub fn forward(&self, inputs: &[f64]) -> Vec<BVal> {
-        let mut res: Vec<BVal> = inputs.iter().map(|v| BVal::new(*v)).collect();
+        self.forward_vals(inputs.iter().map(|v| BVal::new(*v)).collect())
+    }
+
+    // same as forward(), but inputs can be outputs of other graph, e.g. of another network, so
+    // gradients flow back through them
+    pub fn forward_vals(&self, inputs: Vec<BVal>) -> Vec<BVal> {
+        let mut res = inputs;
 
         for layer in &self.layers {
             res = layer.forward(res);
@@ -40,80 +107,352 @@ impl Network {
         res
     }
 
+    pub fn forward_batch(&self, inputs: &[Vec<f64>]) -> Vec<Vec<BVal>> {
+        let mut res: Vec<Vec<BVal>> = inputs
+            .iter()
+            .map(|inputs| inputs.iter().map(|v| BVal::new(*v)).collect())
+            .collect();
+
+        for layer in &self.layers {
+            res = layer.forward_batch(res);
+        }
+
+        res
+    }
+
+    // all parameters of the network, including ones of frozen layers
     pub fn parameters(&self) -> &Vec<BVal> {
         &self.parameters
     }
 
+    // parameters of layers which aren't frozen, these are updated by optimizer during training
+    pub fn trainable_parameters(&self) -> &Vec<BVal> {
+        &self.trainable_parameters
+    }
+
+    pub fn is_trainable(&self, layer_idx: usize) -> bool {
+        self.trainable[layer_idx]
+    }
+
+    // whether each parameter (in the order of parameters()) is pruned, see prune()
+    pub fn pruning_mask(&self) -> &[bool] {
+        &self.pruned
+    }
+
+    pub(crate) fn set_pruning_mask(&mut self, pruned: Vec<bool>) {
+        assert_eq!(pruned.len(), self.parameters.len(), "invalid mask size");
+        self.pruned = pruned;
+    }
+
+    // frozen layer keeps its parameters during training and always runs in eval mode, so e.g.
+    // batch norm of pretrained layers doesn't update its running statistics
+    pub fn set_trainable(&mut self, layer_idx: usize, trainable: bool) {
+        assert!(layer_idx < self.layers.len(), "invalid layer index");
+
+        self.trainable[layer_idx] = trainable;
+        self.update_trainable();
+    }
+
+    // freezes all current layers, e.g. before replacing head of pretrained network
+    pub fn freeze(&mut self) {
+        self.trainable.fill(false);
+        self.update_trainable();
+    }
+
+    pub fn unfreeze(&mut self) {
+        self.trainable.fill(true);
+        self.update_trainable();
+    }
+
+    fn update_trainable(&mut self) {
+        self.trainable_parameters = self
+            .layers
+            .iter()
+            .zip(&self.trainable)
+            .filter(|(_, trainable)| **trainable)
+            .flat_map(|(layer, _)| layer.parameters())
+            .collect();
+
+        // frozen layers switch to eval mode
+        self.set_training(self.training);
+    }
+
+    // keeps first layers (with their parameters and trainable flags) and appends new ones, e.g.
+    // to fine-tune pretrained network for other number of classes. new layers are trainable
+    pub fn replace_head(
+        self,
+        keep_layers: usize,
+        head: Vec<Box<dyn Layer>>,
+    ) -> Result<Network, NetworkError> {
+        assert!(keep_layers <= self.layers.len(), "invalid layers count");
+
+        let kept_parameters_count: usize = self.layers[..keep_layers]
+            .iter()
+            .map(|layer| layer.parameters().len())
+            .sum();
+
+        let Network {
+            input_shape,
+            mut layers,
+            mut trainable,
+            mut pruned,
+            training,
+            ..
+        } = self;
+
+        layers.truncate(keep_layers);
+        trainable.truncate(keep_layers);
+        trainable.resize(keep_layers + head.len(), true);
+        layers.extend(head);
+
+        let mut net = Network::from_layers(input_shape, layers)?;
+        net.trainable = trainable;
+        net.training = training;
+
+        // kept layers remain pruned
+        pruned.truncate(kept_parameters_count);
+        pruned.resize(net.parameters.len(), false);
+        net.pruned = pruned;
+
+        net.update_trainable();
+
+        Ok(net)
+    }
+
+    // enables training behavior of layers, e.g. dropout
+    pub fn train(&mut self) {
+        self.set_training(true);
+    }
+
+    // disables training behavior of layers, so forward pass is deterministic
+    pub fn eval(&mut self) {
+        self.set_training(false);
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+
+        for (layer, trainable) in self.layers.iter_mut().zip(&self.trainable) {
+            layer.set_training(training && *trainable);
+        }
+    }
+
     pub fn reset_grad(&self) {
         for param in self.parameters() {
             param.borrow_mut().grad = 0.0;
         }
     }
 
-    fn get_layer_sizes(&self) -> Vec<usize> {
+    pub fn input_shape(&self) -> &[usize] {
+        &self.input_shape
+    }
+
+    pub fn output_shape(&self) -> Vec<usize> {
+        self.layers
+            .iter()
+            .fold(self.input_shape.clone(), |shape, layer| {
+                layer
+                    .output_shape(&shape)
+                    .expect("layers shapes are checked on network creation")
+            })
+    }
+
+    // shapes, parameters counts and weight statistics of each layer
+    pub fn summary(&self) -> NetworkSummary {
+        NetworkSummary::new(self)
+    }
+
+    // sizes of network inputs and outputs of each dense layer
+    pub(crate) fn get_layer_sizes(&self) -> Vec<usize> {
         let mut layers_sizes: Vec<usize> = Vec::new();
 
         // input size
-        layers_sizes.push(self.layers[0].neurons[0].weights.len());
+        layers_sizes.push(self.input_shape.iter().product());
 
         for layer in &self.layers {
-            layers_sizes.push(layer.neurons.len());
+            if let Some(dense) = layer.as_any().downcast_ref::<Dense>() {
+                layers_sizes.push(dense.outputs_count());
+            }
         }
 
         layers_sizes
     }
 
-    fn serialize_to_file_path(&self, path: &str) {
-        let file = File::create(path).expect("failed to create file");
-        let mut writer = BufWriter::new(file);
+    pub fn serialize_to_writer(&self, mut writer: impl Write) -> Result<(), NetworkError> {
+        writer.write_all(&MAGIC)?;
 
-        // write network structure
-        utils::write_u32(&mut writer, self.layers.len() as u32);
-        utils::write_u32(&mut writer, self.layers[0].neurons[0].weights.len() as u32);
+        if self.pruned.contains(&true) {
+            utils::write_u32(&mut writer, SPARSE_FORMAT_VERSION)?;
 
-        for layer in &self.layers {
-            utils::write_u32(&mut writer, layer.neurons.len() as u32);
+            let mut bytes = Vec::new();
+            self.serialize_layers(&mut bytes)?;
+            utils::write_zero_runs(&mut writer, &bytes)?;
+
+            // bit of each parameter, in the order of parameters()
+            let mask: Vec<u8> = self
+                .pruned
+                .chunks(8)
+                .map(|bits| {
+                    bits.iter()
+                        .enumerate()
+                        .fold(0, |byte, (idx, pruned)| byte | (*pruned as u8) << idx)
+                })
+                .collect();
+            writer.write_all(&mask)?;
+        } else {
+            utils::write_u32(&mut writer, FORMAT_VERSION)?;
+            self.serialize_layers(&mut writer)?;
         }
 
-        // write params
-        for param in self.parameters() {
-            let d = param.borrow().d;
-            utils::write_f64(&mut writer, d);
+        writer.flush()?;
+        Ok(())
+    }
+
+    // model file contents, e.g. to store network updated in the browser
+    pub fn to_bytes(&self) -> Result<Vec<u8>, NetworkError> {
+        let mut bytes = Vec::new();
+        self.serialize_to_writer(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn serialize_layers(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        // write input shape
+        utils::write_u32(writer, self.input_shape.len() as u32)?;
+
+        for size in &self.input_shape {
+            utils::write_u32(writer, *size as u32)?;
+        }
+
+        // write layers
+        utils::write_u32(writer, self.layers.len() as u32)?;
+
+        for layer in &self.layers {
+            layer::serialize_layer(layer.as_ref(), writer)?;
         }
+
+        Ok(())
+    }
+
+    pub(crate) fn serialize_to_file_path(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(), NetworkError> {
+        let file = File::create(path)?;
+        self.serialize_to_writer(BufWriter::new(file))
     }
 
-    pub fn serialize_to_file(&self, dir: &str, file_name_prefix: &str) {
+    pub fn serialize_to_file(&self, dir: &str, file_name_prefix: &str) -> Result<(), NetworkError> {
         let path = utils::get_model_file_path(dir, file_name_prefix, &self.get_layer_sizes());
         println!("serializing network to file: {}", path);
-        self.serialize_to_file_path(&path);
+        self.serialize_to_file_path(&path)
+    }
+
+    pub fn deserialize_from_reader(mut reader: impl Read) -> Result<Self, NetworkError> {
+        // read header
+        let magic = utils::read_magic(&mut reader)?;
+
+        let net = if magic == MAGIC {
+            let version = utils::read_u32(&mut reader)?;
+
+            match version {
+                FORMAT_VERSION => Self::deserialize_layers(&mut reader)?,
+                SPARSE_FORMAT_VERSION => Self::deserialize_sparse(&mut reader)?,
+                DENSE_FORMAT_VERSION => {
+                    let layers_count = utils::read_u32(&mut reader)?;
+                    Self::deserialize_dense_layers(&mut reader, layers_count)?
+                }
+                _ => return Err(NetworkError::UnsupportedVersion(version)),
+            }
+        } else {
+            // files written before header was introduced start with layers count right away
+            let layers_count = u32::from_ne_bytes(magic);
+
+            if layers_count == 0 || layers_count > MAX_LEGACY_LAYERS_COUNT {
+                return Err(NetworkError::BadMagic(magic));
+            }
+
+            Self::deserialize_dense_layers(&mut reader, layers_count)?
+        };
+
+        utils::ensure_eof(&mut reader)?;
+
+        Ok(net)
     }
 
-    pub fn deserialize_from_reader(mut reader: impl Read) -> Self {
+    fn deserialize_layers(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        // read input shape
+        let dims = utils::read_u32(reader)?;
+
+        let mut input_shape = Vec::new();
+        for _ in 0..dims {
+            input_shape.push(utils::read_u32(reader)? as usize);
+        }
+
+        // read layers
+        let layers_count = utils::read_u32(reader)?;
+
+        let mut layers = Vec::new();
+        for _ in 0..layers_count {
+            layers.push(layer::deserialize_layer(reader)?);
+        }
+
+        Network::from_layers(input_shape, layers)
+    }
+
+    fn deserialize_sparse(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let bytes = utils::read_zero_runs(reader)?;
+
+        let mut layers_reader = bytes.as_slice();
+        let mut net = Self::deserialize_layers(&mut layers_reader)?;
+        utils::ensure_eof(&mut layers_reader)?;
+
+        let mut mask = vec![0u8; net.pruned.chunks(8).len()];
+        reader.read_exact(&mut mask)?;
+
+        for (byte, bits) in mask.iter().zip(net.pruned.chunks_mut(8)) {
+            for (idx, pruned) in bits.iter_mut().enumerate() {
+                *pruned = byte & (1 << idx) != 0;
+            }
+        }
+
+        Ok(net)
+    }
+
+    fn deserialize_dense_layers(
+        reader: &mut dyn Read,
+        layers_count: u32,
+    ) -> Result<Self, NetworkError> {
         // read network structure
-        let layers_count = utils::read_u32(&mut reader);
         let mut layers_sizes: Vec<usize> = Vec::new();
 
-        let inputs_size = utils::read_u32(&mut reader);
+        let inputs_size = utils::read_u32(reader)?;
         layers_sizes.push(inputs_size as usize);
 
         for _ in 0..layers_count {
-            layers_sizes.push(utils::read_u32(&mut reader) as usize);
+            layers_sizes.push(utils::read_u32(reader)? as usize);
         }
 
+        let params_count = get_parameters_count(&layers_sizes)
+            .ok_or_else(|| NetworkError::InvalidShape(layers_sizes.clone()))?;
+
+        let params = utils::read_params(reader, params_count)?;
+
         // init network
         let net = Network::new(layers_sizes);
 
-        // read params
-        for param in net.parameters() {
-            let d = utils::read_f64(&mut reader);
+        for (param, d) in net.parameters().iter().zip(params) {
             param.borrow_mut().d = d;
         }
 
-        net
+        Ok(net)
     }
 
-    fn deserialize_from_file_path(path: &str) -> Self {
-        let file = File::open(path).expect("failed to open file");
+    pub(crate) fn deserialize_from_file_path(path: impl AsRef<Path>) -> Result<Self, NetworkError> {
+        let file = File::open(path)?;
         let reader = BufReader::new(file);
 
         Self::deserialize_from_reader(reader)
@@ -126,20 +465,73 @@ impl Network {
     ) -> Network {
         let path = utils::get_model_file_path(dir, file_name_prefix, &layers_sizes);
 
-        if fs::metadata(&path).is_ok() {
-            println!("deserializing network from file: {}", path);
-            Network::deserialize_from_file_path(&path)
-        } else {
+        if fs::metadata(&path).is_err() {
             println!("initializing network: {:?}", layers_sizes);
-            Network::new(layers_sizes)
+            return Network::new(layers_sizes);
         }
+
+        println!("deserializing network from file: {}", path);
+
+        let net = Network::deserialize_from_file_path(&path).and_then(|net| {
+            let actual = net.get_layer_sizes();
+
+            if actual == layers_sizes {
+                Ok(net)
+            } else {
+                Err(NetworkError::ShapeMismatch {
+                    expected: layers_sizes.clone(),
+                    actual,
+                })
+            }
+        });
+
+        match net {
+            Ok(net) => net,
+            Err(err) => {
+                println!(
+                    "failed to deserialize network ({}), initializing network: {:?}",
+                    err, layers_sizes
+                );
+                Network::new(layers_sizes)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
     }
 }
 
+fn get_parameters_count(layers_sizes: &[usize]) -> Option<usize> {
+    if layers_sizes.len() < 2 || layers_sizes.contains(&0) {
+        return None;
+    }
+
+    let mut count: usize = 0;
+
+    for sizes in layers_sizes.windows(2) {
+        let layer_count = dense::get_parameters_count(sizes[0], sizes[1])?;
+        count = count.checked_add(layer_count)?;
+    }
+
+    Some(count)
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
 
+    use crate::{
+        layer::{
+            avg_pool2d::AvgPool2D, batch_norm1d::BatchNorm1D, conv2d::Conv2D, dropout::Dropout,
+            flatten::Flatten, gru::Gru, lstm::Lstm, max_pool2d::MaxPool2D, reshape::Reshape,
+            rnn::Rnn,
+        },
+        rng,
+    };
+
     use super::*;
 
     #[test]
@@ -198,24 +590,210 @@ mod tests {
         assert!(last_total_loss.borrow().d < 0.1);
     }
 
+    #[test]
+    fn classification_cnn() {
+        // fixed initialization, so convergence doesn't depend on luck
+        rng::seed(1);
+
+        // 6x6 images with vertical line (expected 1) or horizontal line (expected -1)
+        let mut samples = Vec::new();
+
+        for line_idx in 0..6 {
+            let mut vertical = vec![0.0; 36];
+            let mut horizontal = vec![0.0; 36];
+
+            for idx in 0..6 {
+                vertical[idx * 6 + line_idx] = 1.0;
+                horizontal[line_idx * 6 + idx] = 1.0;
+            }
+
+            samples.push((vertical, 1.0));
+            samples.push((horizontal, -1.0));
+        }
+
+        let net = Network::from_layers(
+            vec![36],
+            vec![
+                Box::new(Reshape::new(vec![1, 6, 6])),
+                Box::new(Conv2D::new([1, 6, 6], 4, 3, 1, 1)),
+                Box::new(Activation::relu()),
+                Box::new(MaxPool2D::new([4, 6, 6], 2, 2)),
+                Box::new(AvgPool2D::new([4, 3, 3], 2, 1)),
+                Box::new(Flatten::new()),
+                Box::new(Dense::new(16, 1)),
+                Box::new(Activation::tanh()),
+            ],
+        )
+        .expect("failed to create network");
+
+        let mut last_total_loss = BVal::new(0.0);
+
+        for _ in 0..100 {
+            // forward
+            let mut total_loss = BVal::new(0.0);
+            for (input, expected) in &samples {
+                let output = &net.forward(input)[0];
+                let loss = (*expected - output).pow(2.0);
+                total_loss = &total_loss + &loss;
+                last_total_loss = total_loss.clone();
+            }
+
+            // backward
+            net.reset_grad();
+
+            total_loss.borrow_mut().grad = 1.0;
+            total_loss.backward();
+
+            // update
+            for param in net.parameters() {
+                let grad = param.borrow().grad;
+                param.borrow_mut().d -= 0.05 * grad;
+            }
+        }
+
+        assert!(last_total_loss.borrow().d < 0.1);
+    }
+
+    #[test]
+    fn classification_rnn() {
+        // fixed initialization, so convergence doesn't depend on luck
+        rng::seed(1);
+
+        // sequences of 4 values, where expected output is the first value, so it has to be kept
+        // in hidden state until the end of the sequence
+        let samples: Vec<(Vec<f64>, f64)> = (0..16)
+            .map(|n| {
+                let input: Vec<f64> = (0..4)
+                    .map(|bit| if n & (1 << bit) != 0 { 1.0 } else { -1.0 })
+                    .collect();
+                let expected = input[0];
+                (input, expected)
+            })
+            .collect();
+
+        let recurrent_layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Rnn::new(1, 4)),
+            Box::new(Gru::new(1, 4)),
+            Box::new(Lstm::new(1, 4)),
+        ];
+
+        for recurrent in recurrent_layers {
+            let tag = recurrent.tag();
+
+            let net = Network::from_layers(
+                vec![4, 1],
+                vec![
+                    recurrent,
+                    Box::new(Dense::new(4, 1)),
+                    Box::new(Activation::tanh()),
+                ],
+            )
+            .expect("failed to create network");
+
+            let mut last_total_loss = BVal::new(0.0);
+
+            for _ in 0..100 {
+                // forward
+                let mut total_loss = BVal::new(0.0);
+                for (input, expected) in &samples {
+                    let output = &net.forward(input)[0];
+                    let loss = (*expected - output).pow(2.0);
+                    total_loss = &total_loss + &loss;
+                }
+                last_total_loss = total_loss.clone();
+
+                // backward
+                net.reset_grad();
+
+                total_loss.borrow_mut().grad = 1.0;
+                total_loss.backward();
+
+                // update
+                for param in net.parameters() {
+                    let grad = param.borrow().grad;
+                    param.borrow_mut().d -= 0.05 * grad;
+                }
+            }
+
+            assert!(
+                last_total_loss.borrow().d < 0.5,
+                "layer: {tag}, loss: {}",
+                last_total_loss.borrow().d
+            );
+
+            let bytes = serialize_to_bytes(&net);
+            let net2 = Network::deserialize_from_reader(bytes.as_slice())
+                .expect("failed to deserialize network");
+
+            for (input, _) in &samples {
+                assert_eq!(
+                    net.forward(input)[0].borrow().d,
+                    net2.forward(input)[0].borrow().d
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn serialization_lenet() {
+        let net1 = Network::from_layers(
+            vec![784],
+            vec![
+                Box::new(Reshape::new(vec![1, 28, 28])),
+                Box::new(Conv2D::new([1, 28, 28], 6, 5, 1, 2)),
+                Box::new(Activation::relu()),
+                Box::new(MaxPool2D::new([6, 28, 28], 2, 2)),
+                Box::new(Conv2D::new([6, 14, 14], 16, 5, 1, 0)),
+                Box::new(Activation::relu()),
+                Box::new(MaxPool2D::new([16, 10, 10], 2, 2)),
+                Box::new(Flatten::new()),
+                Box::new(Dense::new(400, 120)),
+                Box::new(Activation::relu()),
+                Box::new(Dense::new(120, 84)),
+                Box::new(Activation::relu()),
+                Box::new(Dense::new(84, 10)),
+                Box::new(Activation::tanh()),
+            ],
+        )
+        .expect("failed to create network");
+
+        let bytes = serialize_to_bytes(&net1);
+        let net2 = Network::deserialize_from_reader(bytes.as_slice())
+            .expect("failed to deserialize network");
+
+        assert_eq!(net2.output_shape(), vec![10]);
+
+        let input: Vec<f64> = (0..784).map(|idx| (idx as f64 * 0.1).sin()).collect();
+        let outputs1 = net1.forward(&input);
+        let outputs2 = net2.forward(&input);
+
+        for (out1, out2) in outputs1.iter().zip(outputs2.iter()) {
+            assert_eq!(out1.borrow().d, out2.borrow().d);
+        }
+    }
+
     #[test]
     fn serialization() {
         const FILE_PATH: &str = "test.nm";
 
         let net1 = Network::new(vec![3, 4, 4, 1]);
-        net1.serialize_to_file_path(FILE_PATH);
+        net1.serialize_to_file_path(FILE_PATH)
+            .expect("failed to serialize network");
 
-        let net2 = Network::deserialize_from_file_path(FILE_PATH);
+        let net2 =
+            Network::deserialize_from_file_path(FILE_PATH).expect("failed to deserialize network");
 
         fs::remove_file(FILE_PATH).expect("failed to remove file");
 
         let layers1 = &net1.layers;
-        let layers2 = &net1.layers;
+        let layers2 = &net2.layers;
+
+        assert_eq!(layers1.len(), layers2.len());
 
         let layers = layers1.iter().zip(layers2.iter());
 
         for (layer1, layer2) in layers {
-            assert_eq!(layer1.neurons.len(), layer2.neurons.len());
+            assert_eq!(layer1.tag(), layer2.tag());
         }
 
         let params1 = net1.parameters();
@@ -231,4 +809,442 @@ mod tests {
             assert_eq!(param1.borrow().grad, param2.borrow().grad);
         }
     }
+
+    fn serialize_to_bytes(net: &Network) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        net.serialize_to_writer(&mut bytes)
+            .expect("failed to serialize network");
+        bytes
+    }
+
+    #[test]
+    fn deserialization_legacy() {
+        let net1 = Network::new(vec![3, 4, 1]);
+
+        // legacy format: no header, layers count goes first
+        let mut bytes = Vec::new();
+        for n in [2u32, 3, 4, 1] {
+            bytes.extend(n.to_ne_bytes());
+        }
+        for param in net1.parameters() {
+            bytes.extend(param.borrow().d.to_ne_bytes());
+        }
+
+        let net2 = Network::deserialize_from_reader(bytes.as_slice())
+            .expect("failed to deserialize network");
+
+        assert_eq!(net2.get_layer_sizes(), vec![3, 4, 1]);
+
+        for (param1, param2) in net1.parameters().iter().zip(net2.parameters().iter()) {
+            assert_eq!(param1.borrow().d, param2.borrow().d);
+        }
+    }
+
+    #[test]
+    fn deserialization_dense_format() {
+        let net1 = Network::new(vec![3, 4, 1]);
+
+        // version 1: header, then layers sizes and params of dense layers
+        let mut bytes = MAGIC.to_vec();
+        for n in [DENSE_FORMAT_VERSION, 2, 3, 4, 1] {
+            bytes.extend(n.to_ne_bytes());
+        }
+        for param in net1.parameters() {
+            bytes.extend(param.borrow().d.to_ne_bytes());
+        }
+
+        let net2 = Network::deserialize_from_reader(bytes.as_slice())
+            .expect("failed to deserialize network");
+
+        let input = [1.0, -2.0, 0.5];
+        assert_eq!(
+            net1.forward(&input)[0].borrow().d,
+            net2.forward(&input)[0].borrow().d
+        );
+    }
+
+    fn create_mixed_layers_network() -> Network {
+        Network::from_layers(
+            vec![2, 3],
+            vec![
+                Box::new(Flatten::new()),
+                Box::new(Dense::new(6, 4)),
+                Box::new(Activation::relu()),
+                Box::new(Dropout::new(0.2)),
+                Box::new(Reshape::new(vec![2, 2])),
+                Box::new(Flatten::new()),
+                Box::new(Dense::new(4, 2)),
+                Box::new(Activation::sigmoid()),
+            ],
+        )
+        .expect("failed to create network")
+    }
+
+    #[test]
+    fn from_layers() {
+        let net = create_mixed_layers_network();
+
+        assert_eq!(net.input_shape(), &[2, 3]);
+        assert_eq!(net.output_shape(), vec![2]);
+        assert_eq!(net.parameters().len(), 7 * 4 + 5 * 2);
+        assert_eq!(net.get_layer_sizes(), vec![6, 4, 2]);
+
+        let outputs = net.forward(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        assert_eq!(outputs.len(), 2);
+
+        for out in outputs {
+            assert!(out.borrow().d > 0.0 && out.borrow().d < 1.0);
+        }
+    }
+
+    #[test]
+    fn from_layers_shape_mismatch() {
+        let res = Network::from_layers(
+            vec![2, 3],
+            vec![Box::new(Dense::new(6, 4)), Box::new(Activation::relu())],
+        );
+
+        assert!(matches!(
+            res,
+            Err(NetworkError::LayerShapeMismatch { layer, input_shape })
+                if layer == Dense::TAG && input_shape == vec![2, 3]
+        ));
+    }
+
+    #[test]
+    fn forward_batch() {
+        let net = Network::new(vec![3, 4, 2]);
+
+        let inputs = vec![vec![1.0, 2.0, 3.0], vec![-1.0, 0.5, 0.0]];
+        let outputs = net.forward_batch(&inputs);
+
+        assert_eq!(outputs.len(), 2);
+
+        for (input, output) in inputs.iter().zip(outputs) {
+            let expected = net.forward(input);
+
+            for (out, expected) in output.iter().zip(expected) {
+                assert_eq!(out.borrow().d, expected.borrow().d);
+            }
+        }
+    }
+
+    #[test]
+    fn forward_vals() {
+        let encoder = Network::new(vec![3, 2]);
+        let decoder = Network::new(vec![2, 3]);
+
+        let hidden = encoder.forward(&[1.0, 2.0, 3.0]);
+        let outputs = decoder.forward_vals(hidden);
+
+        let expected = decoder.forward(
+            &encoder
+                .forward(&[1.0, 2.0, 3.0])
+                .iter()
+                .map(|v| v.borrow().d)
+                .collect::<Vec<f64>>(),
+        );
+
+        for (out, expected) in outputs.iter().zip(expected) {
+            assert_eq!(out.borrow().d, expected.borrow().d);
+        }
+
+        // gradients reach parameters of the first network
+        outputs[0].borrow_mut().grad = 1.0;
+        outputs[0].backward();
+
+        assert!(encoder.parameters().iter().any(|p| p.borrow().grad != 0.0));
+    }
+
+    #[test]
+    fn classification_batch_norm() {
+        let inputs: Vec<Vec<f64>> = vec![
+            vec![20.0, 30.0, -10.0],
+            vec![30.0, -10.0, 5.0],
+            vec![5.0, 10.0, 10.0],
+            vec![10.0, 10.0, -10.0],
+        ];
+        let expecteds: Vec<f64> = vec![1.0, -1.0, -1.0, 1.0];
+
+        let mut net = Network::from_layers(
+            vec![3],
+            vec![
+                Box::new(BatchNorm1D::new(3)),
+                Box::new(Dense::new(3, 4)),
+                Box::new(Activation::tanh()),
+                Box::new(Dense::new(4, 1)),
+                Box::new(Activation::tanh()),
+            ],
+        )
+        .expect("failed to create network");
+
+        net.train();
+
+        let mut last_total_loss = BVal::new(0.0);
+
+        for _ in 0..300 {
+            // forward
+            let outputs = net.forward_batch(&inputs);
+
+            let mut total_loss = BVal::new(0.0);
+            for (output, expected) in outputs.iter().zip(expecteds.iter()) {
+                let loss = (*expected - &output[0]).pow(2.0);
+                total_loss = &total_loss + &loss;
+            }
+            last_total_loss = total_loss.clone();
+
+            // backward
+            net.reset_grad();
+
+            total_loss.borrow_mut().grad = 1.0;
+            total_loss.backward();
+
+            // update
+            for param in net.parameters() {
+                let grad = param.borrow().grad;
+                param.borrow_mut().d -= 0.05 * grad;
+            }
+        }
+
+        assert!(last_total_loss.borrow().d < 0.1);
+
+        // running statistics are saved with the model, so eval mode gives same predictions
+        // after deserialization
+        net.eval();
+
+        let bytes = serialize_to_bytes(&net);
+        let net2 = Network::deserialize_from_reader(bytes.as_slice())
+            .expect("failed to deserialize network");
+
+        for (input, expected) in inputs.iter().zip(expecteds.iter()) {
+            let output1 = net.forward(input)[0].borrow().d;
+            let output2 = net2.forward(input)[0].borrow().d;
+
+            assert_eq!(output1, output2);
+            assert_eq!(output1.signum(), *expected);
+        }
+    }
+
+    #[test]
+    fn train_eval() {
+        let input: Vec<f64> = (0..100).map(|n| n as f64).collect();
+
+        let mut net = Network::from_layers(
+            vec![100],
+            vec![
+                Box::new(Dropout::with_seed(0.5, 42)),
+                Box::new(Dropout::with_seed(0.5, 43)),
+            ],
+        )
+        .expect("failed to create network");
+
+        let forward = |net: &Network| -> Vec<f64> {
+            net.forward(&input).iter().map(|v| v.borrow().d).collect()
+        };
+
+        // dropout is inactive in eval mode
+        assert!(!net.is_training());
+        assert_eq!(forward(&net), input);
+
+        net.train();
+        assert!(net.is_training());
+
+        let outputs = forward(&net);
+        assert_ne!(outputs, input);
+        assert!(outputs.contains(&0.0));
+
+        net.eval();
+        assert_eq!(forward(&net), input);
+    }
+
+    #[test]
+    fn trainable() {
+        let mut net = Network::from_layers(
+            vec![3],
+            vec![
+                Box::new(Dense::new(3, 4)),
+                Box::new(Dropout::with_seed(0.5, 42)),
+                Box::new(Dense::new(4, 2)),
+            ],
+        )
+        .expect("failed to create network");
+
+        assert_eq!(net.trainable_parameters().len(), 26);
+
+        net.set_trainable(0, false);
+        net.set_trainable(1, false);
+
+        assert!(!net.is_trainable(0));
+        assert!(net.is_trainable(2));
+        assert_eq!(net.parameters().len(), 26);
+        assert_eq!(net.trainable_parameters().len(), 10);
+
+        // frozen dropout stays inactive
+        net.train();
+        let input = [1.0, 2.0, 3.0];
+        let output1: Vec<f64> = net.forward(&input).iter().map(|v| v.borrow().d).collect();
+        let output2: Vec<f64> = net.forward(&input).iter().map(|v| v.borrow().d).collect();
+        assert_eq!(output1, output2);
+
+        net.freeze();
+        assert!(net.trainable_parameters().is_empty());
+
+        net.unfreeze();
+        assert_eq!(net.trainable_parameters().len(), 26);
+    }
+
+    #[test]
+    fn replace_head() {
+        let mut net = Network::new(vec![3, 4, 2]);
+        net.freeze();
+
+        let input = [1.0, -2.0, 0.5];
+        let hidden: Vec<f64> = net.layers[..2]
+            .iter()
+            .fold(
+                input.iter().map(|d| BVal::new(*d)).collect(),
+                |res, layer| layer.forward(res),
+            )
+            .iter()
+            .map(|v| v.borrow().d)
+            .collect();
+
+        let net = net
+            .replace_head(
+                2,
+                vec![Box::new(Dense::new(4, 5)), Box::new(Activation::sigmoid())],
+            )
+            .expect("failed to replace head");
+
+        assert_eq!(net.output_shape(), vec![5]);
+        assert_eq!(net.parameters().len(), 16 + 25);
+        assert_eq!(net.trainable_parameters().len(), 25);
+        assert!(!net.is_trainable(1));
+        assert!(net.is_trainable(2));
+
+        // kept layers are the same
+        let head = &net.layers[2];
+        let expected: Vec<f64> = head
+            .forward(hidden.iter().map(|d| BVal::new(*d)).collect())
+            .iter()
+            .map(|v| net.layers[3].forward(vec![v.clone()])[0].borrow().d)
+            .collect();
+        let outputs: Vec<f64> = net.forward(&input).iter().map(|v| v.borrow().d).collect();
+        assert_eq!(outputs, expected);
+
+        // fine-tuned network is saved as any other
+        let restored = Network::deserialize_from_reader(serialize_to_bytes(&net).as_slice())
+            .expect("failed to deserialize network");
+        let restored: Vec<f64> = restored
+            .forward(&input)
+            .iter()
+            .map(|v| v.borrow().d)
+            .collect();
+        assert_eq!(restored, outputs);
+
+        // new head should take outputs of kept layers
+        let res = net.replace_head(2, vec![Box::new(Dense::new(3, 5))]);
+        assert!(matches!(
+            res,
+            Err(NetworkError::LayerShapeMismatch { input_shape, .. }) if input_shape == vec![4]
+        ));
+    }
+
+    #[test]
+    fn serialization_mixed_layers() {
+        let net1 = create_mixed_layers_network();
+        let bytes = serialize_to_bytes(&net1);
+
+        let net2 = Network::deserialize_from_reader(bytes.as_slice())
+            .expect("failed to deserialize network");
+
+        assert_eq!(net2.input_shape(), net1.input_shape());
+
+        let tags1: Vec<_> = net1.layers.iter().map(|layer| layer.tag()).collect();
+        let tags2: Vec<_> = net2.layers.iter().map(|layer| layer.tag()).collect();
+
+        assert_eq!(tags1, tags2);
+
+        let input = [1.0, -2.0, 0.5, 0.0, 3.0, -1.0];
+        let outputs1 = net1.forward(&input);
+        let outputs2 = net2.forward(&input);
+
+        for (out1, out2) in outputs1.iter().zip(outputs2.iter()) {
+            assert_eq!(out1.borrow().d, out2.borrow().d);
+        }
+    }
+
+    #[test]
+    fn deserialization_bad_magic() {
+        let bytes = [0xff; 64];
+        let res = Network::deserialize_from_reader(bytes.as_slice());
+
+        assert!(matches!(
+            res,
+            Err(NetworkError::BadMagic([0xff, 0xff, 0xff, 0xff]))
+        ));
+    }
+
+    #[test]
+    fn deserialization_unexpected_eof() {
+        let bytes = serialize_to_bytes(&Network::new(vec![3, 4, 1]));
+
+        for len in [0, 2, 10, bytes.len() - 1] {
+            let res = Network::deserialize_from_reader(&bytes[..len]);
+            assert!(matches!(res, Err(NetworkError::UnexpectedEof)));
+        }
+    }
+
+    #[test]
+    fn deserialization_trailing_bytes() {
+        let mut bytes = serialize_to_bytes(&Network::new(vec![3, 4, 1]));
+        bytes.push(0);
+
+        let res = Network::deserialize_from_reader(bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::TrailingBytes)));
+    }
+
+    #[test]
+    fn deserialization_invalid_shape() {
+        let mut bytes = MAGIC.to_vec();
+        for n in [DENSE_FORMAT_VERSION, 2, 3, 0, 1] {
+            bytes.extend(n.to_ne_bytes());
+        }
+
+        let res = Network::deserialize_from_reader(bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::InvalidShape(sizes)) if sizes == vec![3, 0, 1]));
+    }
+
+    #[test]
+    fn deserialization_unsupported_version() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(99u32.to_ne_bytes());
+
+        let res = Network::deserialize_from_reader(bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::UnsupportedVersion(99))));
+    }
+
+    #[test]
+    fn new_or_deserialize_from_file_shape_mismatch() {
+        const DIR: &str = ".";
+        const PREFIX: &str = "test-shape-mismatch";
+
+        let net1 = Network::new(vec![3, 4, 1]);
+        let path = utils::get_model_file_path(DIR, PREFIX, &[3, 4, 1]);
+
+        // write model with different shape to the file which is expected for [3, 4, 1]
+        let net2 = Network::new(vec![3, 5, 1]);
+        net2.serialize_to_file_path(&path)
+            .expect("failed to serialize network");
+
+        let net3 = Network::new_or_deserialize_from_file(vec![3, 4, 1], DIR, PREFIX);
+
+        fs::remove_file(&path).expect("failed to remove file");
+
+        assert_eq!(net3.get_layer_sizes(), net1.get_layer_sizes());
+    }
 }
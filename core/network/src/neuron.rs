@@ -40,7 +40,7 @@ impl Neuron {
             sum = &sum + &mul;
         }
 
-        sum.tanh()
+        sum
     }
 
     pub fn parameters(&self) -> Vec<BVal> {
@@ -60,6 +60,11 @@ mod tests {
 
         let out = n.forward(&vec![BVal::new(1.0), BVal::new(2.0), BVal::new(3.0)]);
 
-        assert!((out.borrow().d > -1.0) && (out.borrow().d < 1.0));
+        let expected = n.bias.borrow().d
+            + n.weights[0].borrow().d
+            + n.weights[1].borrow().d * 2.0
+            + n.weights[2].borrow().d * 3.0;
+
+        assert!((out.borrow().d - expected).abs() < 1e-12);
     }
 }
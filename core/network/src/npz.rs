@@ -0,0 +1,521 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, Write},
+};
+
+use zip::{result::ZipError, write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{error::NetworkError, layer::dense::Dense, network::Network};
+
+// NumPy .npz archive compatible with dfdx SaveToNpz/LoadFromNpz for tuple of Linear layers,
+// e.g. (Linear<784, 100>, Linear<100, 10>). each layer is stored as two arrays:
+// "{layer_idx}.weight.npy" with shape (outputs, inputs) and "{layer_idx}.bias.npy" with shape
+// (outputs,), where layer_idx counts dense layers only. activations are not stored, so
+// dfdx model should apply same activations to produce same outputs. loaded network gets tanh
+// activation after each dense layer. loading also accepts gaps in indices, which dfdx leaves
+// for layers without parameters
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+// numpy aligns array data to 64 bytes
+const NPY_HEADER_ALIGN: usize = 64;
+
+impl Network {
+    pub fn save_npz(&self, path: &str) -> Result<(), NetworkError> {
+        let file = File::create(path)?;
+        self.save_npz_to_writer(BufWriter::new(file))
+    }
+
+    pub fn save_npz_to_writer(&self, writer: impl Write + Seek) -> Result<(), NetworkError> {
+        let mut zip = ZipWriter::new(writer);
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+        // layers without parameters (activation, dropout, etc) have nothing to save
+        let mut dense_layers = Vec::new();
+
+        for layer in &self.layers {
+            if let Some(dense) = layer.as_any().downcast_ref::<Dense>() {
+                dense_layers.push(dense);
+            } else if !layer.parameters().is_empty() {
+                return Err(NetworkError::UnsupportedLayer(String::from(layer.tag())));
+            }
+        }
+
+        for (layer_idx, layer) in dense_layers.into_iter().enumerate() {
+            let outputs = layer.outputs_count();
+            let inputs = layer.inputs_count();
+
+            let mut weights = Vec::with_capacity(outputs * inputs);
+            let mut biases = Vec::with_capacity(outputs);
+
+            for neuron in &layer.neurons {
+                weights.extend(neuron.weights.iter().map(|w| w.borrow().d as f32));
+                biases.push(neuron.bias.borrow().d as f32);
+            }
+
+            zip.start_file(format!("{layer_idx}.weight.npy"), options)
+                .map_err(from_zip_error)?;
+            write_npy(&mut zip, &[outputs, inputs], &weights)?;
+
+            zip.start_file(format!("{layer_idx}.bias.npy"), options)
+                .map_err(from_zip_error)?;
+            write_npy(&mut zip, &[outputs], &biases)?;
+        }
+
+        let mut writer = zip.finish().map_err(from_zip_error)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn load_npz(path: &str) -> Result<Network, NetworkError> {
+        let file = File::open(path)?;
+        Self::load_npz_from_reader(BufReader::new(file))
+    }
+
+    pub fn load_npz_from_reader(reader: impl Read + Seek) -> Result<Network, NetworkError> {
+        let mut zip = ZipArchive::new(reader).map_err(from_zip_error)?;
+
+        let layers_indices = get_layers_indices(&zip)?;
+        let mut layers = Vec::new();
+
+        for layer_idx in layers_indices {
+            let weight_name = format!("{layer_idx}.weight.npy");
+            let bias_name = format!("{layer_idx}.bias.npy");
+
+            let (weights_shape, weights) = read_npz_entry(&mut zip, &weight_name)?;
+            let (biases_shape, biases) = read_npz_entry(&mut zip, &bias_name)?;
+
+            if weights_shape.len() != 2 || biases_shape != [weights_shape[0]] {
+                return Err(NetworkError::InvalidNpz(format!(
+                    "unexpected arrays shapes for layer {layer_idx}: \
+                    weight {weights_shape:?}, bias {biases_shape:?}"
+                )));
+            }
+
+            layers.push((weights_shape[1], weights_shape[0], weights, biases));
+        }
+
+        if layers.is_empty() {
+            return Err(NetworkError::InvalidNpz(String::from(
+                "no layers found in archive",
+            )));
+        }
+
+        // layers should be chained, ie. inputs of each layer should match outputs of previous one
+        let mut layers_sizes = vec![layers[0].0];
+
+        for (inputs, outputs, _, _) in &layers {
+            let prev_outputs = *layers_sizes.last().unwrap();
+
+            if *inputs != prev_outputs {
+                let mut actual = layers_sizes.clone();
+                actual.push(*inputs);
+
+                return Err(NetworkError::ShapeMismatch {
+                    expected: layers_sizes,
+                    actual,
+                });
+            }
+
+            layers_sizes.push(*outputs);
+        }
+
+        let net = Network::new(layers_sizes);
+
+        let dense_layers = net
+            .layers
+            .iter()
+            .filter_map(|layer| layer.as_any().downcast_ref::<Dense>());
+
+        for (layer, (inputs, _, weights, biases)) in dense_layers.zip(layers) {
+            for (neuron_idx, neuron) in layer.neurons.iter().enumerate() {
+                let neuron_weights = &weights[neuron_idx * inputs..(neuron_idx + 1) * inputs];
+
+                for (weight, d) in neuron.weights.iter().zip(neuron_weights) {
+                    weight.borrow_mut().d = *d;
+                }
+
+                neuron.bias.borrow_mut().d = biases[neuron_idx];
+            }
+        }
+
+        Ok(net)
+    }
+}
+
+// sorted indices of layers stored in archive. dfdx names arrays by position in the model tuple,
+// which includes layers without parameters, so indices may have gaps, e.g. "0.*" and "2.*" for
+// (Linear, ReLU, Linear). any other entry means archive isn't a plain tuple of linear layers, and
+// loading it partially would give different network
+fn get_layers_indices<R: Read + Seek>(zip: &ZipArchive<R>) -> Result<Vec<usize>, NetworkError> {
+    let mut arrays: BTreeMap<usize, (bool, bool)> = BTreeMap::new();
+
+    for name in zip.file_names() {
+        let (idx, is_weight) = if let Some(idx) = name.strip_suffix(".weight.npy") {
+            (idx, true)
+        } else if let Some(idx) = name.strip_suffix(".bias.npy") {
+            (idx, false)
+        } else {
+            return Err(NetworkError::InvalidNpz(format!(
+                "unexpected entry: {name}"
+            )));
+        };
+
+        let idx: usize = idx
+            .parse()
+            .map_err(|_| NetworkError::InvalidNpz(format!("unexpected entry: {name}")))?;
+
+        let (weight, bias) = arrays.entry(idx).or_default();
+
+        if is_weight {
+            *weight = true;
+        } else {
+            *bias = true;
+        }
+    }
+
+    for (idx, (weight, bias)) in &arrays {
+        if !weight || !bias {
+            return Err(NetworkError::InvalidNpz(format!(
+                "layer {idx} should have both weight and bias"
+            )));
+        }
+    }
+
+    Ok(arrays.into_keys().collect())
+}
+
+fn from_zip_error(err: ZipError) -> NetworkError {
+    match err {
+        ZipError::Io(err) => NetworkError::from(err),
+        err => NetworkError::InvalidNpz(err.to_string()),
+    }
+}
+
+// https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html
+fn write_npy(writer: &mut impl Write, shape: &[usize], data: &[f32]) -> Result<(), NetworkError> {
+    let shape = match shape {
+        [size] => format!("({size},)"),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|size| size.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {shape}, }}");
+
+    // magic (6) + version (2) + header length (2) + header + newline should be aligned
+    let unaligned_len = NPY_MAGIC.len() + 2 + 2 + header.len() + 1;
+    let padding = (NPY_HEADER_ALIGN - unaligned_len % NPY_HEADER_ALIGN) % NPY_HEADER_ALIGN;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    writer.write_all(NPY_MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+
+    for d in data {
+        writer.write_all(&d.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn read_npz_entry<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<(Vec<usize>, Vec<f64>), NetworkError> {
+    let mut entry = zip.by_name(name).map_err(from_zip_error)?;
+    let size = entry.size();
+
+    read_npy(&mut entry, size)
+}
+
+// size is the number of bytes of npy data, header sizes read from it are checked against it, so
+// corrupted header fails before anything is allocated
+fn read_npy(reader: &mut impl Read, size: u64) -> Result<(Vec<usize>, Vec<f64>), NetworkError> {
+    let mut magic = [0; 6];
+    reader.read_exact(&mut magic)?;
+
+    if magic != NPY_MAGIC {
+        return Err(NetworkError::InvalidNpz(String::from("bad npy magic")));
+    }
+
+    let mut version = [0; 2];
+    reader.read_exact(&mut version)?;
+
+    let (header_len, header_len_size) = match version[0] {
+        1 => {
+            let mut buf = [0; 2];
+            reader.read_exact(&mut buf)?;
+            (u16::from_le_bytes(buf) as u64, buf.len())
+        }
+        2 | 3 => {
+            let mut buf = [0; 4];
+            reader.read_exact(&mut buf)?;
+            (u32::from_le_bytes(buf) as u64, buf.len())
+        }
+        major => {
+            return Err(NetworkError::InvalidNpz(format!(
+                "unsupported npy version: {major}"
+            )))
+        }
+    };
+
+    let prefix_size = (magic.len() + version.len() + header_len_size) as u64;
+    let data_size = size
+        .checked_sub(prefix_size)
+        .and_then(|size| size.checked_sub(header_len))
+        .ok_or_else(|| NetworkError::InvalidNpz(String::from("npy header exceeds entry size")))?;
+
+    let header_len = header_len as usize;
+    let mut header = vec![0; header_len];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8_lossy(&header);
+
+    let descr = get_npy_header_value(&header, "descr")?;
+    let fortran_order = get_npy_header_value(&header, "fortran_order")?;
+    let shape = get_npy_header_value(&header, "shape")?;
+
+    if fortran_order != "False" {
+        return Err(NetworkError::InvalidNpz(String::from(
+            "fortran order is not supported",
+        )));
+    }
+
+    let shape = shape
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|size| size.trim())
+        .filter(|size| !size.is_empty())
+        .map(|size| size.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| NetworkError::InvalidNpz(format!("invalid npy shape: {shape}")))?;
+
+    let item_size = match descr.trim_matches('\'') {
+        "<f4" => 4,
+        "<f8" => 8,
+        descr => {
+            return Err(NetworkError::InvalidNpz(format!(
+                "unsupported npy data type: {descr}"
+            )))
+        }
+    };
+
+    let count = shape
+        .iter()
+        .try_fold(1usize, |count, size| count.checked_mul(*size))
+        .and_then(|count| {
+            count
+                .checked_mul(item_size)
+                .filter(|count_size| *count_size as u64 <= data_size)
+                .map(|_| count)
+        })
+        .ok_or_else(|| {
+            NetworkError::InvalidNpz(format!("npy shape {shape:?} exceeds entry size"))
+        })?;
+
+    let mut data = Vec::with_capacity(count);
+
+    match item_size {
+        4 => {
+            let mut buf = [0; 4];
+            for _ in 0..count {
+                reader.read_exact(&mut buf)?;
+                data.push(f32::from_le_bytes(buf) as f64);
+            }
+        }
+        _ => {
+            let mut buf = [0; 8];
+            for _ in 0..count {
+                reader.read_exact(&mut buf)?;
+                data.push(f64::from_le_bytes(buf));
+            }
+        }
+    }
+
+    Ok((shape, data))
+}
+
+// extracts raw value of the key from npy header, which is python dict literal,
+// e.g. "{'descr': '<f4', 'fortran_order': False, 'shape': (10, 3), }"
+fn get_npy_header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, NetworkError> {
+    let key_pattern = format!("'{key}':");
+
+    let start = header
+        .find(&key_pattern)
+        .map(|idx| idx + key_pattern.len())
+        .ok_or_else(|| NetworkError::InvalidNpz(format!("no '{key}' in npy header")))?;
+
+    let value = header[start..].trim_start();
+
+    // shape tuple contains commas itself, so read it up to closing parenthesis
+    let end = if value.starts_with('(') {
+        value.find(')').map(|idx| idx + 1)
+    } else {
+        value.find([',', '}'])
+    }
+    .ok_or_else(|| NetworkError::InvalidNpz(format!("invalid '{key}' in npy header")))?;
+
+    Ok(value[..end].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor};
+
+    use super::*;
+
+    #[test]
+    fn npy_header() {
+        let mut bytes = Vec::new();
+        write_npy(&mut bytes, &[2, 3], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+
+        // data goes right after aligned header
+        let header_len = bytes.len() - 6 * 4;
+        assert_eq!(header_len % NPY_HEADER_ALIGN, 0);
+        assert_eq!(bytes[header_len - 1], b'\n');
+
+        let (shape, data) = read_npy(&mut bytes.as_slice(), bytes.len() as u64).unwrap();
+
+        assert_eq!(shape, vec![2, 3]);
+        assert_eq!(data, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn npy_malicious_header() {
+        let data = [1.0, 2.0];
+
+        // shapes which overflow or don't fit into the data
+        for shape in [
+            vec![usize::MAX, 2],
+            vec![1 << 40, 1 << 40],
+            vec![1 << 30],
+            vec![3],
+        ] {
+            let mut bytes = Vec::new();
+            write_npy(&mut bytes, &shape, &data).unwrap();
+
+            assert!(matches!(
+                read_npy(&mut bytes.as_slice(), bytes.len() as u64),
+                Err(NetworkError::InvalidNpz(_))
+            ));
+        }
+
+        // header longer than the entry
+        let mut bytes = Vec::new();
+        write_npy(&mut bytes, &[2], &data).unwrap();
+
+        assert!(matches!(
+            read_npy(&mut bytes.as_slice(), 20),
+            Err(NetworkError::InvalidNpz(_))
+        ));
+    }
+
+    #[test]
+    fn npz_array_names() {
+        let net = Network::new(vec![3, 4, 2]);
+
+        let mut bytes = Cursor::new(Vec::new());
+        net.save_npz_to_writer(&mut bytes).unwrap();
+
+        let zip = ZipArchive::new(bytes).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+
+        assert_eq!(
+            names,
+            vec!["0.bias.npy", "0.weight.npy", "1.bias.npy", "1.weight.npy"]
+        );
+    }
+
+    #[test]
+    fn npz_round_trip() {
+        const FILE_PATH: &str = "test.npz";
+
+        let net1 = Network::new(vec![3, 4, 4, 1]);
+        net1.save_npz(FILE_PATH).expect("failed to save npz");
+
+        let net2 = Network::load_npz(FILE_PATH).expect("failed to load npz");
+
+        fs::remove_file(FILE_PATH).expect("failed to remove file");
+
+        assert_eq!(net1.parameters().len(), net2.parameters().len());
+
+        let inputs = vec![
+            vec![2.0, 3.0, -1.0],
+            vec![3.0, -1.0, 0.5],
+            vec![0.5, 1.0, 1.0],
+            vec![1.0, 1.0, -1.0],
+        ];
+
+        for input in &inputs {
+            let output1 = net1.forward(input)[0].borrow().d;
+            let output2 = net2.forward(input)[0].borrow().d;
+
+            // params are stored as f32, same as in dfdx
+            assert!((output1 - output2).abs() < 1e-5);
+        }
+    }
+
+    fn write_npz(layers: &[(usize, usize, usize)], extra_entry: Option<&str>) -> Cursor<Vec<u8>> {
+        let mut bytes = Cursor::new(Vec::new());
+
+        {
+            let mut zip = ZipWriter::new(&mut bytes);
+            let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+            for (layer_idx, inputs, outputs) in layers {
+                zip.start_file(format!("{layer_idx}.weight.npy"), options)
+                    .unwrap();
+                write_npy(&mut zip, &[*outputs, *inputs], &vec![0.5; inputs * outputs]).unwrap();
+
+                zip.start_file(format!("{layer_idx}.bias.npy"), options)
+                    .unwrap();
+                write_npy(&mut zip, &[*outputs], &vec![0.5; *outputs]).unwrap();
+            }
+
+            if let Some(name) = extra_entry {
+                zip.start_file(name, options).unwrap();
+                write_npy(&mut zip, &[1], &[0.0]).unwrap();
+            }
+
+            zip.finish().unwrap();
+        }
+
+        bytes.set_position(0);
+        bytes
+    }
+
+    #[test]
+    fn npz_gapped_indices() {
+        // (Linear<3, 4>, ReLU, Linear<4, 2>) saved by dfdx
+        let net = Network::load_npz_from_reader(write_npz(&[(0, 3, 4), (2, 4, 2)], None))
+            .expect("failed to load npz");
+
+        assert_eq!(net.get_layer_sizes(), vec![3, 4, 2]);
+
+        for res in [
+            Network::load_npz_from_reader(write_npz(&[(0, 3, 4)], Some("1.weight.npy"))),
+            Network::load_npz_from_reader(write_npz(&[(0, 3, 4)], Some("1.0.weight.npy"))),
+            Network::load_npz_from_reader(write_npz(&[(0, 3, 4)], Some("1.running_mean.npy"))),
+        ] {
+            assert!(matches!(res, Err(NetworkError::InvalidNpz(_))));
+        }
+    }
+
+    #[test]
+    fn npz_shape_mismatch() {
+        let res = Network::load_npz_from_reader(write_npz(&[(0, 3, 4), (1, 5, 2)], None));
+
+        assert!(matches!(res, Err(NetworkError::ShapeMismatch { .. })));
+    }
+}
@@ -0,0 +1,299 @@
+use autograd::val::BVal;
+use rand::{seq::index, Rng};
+
+use crate::{error::NetworkError, network::Network, rng};
+
+// samples learned before, which are mixed into each online update, so network doesn't forget
+// them while fitting a new one. can be filled upfront with reference samples, e.g. with part of
+// the original train set, to preserve what network learned in offline training
+pub struct ReplayBuffer {
+    capacity: usize,
+    // number of stored samples mixed into each update step
+    replay_size: usize,
+    samples: Vec<(Vec<f64>, Vec<f64>)>,
+    // once buffer is full, reservoir sampling keeps each of pushed samples with equal probability
+    pushed_count: usize,
+}
+
+impl ReplayBuffer {
+    pub fn new(capacity: usize, replay_size: usize) -> Self {
+        assert!(capacity > 0, "replay buffer capacity should be positive");
+
+        Self {
+            capacity,
+            replay_size,
+            samples: Vec::new(),
+            pushed_count: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn replay_size(&self) -> usize {
+        self.replay_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn samples(&self) -> &[(Vec<f64>, Vec<f64>)] {
+        &self.samples
+    }
+
+    pub fn push(&mut self, inputs: Vec<f64>, target: Vec<f64>) {
+        self.pushed_count += 1;
+
+        if self.samples.len() < self.capacity {
+            self.samples.push((inputs, target));
+        } else {
+            let idx = rng::with_rng(|rng| rng.gen_range(0..self.pushed_count));
+
+            if idx < self.capacity {
+                self.samples[idx] = (inputs, target);
+            }
+        }
+    }
+
+    // distinct random samples for one update step
+    fn replay(&self) -> Vec<(&[f64], &[f64])> {
+        let count = self.replay_size.min(self.samples.len());
+
+        rng::with_rng(|rng| index::sample(rng, self.samples.len(), count))
+            .into_iter()
+            .map(|idx| {
+                let (inputs, target) = &self.samples[idx];
+                (inputs.as_slice(), target.as_slice())
+            })
+            .collect()
+    }
+}
+
+impl Network {
+    // single sgd step on squared error of the sample, e.g. to correct misclassified input.
+    // returns loss of the sample before the step
+    pub fn learn_one(
+        &mut self,
+        inputs: &[f64],
+        target: &[f64],
+        learning_rate: f64,
+    ) -> Result<f64, NetworkError> {
+        let losses = self.learn_batch(&[(inputs, target)], learning_rate)?;
+        Ok(losses[0])
+    }
+
+    // few sgd steps on the sample, each of which also replays samples stored in the buffer, so
+    // update doesn't override what was learned before. the sample is stored in the buffer
+    // afterwards. returns loss of the sample before the first step
+    pub fn learn_one_replayed(
+        &mut self,
+        inputs: &[f64],
+        target: &[f64],
+        learning_rate: f64,
+        steps: usize,
+        buffer: &mut ReplayBuffer,
+    ) -> Result<f64, NetworkError> {
+        self.check_sample(inputs, target)?;
+
+        let mut loss = None;
+
+        for _ in 0..steps {
+            let mut batch = vec![(inputs, target)];
+            batch.extend(buffer.replay());
+
+            let losses = self.learn_batch(&batch, learning_rate)?;
+            loss.get_or_insert(losses[0]);
+        }
+
+        buffer.push(inputs.to_vec(), target.to_vec());
+
+        match loss {
+            Some(loss) => Ok(loss),
+            None => Ok(self.sample_loss(inputs, target).borrow().d),
+        }
+    }
+
+    // returns losses of samples before the step
+    fn learn_batch(
+        &mut self,
+        samples: &[(&[f64], &[f64])],
+        learning_rate: f64,
+    ) -> Result<Vec<f64>, NetworkError> {
+        for (inputs, target) in samples {
+            self.check_sample(inputs, target)?;
+        }
+
+        self.reset_grad();
+
+        let mut batch_loss = BVal::new(0.0);
+        let mut losses = Vec::with_capacity(samples.len());
+
+        for (inputs, target) in samples {
+            let loss = self.sample_loss(inputs, target);
+            losses.push(loss.borrow().d);
+            batch_loss = &batch_loss + &loss;
+        }
+
+        let batch_loss = &batch_loss / samples.len() as f64;
+        batch_loss.borrow_mut().grad = 1.0;
+        batch_loss.backward();
+
+        // pruned parameters stay pruned, and frozen layers aren't updated
+        self.apply_pruning_mask();
+
+        for param in self.trainable_parameters() {
+            let mut param = param.borrow_mut();
+            param.d -= learning_rate * param.grad;
+        }
+
+        self.apply_pruning_mask();
+
+        Ok(losses)
+    }
+
+    fn sample_loss(&self, inputs: &[f64], target: &[f64]) -> BVal {
+        let mut loss = BVal::new(0.0);
+
+        for (out, expected) in self.forward(inputs).iter().zip(target) {
+            loss = &loss + &(out - *expected).pow(2.0);
+        }
+
+        loss
+    }
+
+    fn check_sample(&self, inputs: &[f64], target: &[f64]) -> Result<(), NetworkError> {
+        let inputs_count = self.input_shape().iter().product();
+        let outputs_count = self.output_shape().iter().product();
+
+        if inputs.len() != inputs_count || target.len() != outputs_count {
+            return Err(NetworkError::ShapeMismatch {
+                expected: vec![inputs_count, outputs_count],
+                actual: vec![inputs.len(), target.len()],
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one_hotted(class: usize) -> Vec<f64> {
+        let mut target = vec![0.0; 3];
+        target[class] = 1.0;
+        target
+    }
+
+    fn predict(net: &Network, inputs: &[f64]) -> usize {
+        let outputs: Vec<f64> = net.forward(inputs).iter().map(|o| o.borrow().d).collect();
+
+        (0..outputs.len())
+            .max_by(|a, b| outputs[*a].total_cmp(&outputs[*b]))
+            .unwrap()
+    }
+
+    #[test]
+    fn learn_one() {
+        rng::seed(0);
+
+        let mut net = Network::new(vec![2, 4, 3]);
+        let inputs = [0.5, -0.5];
+        let target = one_hotted(2);
+
+        let mut losses = Vec::new();
+        for _ in 0..50 {
+            losses.push(net.learn_one(&inputs, &target, 0.1).unwrap());
+        }
+
+        assert!(losses.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(predict(&net, &inputs), 2);
+
+        assert!(matches!(
+            net.learn_one(&[0.5], &target, 0.1),
+            Err(NetworkError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            net.learn_one(&inputs, &[1.0], 0.1),
+            Err(NetworkError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn learn_one_frozen() {
+        let mut net = Network::new(vec![2, 4, 3]);
+        net.freeze();
+        net.set_trainable(2, true);
+
+        let frozen: Vec<f64> = net.layers[0]
+            .parameters()
+            .iter()
+            .map(|p| p.borrow().d)
+            .collect();
+
+        net.learn_one(&[0.5, -0.5], &one_hotted(0), 0.1).unwrap();
+
+        let params: Vec<f64> = net.layers[0]
+            .parameters()
+            .iter()
+            .map(|p| p.borrow().d)
+            .collect();
+
+        assert_eq!(params, frozen);
+    }
+
+    #[test]
+    fn replay_keeps_learned_samples() {
+        rng::seed(0);
+
+        let samples = [([1.0, 0.0], 0), ([0.0, 1.0], 1), ([-1.0, -1.0], 2)];
+
+        // each sample is learned in turn, the last one shouldn't override previous ones
+        let mut net = Network::new(vec![2, 6, 3]);
+        let mut buffer = ReplayBuffer::new(10, 2);
+
+        for _ in 0..30 {
+            for (inputs, class) in &samples {
+                net.learn_one_replayed(inputs, &one_hotted(*class), 0.1, 3, &mut buffer)
+                    .unwrap();
+            }
+        }
+
+        for (inputs, class) in &samples {
+            assert_eq!(predict(&net, inputs), *class);
+        }
+
+        // exported weights are restored as they are
+        let restored = Network::deserialize_from_reader(net.to_bytes().unwrap().as_slice())
+            .expect("failed to deserialize network");
+
+        for (inputs, class) in &samples {
+            assert_eq!(predict(&restored, inputs), *class);
+        }
+    }
+
+    #[test]
+    fn replay_buffer() {
+        let mut buffer = ReplayBuffer::new(3, 2);
+
+        assert!(buffer.is_empty());
+        assert!(buffer.replay().is_empty());
+
+        for idx in 0..10 {
+            buffer.push(vec![idx as f64], vec![0.0]);
+        }
+
+        assert_eq!(buffer.len(), 3);
+
+        let replayed = buffer.replay();
+        assert_eq!(replayed.len(), 2);
+        assert_ne!(replayed[0].0, replayed[1].0);
+    }
+}
@@ -0,0 +1,428 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::{
+    error::NetworkError,
+    layer::{
+        activation::{Activation, ActivationKind},
+        dense::Dense,
+        dropout::Dropout,
+        flatten::Flatten,
+        reshape::Reshape,
+    },
+    network::Network,
+};
+
+// ONNX model is a protobuf message, which is encoded by hand here to avoid pulling protobuf
+// toolchain for the sake of few messages. field numbers are taken from onnx.proto
+// https://github.com/onnx/onnx/blob/main/onnx/onnx.proto
+
+const IR_VERSION: u64 = 8;
+const OPSET_VERSION: u64 = 13;
+
+const INPUT_NAME: &str = "input";
+const OUTPUT_NAME: &str = "output";
+
+// name of dynamic batch dimension of graph input and output
+const BATCH_DIM_PARAM: &str = "N";
+
+// TensorProto.DataType.FLOAT
+const DATA_TYPE_FLOAT: u64 = 1;
+
+// AttributeProto.AttributeType.INT
+const ATTRIBUTE_TYPE_INT: u64 = 2;
+
+struct Node {
+    op_type: &'static str,
+    name: String,
+    inputs: Vec<String>,
+    // Gemm attribute, which makes it multiply inputs by transposed weights matrix
+    trans_b: bool,
+}
+
+impl Network {
+    pub fn export_onnx(&self, path: &str) -> Result<(), NetworkError> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(&self.to_onnx_bytes()?)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    // dense layers are exported as Gemm nodes (with weights and biases as initializers) and
+    // activation layers as corresponding activation nodes. graph input is [N, inputs] and output
+    // is [N, outputs] float tensor, so layers which only change shape are skipped, as well as
+    // dropout which does nothing in inference
+    pub fn to_onnx_bytes(&self) -> Result<Vec<u8>, NetworkError> {
+        let mut graph = ProtoWriter::default();
+        let mut nodes: Vec<Node> = Vec::new();
+
+        for (layer_idx, layer) in self.layers.iter().enumerate() {
+            let prev_output = nodes
+                .last()
+                .map(|node| node.name.clone())
+                .unwrap_or_else(|| String::from(INPUT_NAME));
+
+            if let Some(dense) = layer.as_any().downcast_ref::<Dense>() {
+                let outputs = dense.outputs_count();
+                let inputs = dense.inputs_count();
+
+                let weight_name = format!("layer_{layer_idx}.weight");
+                let bias_name = format!("layer_{layer_idx}.bias");
+
+                let mut weights = Vec::with_capacity(outputs * inputs);
+                let mut biases = Vec::with_capacity(outputs);
+
+                for neuron in &dense.neurons {
+                    weights.extend(neuron.weights.iter().map(|w| w.borrow().d as f32));
+                    biases.push(neuron.bias.borrow().d as f32);
+                }
+
+                graph.message(5, |tensor| {
+                    write_tensor(tensor, &weight_name, &[outputs, inputs], &weights)
+                });
+                graph.message(5, |tensor| {
+                    write_tensor(tensor, &bias_name, &[outputs], &biases)
+                });
+
+                // Gemm: Y = A * B' + C, where B is weights matrix [outputs, inputs]
+                nodes.push(Node {
+                    op_type: "Gemm",
+                    name: format!("layer_{layer_idx}.gemm"),
+                    inputs: vec![prev_output, weight_name, bias_name],
+                    trans_b: true,
+                });
+            } else if let Some(activation) = layer.as_any().downcast_ref::<Activation>() {
+                let op_type = match activation.kind {
+                    ActivationKind::Tanh => "Tanh",
+                    ActivationKind::Relu => "Relu",
+                    ActivationKind::Sigmoid => "Sigmoid",
+                };
+
+                nodes.push(Node {
+                    op_type,
+                    name: format!("layer_{layer_idx}.{}", op_type.to_lowercase()),
+                    inputs: vec![prev_output],
+                    trans_b: false,
+                });
+            } else if !(layer.as_any().is::<Dropout>()
+                || layer.as_any().is::<Flatten>()
+                || layer.as_any().is::<Reshape>())
+            {
+                return Err(NetworkError::UnsupportedLayer(String::from(layer.tag())));
+            }
+        }
+
+        // graph should produce output even if there is no layers to export
+        if nodes.is_empty() {
+            nodes.push(Node {
+                op_type: "Identity",
+                name: String::from("identity"),
+                inputs: vec![String::from(INPUT_NAME)],
+                trans_b: false,
+            });
+        }
+
+        // nodes are chained by output names, which are same as node names except the last one
+        let nodes_count = nodes.len();
+
+        for (node_idx, node) in nodes.iter().enumerate() {
+            let output = if node_idx == nodes_count - 1 {
+                OUTPUT_NAME
+            } else {
+                &node.name
+            };
+
+            graph.message(1, |proto| {
+                for input in &node.inputs {
+                    proto.string(1, input);
+                }
+                proto.string(2, output);
+                proto.string(3, &node.name);
+                proto.string(4, node.op_type);
+
+                if node.trans_b {
+                    proto.message(5, |attr| {
+                        attr.string(1, "transB");
+                        attr.varint(3, 1);
+                        attr.varint(20, ATTRIBUTE_TYPE_INT);
+                    });
+                }
+            });
+        }
+
+        let inputs_size = self.input_shape().iter().product();
+        let outputs_size = self.output_shape().iter().product();
+
+        graph.string(2, "network");
+        graph.message(11, |value_info| {
+            write_value_info(value_info, INPUT_NAME, inputs_size)
+        });
+        graph.message(12, |value_info| {
+            write_value_info(value_info, OUTPUT_NAME, outputs_size)
+        });
+
+        let mut model = ProtoWriter::default();
+
+        model.varint(1, IR_VERSION);
+        model.string(2, "network");
+        model.bytes(7, &graph.buf);
+        model.message(8, |opset| {
+            opset.string(1, "");
+            opset.varint(2, OPSET_VERSION);
+        });
+
+        Ok(model.buf)
+    }
+}
+
+// TensorProto
+fn write_tensor(tensor: &mut ProtoWriter, name: &str, dims: &[usize], data: &[f32]) {
+    for dim in dims {
+        tensor.varint(1, *dim as u64);
+    }
+
+    tensor.varint(2, DATA_TYPE_FLOAT);
+    tensor.string(8, name);
+
+    let raw_data: Vec<u8> = data.iter().flat_map(|d| d.to_le_bytes()).collect();
+    tensor.bytes(9, &raw_data);
+}
+
+// ValueInfoProto with float tensor type of shape [N, size]
+fn write_value_info(value_info: &mut ProtoWriter, name: &str, size: usize) {
+    value_info.string(1, name);
+    value_info.message(2, |type_proto| {
+        type_proto.message(1, |tensor_type| {
+            tensor_type.varint(1, DATA_TYPE_FLOAT);
+            tensor_type.message(2, |shape| {
+                shape.message(1, |dim| dim.string(2, BATCH_DIM_PARAM));
+                shape.message(1, |dim| dim.varint(1, size as u64));
+            });
+        });
+    });
+}
+
+// https://protobuf.dev/programming-guides/encoding/
+#[derive(Default)]
+struct ProtoWriter {
+    buf: Vec<u8>,
+}
+
+impl ProtoWriter {
+    const WIRE_TYPE_VARINT: u64 = 0;
+    const WIRE_TYPE_LEN: u64 = 2;
+
+    fn raw_varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.buf.push((n as u8 & 0x7f) | 0x80);
+            n >>= 7;
+        }
+        self.buf.push(n as u8);
+    }
+
+    fn tag(&mut self, field: u64, wire_type: u64) {
+        self.raw_varint(field << 3 | wire_type);
+    }
+
+    fn varint(&mut self, field: u64, n: u64) {
+        self.tag(field, Self::WIRE_TYPE_VARINT);
+        self.raw_varint(n);
+    }
+
+    fn bytes(&mut self, field: u64, bytes: &[u8]) {
+        self.tag(field, Self::WIRE_TYPE_LEN);
+        self.raw_varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn string(&mut self, field: u64, s: &str) {
+        self.bytes(field, s.as_bytes());
+    }
+
+    fn message(&mut self, field: u64, write: impl FnOnce(&mut ProtoWriter)) {
+        let mut nested = ProtoWriter::default();
+        write(&mut nested);
+        self.bytes(field, &nested.buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Value<'a> {
+        Varint(u64),
+        Bytes(&'a [u8]),
+    }
+
+    // decodes one level of protobuf message into list of fields
+    fn decode(mut buf: &[u8]) -> Vec<(u64, Value)> {
+        fn read_varint(buf: &mut &[u8]) -> u64 {
+            let mut n = 0;
+            let mut shift = 0;
+
+            loop {
+                let byte = buf[0];
+                *buf = &buf[1..];
+                n |= ((byte & 0x7f) as u64) << shift;
+                shift += 7;
+
+                if byte & 0x80 == 0 {
+                    return n;
+                }
+            }
+        }
+
+        let mut fields = Vec::new();
+
+        while !buf.is_empty() {
+            let tag = read_varint(&mut buf);
+
+            let value = match tag & 0x7 {
+                0 => Value::Varint(read_varint(&mut buf)),
+                2 => {
+                    let len = read_varint(&mut buf) as usize;
+                    let bytes = &buf[..len];
+                    buf = &buf[len..];
+                    Value::Bytes(bytes)
+                }
+                wire_type => panic!("unexpected wire type: {wire_type}"),
+            };
+
+            fields.push((tag >> 3, value));
+        }
+
+        fields
+    }
+
+    fn get_bytes<'a>(fields: &[(u64, Value<'a>)], field: u64) -> Vec<&'a [u8]> {
+        fields
+            .iter()
+            .filter(|(f, _)| *f == field)
+            .map(|(_, v)| match v {
+                Value::Bytes(bytes) => *bytes,
+                Value::Varint(_) => panic!("expected bytes in field {field}"),
+            })
+            .collect()
+    }
+
+    fn get_varints(fields: &[(u64, Value)], field: u64) -> Vec<u64> {
+        fields
+            .iter()
+            .filter(|(f, _)| *f == field)
+            .map(|(_, v)| match v {
+                Value::Varint(n) => *n,
+                Value::Bytes(_) => panic!("expected varint in field {field}"),
+            })
+            .collect()
+    }
+
+    fn get_string(fields: &[(u64, Value)], field: u64) -> String {
+        String::from_utf8(get_bytes(fields, field)[0].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn export() {
+        let net = Network::new(vec![3, 4, 2]);
+
+        let model = net.to_onnx_bytes().unwrap();
+        let model = decode(&model);
+
+        assert_eq!(get_varints(&model, 1), vec![IR_VERSION]);
+
+        let opset = decode(get_bytes(&model, 8)[0]);
+        assert_eq!(get_varints(&opset, 2), vec![OPSET_VERSION]);
+
+        let graph = decode(get_bytes(&model, 7)[0]);
+
+        // nodes
+        let nodes: Vec<_> = get_bytes(&graph, 1).into_iter().map(decode).collect();
+        let op_types: Vec<_> = nodes.iter().map(|node| get_string(node, 4)).collect();
+
+        assert_eq!(op_types, vec!["Gemm", "Tanh", "Gemm", "Tanh"]);
+
+        // nodes are chained from graph input to graph output
+        assert_eq!(get_string(&nodes[0], 1), INPUT_NAME);
+        assert_eq!(get_string(&nodes[1], 1), get_string(&nodes[0], 2));
+        assert_eq!(get_string(&nodes[2], 1), get_string(&nodes[1], 2));
+        assert_eq!(get_string(&nodes[3], 1), get_string(&nodes[2], 2));
+        assert_eq!(get_string(&nodes[3], 2), OUTPUT_NAME);
+
+        let gemm_attr = decode(get_bytes(&nodes[0], 5)[0]);
+        assert_eq!(get_string(&gemm_attr, 1), "transB");
+        assert_eq!(get_varints(&gemm_attr, 3), vec![1]);
+
+        // initializers
+        let initializers: Vec<_> = get_bytes(&graph, 5).into_iter().map(decode).collect();
+
+        let shapes: Vec<_> = initializers
+            .iter()
+            .map(|tensor| (get_string(tensor, 8), get_varints(tensor, 1)))
+            .collect();
+
+        assert_eq!(
+            shapes,
+            vec![
+                (String::from("layer_0.weight"), vec![4, 3]),
+                (String::from("layer_0.bias"), vec![4]),
+                (String::from("layer_2.weight"), vec![2, 4]),
+                (String::from("layer_2.bias"), vec![2]),
+            ]
+        );
+
+        // initializer data matches network params
+        let weights = get_bytes(&initializers[0], 9)[0];
+        assert_eq!(weights.len(), 4 * 3 * 4);
+
+        let first_weight = f32::from_le_bytes(weights[..4].try_into().unwrap());
+        assert_eq!(first_weight, net.parameters()[0].borrow().d as f32);
+
+        // graph input / output shapes
+        for (field, name, size) in [(11, INPUT_NAME, 3), (12, OUTPUT_NAME, 2)] {
+            let value_info = decode(get_bytes(&graph, field)[0]);
+            assert_eq!(get_string(&value_info, 1), name);
+
+            let type_proto = decode(get_bytes(&value_info, 2)[0]);
+            let tensor_type = decode(get_bytes(&type_proto, 1)[0]);
+            let shape = decode(get_bytes(&tensor_type, 2)[0]);
+            let dims: Vec<_> = get_bytes(&shape, 1).into_iter().map(decode).collect();
+
+            assert_eq!(get_string(&dims[0], 2), BATCH_DIM_PARAM);
+            assert_eq!(get_varints(&dims[1], 1), vec![size]);
+        }
+    }
+
+    #[test]
+    fn export_mixed_layers() {
+        let net = Network::from_layers(
+            vec![2, 3],
+            vec![
+                Box::new(Flatten::new()),
+                Box::new(Dense::new(6, 4)),
+                Box::new(Activation::relu()),
+                Box::new(Dropout::new(0.5)),
+                Box::new(Dense::new(4, 2)),
+                Box::new(Activation::sigmoid()),
+            ],
+        )
+        .unwrap();
+
+        let model = net.to_onnx_bytes().unwrap();
+        let model = decode(&model);
+        let graph = decode(get_bytes(&model, 7)[0]);
+
+        let nodes: Vec<_> = get_bytes(&graph, 1).into_iter().map(decode).collect();
+        let op_types: Vec<_> = nodes.iter().map(|node| get_string(node, 4)).collect();
+
+        assert_eq!(op_types, vec!["Gemm", "Relu", "Gemm", "Sigmoid"]);
+
+        // skipped dropout doesn't break the chain
+        assert_eq!(get_string(&nodes[2], 1), get_string(&nodes[1], 2));
+        assert_eq!(get_string(&nodes[3], 2), OUTPUT_NAME);
+    }
+}
@@ -0,0 +1,103 @@
+pub mod adam;
+pub mod sgd;
+
+use std::io::{Read, Write};
+
+use autograd::val::BVal;
+
+use crate::{error::NetworkError, utils};
+
+use self::{adam::Adam, sgd::Sgd};
+
+// optimizer tags are short identifiers, so longer ones can only come from corrupted data
+const MAX_TAG_LEN: usize = 64;
+
+// updates parameters using their gradients collected by backward pass. optimizers with own state
+// (e.g. momentum) expect same parameters in the same order on each step
+pub trait Optimizer {
+    // unique name of optimizer type, which identifies it in checkpoint
+    fn tag(&self) -> &'static str;
+
+    fn step(&mut self, parameters: &[BVal]);
+
+    fn learning_rate(&self) -> f64;
+
+    // lets learning rate be changed during training, e.g. decayed by callbacks
+    fn set_learning_rate(&mut self, learning_rate: f64);
+
+    // writes optimizer config and state, so it can be restored with deserialize_optimizer().
+    // optimizer tag is written by the caller
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError>;
+}
+
+pub fn serialize_optimizer(
+    optimizer: &dyn Optimizer,
+    writer: &mut dyn Write,
+) -> Result<(), NetworkError> {
+    utils::write_string(writer, optimizer.tag())?;
+    optimizer.serialize(writer)
+}
+
+// new optimizer types should be registered here, so they can be read from checkpoint
+pub fn deserialize_optimizer(reader: &mut dyn Read) -> Result<Box<dyn Optimizer>, NetworkError> {
+    let tag = utils::read_string(reader, MAX_TAG_LEN)?
+        .ok_or_else(|| NetworkError::InvalidOptimizer(String::from("invalid optimizer tag")))?;
+
+    let optimizer: Box<dyn Optimizer> = match tag.as_str() {
+        Sgd::TAG => Box::new(Sgd::deserialize(reader)?),
+        Adam::TAG => Box::new(Adam::deserialize(reader)?),
+        _ => return Err(NetworkError::UnknownOptimizer(tag)),
+    };
+
+    Ok(optimizer)
+}
+
+pub(crate) fn read_finite(reader: &mut dyn Read, name: &str) -> Result<f64, NetworkError> {
+    let d = utils::read_f64(reader)?;
+
+    if !d.is_finite() {
+        return Err(NetworkError::InvalidOptimizer(format!(
+            "{name} should be finite"
+        )));
+    }
+
+    Ok(d)
+}
+
+// state values of each parameter, e.g. momentum velocities
+pub(crate) fn write_state(writer: &mut dyn Write, state: &[f64]) -> Result<(), NetworkError> {
+    utils::write_u32(writer, state.len() as u32)?;
+
+    for d in state {
+        utils::write_f64(writer, *d)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn read_state(reader: &mut dyn Read) -> Result<Vec<f64>, NetworkError> {
+    let count = utils::read_u32(reader)?;
+
+    // vector only grows as values are read, so corrupted count fails on missing data
+    let mut state = Vec::new();
+    for _ in 0..count {
+        state.push(read_finite(reader, "state value")?);
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialization_unknown_optimizer() {
+        let mut bytes = Vec::new();
+        utils::write_string(&mut bytes, "unknown").unwrap();
+
+        let res = deserialize_optimizer(&mut bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::UnknownOptimizer(tag)) if tag == "unknown"));
+    }
+}
@@ -0,0 +1,203 @@
+use std::io::{Read, Write};
+
+use autograd::val::BVal;
+
+use crate::{error::NetworkError, utils};
+
+use super::Optimizer;
+
+// adam keeps running averages of gradients and their squares, so each parameter gets its own
+// step size. averages are bias-corrected, since they start from zeros
+pub struct Adam {
+    learning_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    steps: u64,
+    moments: Vec<f64>,
+    squared_moments: Vec<f64>,
+}
+
+impl Adam {
+    pub const TAG: &'static str = "adam";
+
+    pub fn new(learning_rate: f64) -> Self {
+        Self::with_params(learning_rate, 0.9, 0.999, 1e-8)
+    }
+
+    pub fn with_params(learning_rate: f64, beta1: f64, beta2: f64, epsilon: f64) -> Self {
+        assert!((0.0..1.0).contains(&beta1), "beta1 should be in [0, 1)");
+        assert!((0.0..1.0).contains(&beta2), "beta2 should be in [0, 1)");
+        assert!(epsilon > 0.0, "epsilon should be positive");
+
+        Adam {
+            learning_rate,
+            beta1,
+            beta2,
+            epsilon,
+            steps: 0,
+            moments: Vec::new(),
+            squared_moments: Vec::new(),
+        }
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let learning_rate = super::read_finite(reader, "learning rate")?;
+        let beta1 = utils::read_f64(reader)?;
+        let beta2 = utils::read_f64(reader)?;
+        let epsilon = utils::read_f64(reader)?;
+
+        if !(0.0..1.0).contains(&beta1) || !(0.0..1.0).contains(&beta2) {
+            return Err(NetworkError::InvalidOptimizer(format!(
+                "invalid betas: {beta1}, {beta2}"
+            )));
+        }
+
+        if !(epsilon > 0.0 && epsilon.is_finite()) {
+            return Err(NetworkError::InvalidOptimizer(format!(
+                "invalid epsilon: {epsilon}"
+            )));
+        }
+
+        let mut adam = Adam::with_params(learning_rate, beta1, beta2, epsilon);
+        adam.steps = utils::read_u64(reader)?;
+        adam.moments = super::read_state(reader)?;
+        adam.squared_moments = super::read_state(reader)?;
+
+        if adam.moments.len() != adam.squared_moments.len() {
+            return Err(NetworkError::InvalidOptimizer(String::from(
+                "moments count mismatch",
+            )));
+        }
+
+        Ok(adam)
+    }
+}
+
+impl Optimizer for Adam {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn step(&mut self, parameters: &[BVal]) {
+        if self.moments.len() != parameters.len() {
+            self.steps = 0;
+            self.moments = vec![0.0; parameters.len()];
+            self.squared_moments = vec![0.0; parameters.len()];
+        }
+
+        self.steps += 1;
+
+        let bias_correction1 = 1.0 - self.beta1.powf(self.steps as f64);
+        let bias_correction2 = 1.0 - self.beta2.powf(self.steps as f64);
+
+        for ((param, moment), squared_moment) in parameters
+            .iter()
+            .zip(self.moments.iter_mut())
+            .zip(self.squared_moments.iter_mut())
+        {
+            let grad = param.borrow().grad;
+
+            *moment = self.beta1 * *moment + (1.0 - self.beta1) * grad;
+            *squared_moment = self.beta2 * *squared_moment + (1.0 - self.beta2) * grad * grad;
+
+            let moment = *moment / bias_correction1;
+            let squared_moment = *squared_moment / bias_correction2;
+
+            param.borrow_mut().d -=
+                self.learning_rate * moment / (squared_moment.sqrt() + self.epsilon);
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.learning_rate)?;
+        utils::write_f64(writer, self.beta1)?;
+        utils::write_f64(writer, self.beta2)?;
+        utils::write_f64(writer, self.epsilon)?;
+        utils::write_u64(writer, self.steps)?;
+        super::write_state(writer, &self.moments)?;
+        super::write_state(writer, &self.squared_moments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::optim::{deserialize_optimizer, serialize_optimizer};
+
+    use super::*;
+
+    #[test]
+    fn step() {
+        let param1 = BVal::new(1.0);
+        let param2 = BVal::new(1.0);
+        param1.borrow_mut().grad = 10.0;
+        param2.borrow_mut().grad = -0.001;
+
+        let mut adam = Adam::new(0.1);
+        adam.step(&[param1.clone(), param2.clone()]);
+
+        // first step moves each parameter by learning rate regardless of gradient scale
+        assert!((param1.borrow().d - 0.9).abs() < 1e-6);
+        assert!((param2.borrow().d - 1.1).abs() < 1e-4);
+    }
+
+    #[test]
+    fn minimize() {
+        // (p - 3)^2
+        let param = BVal::new(0.0);
+        let mut adam = Adam::new(0.1);
+
+        for _ in 0..500 {
+            let loss = (&param - 3.0).pow(2.0);
+
+            param.borrow_mut().grad = 0.0;
+            loss.borrow_mut().grad = 1.0;
+            loss.backward();
+
+            adam.step(&[param.clone()]);
+        }
+
+        assert!((param.borrow().d - 3.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn serialization() {
+        let param = BVal::new(1.0);
+        let params = [param.clone()];
+
+        let mut adam1 = Adam::new(0.1);
+
+        for grad in [1.0, -0.5, 2.0] {
+            param.borrow_mut().grad = grad;
+            adam1.step(&params);
+        }
+
+        let mut bytes = Vec::new();
+        serialize_optimizer(&adam1, &mut bytes).unwrap();
+        let mut adam2 = deserialize_optimizer(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(adam2.tag(), Adam::TAG);
+
+        // restored moments and steps give the same step
+        let d = param.borrow().d;
+        adam1.step(&params);
+        let expected = param.borrow().d;
+
+        param.borrow_mut().d = d;
+        adam2.step(&params);
+
+        assert_eq!(param.borrow().d, expected);
+
+        // truncated
+        let res = deserialize_optimizer(&mut &bytes[..bytes.len() - 1]);
+        assert!(matches!(res, Err(NetworkError::UnexpectedEof)));
+    }
+}
@@ -0,0 +1,151 @@
+use std::io::{Read, Write};
+
+use autograd::val::BVal;
+
+use crate::{error::NetworkError, utils};
+
+use super::Optimizer;
+
+// stochastic gradient descent with optional momentum:
+// v = momentum * v + grad, p = p - learning_rate * v
+pub struct Sgd {
+    learning_rate: f64,
+    momentum: f64,
+    velocities: Vec<f64>,
+}
+
+impl Sgd {
+    pub const TAG: &'static str = "sgd";
+
+    pub fn new(learning_rate: f64) -> Self {
+        Self::with_momentum(learning_rate, 0.0)
+    }
+
+    pub fn with_momentum(learning_rate: f64, momentum: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&momentum),
+            "momentum should be in [0, 1)"
+        );
+
+        Sgd {
+            learning_rate,
+            momentum,
+            velocities: Vec::new(),
+        }
+    }
+
+    pub fn momentum(&self) -> f64 {
+        self.momentum
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let learning_rate = super::read_finite(reader, "learning rate")?;
+        let momentum = utils::read_f64(reader)?;
+
+        if !(0.0..1.0).contains(&momentum) {
+            return Err(NetworkError::InvalidOptimizer(format!(
+                "invalid momentum: {momentum}"
+            )));
+        }
+
+        let mut sgd = Sgd::with_momentum(learning_rate, momentum);
+        sgd.velocities = super::read_state(reader)?;
+
+        Ok(sgd)
+    }
+}
+
+impl Optimizer for Sgd {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn step(&mut self, parameters: &[BVal]) {
+        if self.velocities.len() != parameters.len() {
+            self.velocities = vec![0.0; parameters.len()];
+        }
+
+        for (param, velocity) in parameters.iter().zip(self.velocities.iter_mut()) {
+            let grad = param.borrow().grad;
+
+            *velocity = self.momentum * *velocity + grad;
+            param.borrow_mut().d -= self.learning_rate * *velocity;
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.learning_rate)?;
+        utils::write_f64(writer, self.momentum)?;
+        super::write_state(writer, &self.velocities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::optim::{deserialize_optimizer, serialize_optimizer};
+
+    use super::*;
+
+    #[test]
+    fn step() {
+        let param = BVal::new(1.0);
+        param.borrow_mut().grad = 2.0;
+
+        let mut sgd = Sgd::new(0.1);
+        sgd.step(&[param.clone()]);
+
+        assert!((param.borrow().d - 0.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn step_momentum() {
+        let param = BVal::new(1.0);
+        param.borrow_mut().grad = 1.0;
+
+        let mut sgd = Sgd::with_momentum(0.1, 0.9);
+        let params = [param.clone()];
+
+        // v = 1, p = 1 - 0.1
+        sgd.step(&params);
+        assert!((param.borrow().d - 0.9).abs() < 1e-12);
+
+        // v = 0.9 + 1, p = 0.9 - 0.19
+        sgd.step(&params);
+        assert!((param.borrow().d - 0.71).abs() < 1e-12);
+    }
+
+    #[test]
+    fn serialization() {
+        let param = BVal::new(1.0);
+        param.borrow_mut().grad = 1.0;
+        let params = [param.clone()];
+
+        let mut sgd1 = Sgd::with_momentum(0.1, 0.9);
+        sgd1.step(&params);
+
+        let mut bytes = Vec::new();
+        serialize_optimizer(&sgd1, &mut bytes).unwrap();
+        let mut sgd2 = deserialize_optimizer(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(sgd2.tag(), Sgd::TAG);
+        assert_eq!(sgd2.learning_rate(), 0.1);
+
+        // restored velocity gives the same step
+        let d = param.borrow().d;
+        sgd1.step(&params);
+        let expected = param.borrow().d;
+
+        param.borrow_mut().d = d;
+        sgd2.step(&params);
+
+        assert_eq!(param.borrow().d, expected);
+    }
+}
@@ -0,0 +1,572 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use autograd::val::BVal;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{layer::batch_norm1d::BatchNorm1D, network::Network};
+
+// data-parallel computation of batch gradients. autograd values are not thread-safe, so each
+// worker thread keeps its own replica of the network and builds its own computation graph.
+//
+// batch is split into shards of fixed size, and gradients are summed shard by shard in order of
+// shards, so results don't depend on number of threads. layers with own randomness (dropout) are
+// reseeded for each shard from seeds drawn from the network, so their masks don't depend on which
+// thread computes the shard either. batch norm normalizes each shard with its own statistics,
+// and updates of running statistics are applied to the network in order of shards too
+pub struct DataParallel<S, O> {
+    workers: Vec<Worker<S, O>>,
+    shard_size: usize,
+}
+
+struct Worker<S, O> {
+    jobs: Option<Sender<Job<S>>>,
+    results: Receiver<Vec<ShardResult<O>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+// loss of each sample of the shard and any extra output for it. unlike per-sample loss, it can run
+// network on all samples at once with forward_batch(), which batch norm needs to use batch
+// statistics while training
+pub type BatchLossFn<S, O> = dyn Fn(&Network, &[S]) -> Vec<(BVal, O)> + Send + Sync;
+
+// loss function of the calling thread, which doesn't need to be shared with workers
+type ShardLossFn<S, O> = dyn Fn(&Network, &[S]) -> Vec<(BVal, O)>;
+
+// mean and variance of each batch norm layer, in order of layers
+type RunningStats = Vec<(Vec<f64>, Vec<f64>)>;
+
+struct Job<S> {
+    params: Arc<Vec<f64>>,
+    // running statistics are used by batch norm in eval mode
+    running_stats: Arc<RunningStats>,
+    training: bool,
+    // trainable flags of layers, since frozen layers always run in eval mode
+    trainable: Vec<bool>,
+    // seeds of layers generators for the batch, see draw_seeds()
+    seeds: Arc<Vec<Option<u64>>>,
+    shards: Vec<(usize, Vec<S>)>,
+}
+
+struct ShardResult<O> {
+    shard_idx: usize,
+    loss: f64,
+    grads: Vec<f64>,
+    // batch statistics of each batch norm layer, if it was trained on the shard
+    batch_stats: Vec<Option<(Vec<f64>, Vec<f64>)>>,
+    outputs: Vec<O>,
+}
+
+pub struct BatchResult<O> {
+    // sum of samples losses
+    pub loss: f64,
+    // outputs of loss function for each sample in batch order, e.g. predictions
+    pub outputs: Vec<O>,
+}
+
+impl<S, O> DataParallel<S, O>
+where
+    S: Clone + Send + 'static,
+    O: Send + 'static,
+{
+    // loss function returns loss of one sample and any extra output for it
+    pub fn new<F>(net: &Network, threads: usize, shard_size: usize, loss_fn: F) -> Self
+    where
+        F: Fn(&Network, &S) -> (BVal, O) + Send + Sync + 'static,
+    {
+        Self::with_batch_loss(net, threads, shard_size, per_sample_loss(loss_fn))
+    }
+
+    // loss function returns losses of all samples of the shard, see BatchLossFn
+    pub fn with_batch_loss<F>(net: &Network, threads: usize, shard_size: usize, loss_fn: F) -> Self
+    where
+        F: Fn(&Network, &[S]) -> Vec<(BVal, O)> + Send + Sync + 'static,
+    {
+        assert!(threads > 0, "threads count should be positive");
+        assert!(shard_size > 0, "shard size should be positive");
+
+        let mut bytes = Vec::new();
+        net.serialize_to_writer(&mut bytes)
+            .expect("failed to serialize network");
+
+        let bytes = Arc::new(bytes);
+        let loss_fn = Arc::new(loss_fn);
+
+        let workers = (0..threads)
+            .map(|_| Worker::spawn(bytes.clone(), loss_fn.clone()))
+            .collect();
+
+        DataParallel {
+            workers,
+            shard_size,
+        }
+    }
+
+    // runs forward and backward passes for the batch and adds summed gradients to gradients of
+    // network parameters, so network should have same structure as the one pool was created for
+    pub fn compute(&self, net: &Network, batch: &[S]) -> BatchResult<O> {
+        let params: Arc<Vec<f64>> =
+            Arc::new(net.parameters().iter().map(|p| p.borrow().d).collect());
+        let running_stats = Arc::new(get_running_stats(net));
+        let seeds = Arc::new(draw_seeds(net));
+
+        // distribute shards between workers
+        let mut jobs: Vec<Vec<(usize, Vec<S>)>> = self.workers.iter().map(|_| Vec::new()).collect();
+
+        for (shard_idx, shard) in batch.chunks(self.shard_size).enumerate() {
+            jobs[shard_idx % self.workers.len()].push((shard_idx, shard.to_vec()));
+        }
+
+        let mut busy_workers = Vec::new();
+
+        for (worker, shards) in self.workers.iter().zip(jobs) {
+            if shards.is_empty() {
+                continue;
+            }
+
+            worker
+                .jobs
+                .as_ref()
+                .unwrap()
+                .send(Job {
+                    params: params.clone(),
+                    running_stats: running_stats.clone(),
+                    training: net.is_training(),
+                    trainable: (0..net.layers.len())
+                        .map(|idx| net.is_trainable(idx))
+                        .collect(),
+                    seeds: seeds.clone(),
+                    shards,
+                })
+                .expect("worker thread failed");
+
+            busy_workers.push(worker);
+        }
+
+        let results: Vec<ShardResult<O>> = busy_workers
+            .into_iter()
+            .flat_map(|worker| worker.results.recv().expect("worker thread failed"))
+            .collect();
+
+        merge_results(net, results)
+    }
+}
+
+// same computation as in data-parallel mode, but on the calling thread and right on the network,
+// so results are bit-exact with any number of threads
+pub fn compute_sequential<S, O>(
+    net: &Network,
+    batch: &[S],
+    shard_size: usize,
+    loss_fn: &ShardLossFn<S, O>,
+) -> BatchResult<O> {
+    assert!(shard_size > 0, "shard size should be positive");
+
+    // gradients of each shard are computed separately and summed afterwards, so keep gradients
+    // collected before
+    let initial_grads: Vec<f64> = net.parameters().iter().map(|p| p.borrow().grad).collect();
+
+    // shards reseed layers generators, so restore their state afterwards, same as if the batch
+    // was computed by replicas
+    let seeds = draw_seeds(net);
+    let rngs: Vec<Option<ChaCha8Rng>> = net
+        .layers
+        .iter()
+        .map(|layer| layer.rng().map(|rng| rng.borrow().clone()))
+        .collect();
+
+    // each shard starts with running statistics the batch started with, and their updates are
+    // applied in merge_results(), same as for replicas
+    let running_stats = get_running_stats(net);
+
+    let results: Vec<ShardResult<O>> = batch
+        .chunks(shard_size)
+        .enumerate()
+        .map(|(shard_idx, samples)| {
+            set_running_stats(net, &running_stats);
+            seed_shard(net, &seeds, shard_idx);
+            compute_shard(net, shard_idx, samples, loss_fn)
+        })
+        .collect();
+
+    set_running_stats(net, &running_stats);
+
+    for (param, grad) in net.parameters().iter().zip(initial_grads) {
+        param.borrow_mut().grad = grad;
+    }
+
+    for (layer, rng) in net.layers.iter().zip(rngs) {
+        if let (Some(layer_rng), Some(rng)) = (layer.rng(), rng) {
+            *layer_rng.borrow_mut() = rng;
+        }
+    }
+
+    merge_results(net, results)
+}
+
+// seed of each layer with own generator for one batch. drawing it advances generators of the
+// network, so next batches get different seeds and checkpoint restores the sequence
+fn draw_seeds(net: &Network) -> Vec<Option<u64>> {
+    net.layers
+        .iter()
+        .map(|layer| layer.rng().map(|rng| rng.borrow_mut().gen()))
+        .collect()
+}
+
+// generator of shard depends only on batch seed and shard index, not on the thread computing it
+fn seed_shard(net: &Network, seeds: &[Option<u64>], shard_idx: usize) {
+    for (layer, seed) in net.layers.iter().zip(seeds) {
+        if let (Some(rng), Some(seed)) = (layer.rng(), seed) {
+            let mut shard_rng = ChaCha8Rng::seed_from_u64(*seed);
+            shard_rng.set_stream(shard_idx as u64);
+            *rng.borrow_mut() = shard_rng;
+        }
+    }
+}
+
+// wraps loss of one sample, so it can be computed for the shard
+pub fn per_sample_loss<S, O>(
+    loss_fn: impl Fn(&Network, &S) -> (BVal, O),
+) -> impl Fn(&Network, &[S]) -> Vec<(BVal, O)> {
+    move |net: &Network, samples: &[S]| samples.iter().map(|sample| loss_fn(net, sample)).collect()
+}
+
+fn batch_norms(net: &Network) -> impl Iterator<Item = &BatchNorm1D> {
+    net.layers
+        .iter()
+        .filter_map(|layer| layer.as_any().downcast_ref::<BatchNorm1D>())
+}
+
+fn get_running_stats(net: &Network) -> RunningStats {
+    batch_norms(net)
+        .map(|norm| {
+            (
+                norm.running_mean.borrow().clone(),
+                norm.running_var.borrow().clone(),
+            )
+        })
+        .collect()
+}
+
+fn set_running_stats(net: &Network, stats: &[(Vec<f64>, Vec<f64>)]) {
+    for (norm, (mean, var)) in batch_norms(net).zip(stats) {
+        *norm.running_mean.borrow_mut() = mean.clone();
+        *norm.running_var.borrow_mut() = var.clone();
+    }
+}
+
+fn compute_shard<S, O>(
+    net: &Network,
+    shard_idx: usize,
+    samples: &[S],
+    loss_fn: &ShardLossFn<S, O>,
+) -> ShardResult<O> {
+    // statistics left by forward passes outside of training don't belong to the shard
+    for norm in batch_norms(net) {
+        norm.take_batch_stats();
+    }
+
+    let mut shard_loss = BVal::new(0.0);
+    let mut outputs = Vec::with_capacity(samples.len());
+
+    for (loss, output) in loss_fn(net, samples) {
+        shard_loss = &shard_loss + &loss;
+        outputs.push(output);
+    }
+
+    net.reset_grad();
+    shard_loss.borrow_mut().grad = 1.0;
+    shard_loss.backward();
+
+    let loss = shard_loss.borrow().d;
+
+    ShardResult {
+        shard_idx,
+        loss,
+        grads: net.parameters().iter().map(|p| p.borrow().grad).collect(),
+        batch_stats: batch_norms(net)
+            .map(|norm| norm.take_batch_stats())
+            .collect(),
+        outputs,
+    }
+}
+
+// adds gradients of shards to network parameters and updates running statistics of batch norm
+// in order of shards
+fn merge_results<O>(net: &Network, mut results: Vec<ShardResult<O>>) -> BatchResult<O> {
+    results.sort_by_key(|res| res.shard_idx);
+
+    let mut loss = 0.0;
+    let mut outputs = Vec::new();
+
+    for res in results {
+        loss += res.loss;
+
+        for (param, grad) in net.parameters().iter().zip(res.grads) {
+            param.borrow_mut().grad += grad;
+        }
+
+        for (norm, stats) in batch_norms(net).zip(res.batch_stats) {
+            if let Some((mean, var)) = stats {
+                norm.update_running_stats(&mean, &var);
+            }
+        }
+
+        outputs.extend(res.outputs);
+    }
+
+    BatchResult { loss, outputs }
+}
+
+impl<S, O> Worker<S, O>
+where
+    S: Send + 'static,
+    O: Send + 'static,
+{
+    fn spawn<F>(net_bytes: Arc<Vec<u8>>, loss_fn: Arc<F>) -> Self
+    where
+        F: Fn(&Network, &[S]) -> Vec<(BVal, O)> + Send + Sync + 'static,
+    {
+        let (jobs_sender, jobs_receiver) = mpsc::channel::<Job<S>>();
+        let (results_sender, results_receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let mut net = Network::deserialize_from_reader(net_bytes.as_slice())
+                .expect("failed to deserialize network replica");
+
+            // worker stops when pool is dropped
+            for job in jobs_receiver {
+                for (param, d) in net.parameters().iter().zip(job.params.iter()) {
+                    param.borrow_mut().d = *d;
+                }
+
+                set_running_stats(&net, &job.running_stats);
+
+                for (idx, trainable) in job.trainable.into_iter().enumerate() {
+                    if net.is_trainable(idx) != trainable {
+                        net.set_trainable(idx, trainable);
+                    }
+                }
+
+                if job.training {
+                    net.train();
+                } else {
+                    net.eval();
+                }
+
+                let results: Vec<ShardResult<O>> = job
+                    .shards
+                    .into_iter()
+                    .map(|(shard_idx, samples)| {
+                        set_running_stats(&net, &job.running_stats);
+                        seed_shard(&net, &job.seeds, shard_idx);
+                        compute_shard(&net, shard_idx, &samples, &*loss_fn)
+                    })
+                    .collect();
+
+                if results_sender.send(results).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Worker {
+            jobs: Some(jobs_sender),
+            results: results_receiver,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl<S, O> Drop for Worker<S, O> {
+    fn drop(&mut self) {
+        // closing jobs channel stops worker loop
+        self.jobs.take();
+
+        if let Some(handle) = self.handle.take() {
+            // worker panic is already reported by failed send/receive
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        layer::{activation::Activation, dense::Dense, dropout::Dropout, Layer},
+        test_utils::{create_dataset, get_grads},
+    };
+
+    use super::*;
+
+    fn calc_loss(net: &Network, (input, expected): &(Vec<f64>, f64)) -> (BVal, f64) {
+        let output = &net.forward(input)[0];
+        let loss = (*expected - output).pow(2.0);
+        let d = output.borrow().d;
+        (loss, d)
+    }
+
+    #[test]
+    fn compute() {
+        let net = Network::new(vec![2, 4, 1]);
+        let samples = create_dataset(13);
+
+        // single-threaded reference
+        let mut total_loss = BVal::new(0.0);
+        let mut expected_outputs = Vec::new();
+        for sample in &samples {
+            let (loss, output) = calc_loss(&net, sample);
+            total_loss = &total_loss + &loss;
+            expected_outputs.push(output);
+        }
+
+        net.reset_grad();
+        total_loss.borrow_mut().grad = 1.0;
+        total_loss.backward();
+
+        let expected_grads = get_grads(&net);
+
+        // parallel
+        let pool = DataParallel::new(&net, 3, 2, calc_loss);
+
+        net.reset_grad();
+        let res = pool.compute(&net, &samples);
+
+        assert!((res.loss - total_loss.borrow().d).abs() < 1e-12);
+        assert_eq!(res.outputs, expected_outputs);
+
+        for (grad, expected) in get_grads(&net).iter().zip(expected_grads) {
+            assert!((grad - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn compute_deterministic() {
+        // dropout masks are drawn for each shard, so they don't depend on threads count either
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(2, 5)),
+            Box::new(Activation::tanh()),
+            Box::new(Dropout::with_seed(0.4, 3)),
+            Box::new(Dense::new(5, 2)),
+            Box::new(Activation::tanh()),
+            Box::new(Dense::new(2, 1)),
+        ];
+        let mut net = Network::from_layers(vec![2], layers).unwrap();
+        net.train();
+
+        let samples = create_dataset(13);
+        let rng_state = net.layers[2].rng().unwrap().borrow().clone();
+
+        let mut results = Vec::new();
+
+        for threads in [1, 2, 3, 8] {
+            *net.layers[2].rng().unwrap().borrow_mut() = rng_state.clone();
+            let pool = DataParallel::new(&net, threads, 3, calc_loss);
+
+            net.reset_grad();
+            let res = pool.compute(&net, &samples);
+
+            results.push((res.loss, get_grads(&net)));
+        }
+
+        *net.layers[2].rng().unwrap().borrow_mut() = rng_state;
+
+        net.reset_grad();
+        let res = compute_sequential(&net, &samples, 3, &per_sample_loss(calc_loss));
+
+        results.push((res.loss, get_grads(&net)));
+
+        // bit-exact regardless of threads count
+        for res in &results[1..] {
+            assert_eq!(res.0.to_bits(), results[0].0.to_bits());
+            assert_eq!(res.1, results[0].1);
+        }
+
+        // next batch gets other masks
+        net.reset_grad();
+        let res = compute_sequential(&net, &samples, 3, &per_sample_loss(calc_loss));
+
+        assert_ne!(res.loss.to_bits(), results[0].0.to_bits());
+    }
+
+    #[test]
+    fn compute_batch_norm() {
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(2, 4)),
+            Box::new(BatchNorm1D::new(4)),
+            Box::new(Activation::tanh()),
+            Box::new(Dense::new(4, 1)),
+        ];
+        let mut net = Network::from_layers(vec![2], layers).unwrap();
+        net.train();
+
+        let calc_batch_loss = |net: &Network, samples: &[(Vec<f64>, f64)]| {
+            let inputs: Vec<Vec<f64>> = samples.iter().map(|(input, _)| input.clone()).collect();
+
+            net.forward_batch(&inputs)
+                .iter()
+                .zip(samples)
+                .map(|(output, (_, expected))| {
+                    let loss = (*expected - &output[0]).pow(2.0);
+                    let d = output[0].borrow().d;
+                    (loss, d)
+                })
+                .collect::<Vec<(BVal, f64)>>()
+        };
+
+        let samples = create_dataset(13);
+        let initial_stats = get_running_stats(&net);
+
+        let mut results = Vec::new();
+
+        for threads in [1, 2, 3] {
+            set_running_stats(&net, &initial_stats);
+            let pool = DataParallel::with_batch_loss(&net, threads, 4, calc_batch_loss);
+
+            net.reset_grad();
+            let res = pool.compute(&net, &samples);
+
+            results.push((res.loss, get_grads(&net), get_running_stats(&net)));
+        }
+
+        set_running_stats(&net, &initial_stats);
+
+        net.reset_grad();
+        let res = compute_sequential(&net, &samples, 4, &calc_batch_loss);
+
+        results.push((res.loss, get_grads(&net), get_running_stats(&net)));
+
+        // running statistics are updated by batch statistics of each shard
+        assert_ne!(results[0].2, initial_stats);
+
+        for res in &results[1..] {
+            assert_eq!(res.0.to_bits(), results[0].0.to_bits());
+            assert_eq!(res.1, results[0].1);
+            assert_eq!(res.2, results[0].2);
+        }
+    }
+
+    #[test]
+    fn compute_updated_params() {
+        let net = Network::new(vec![2, 4, 1]);
+        let samples = create_dataset(13);
+
+        let pool = DataParallel::new(&net, 2, 4, calc_loss);
+
+        // params changed after pool creation are passed to workers with each batch
+        for param in net.parameters() {
+            param.borrow_mut().d *= 0.5;
+        }
+
+        let res = pool.compute(&net, &samples);
+        let expected: Vec<f64> = samples.iter().map(|s| calc_loss(&net, s).1).collect();
+
+        assert_eq!(res.outputs, expected);
+    }
+}
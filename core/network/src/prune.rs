@@ -0,0 +1,404 @@
+use crate::{
+    layer::{conv2d::Conv2D, dense::Dense, Layer},
+    network::Network,
+    neuron::Neuron,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneMethod {
+    // individual weights with the smallest magnitudes
+    Unstructured,
+    // whole neurons (outputs of dense layers or convolution filters) with the smallest L2 norms
+    // of weights, along with their biases
+    Structured,
+}
+
+// gradual pruning from initial to final sparsity between given training steps, each nth step.
+// sparsity follows polynomial decay: s = final + (initial - final) * (1 - progress) ^ power, so
+// most weights are pruned early, when network has more time to recover
+// https://arxiv.org/abs/1710.01878
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PruningSchedule {
+    pub method: PruneMethod,
+    pub initial_sparsity: f64,
+    pub final_sparsity: f64,
+    pub start_step: usize,
+    pub end_step: usize,
+    pub frequency: usize,
+    pub power: f64,
+}
+
+impl PruningSchedule {
+    // one-shot pruning is a schedule with the same start and end steps
+    pub fn new(
+        method: PruneMethod,
+        final_sparsity: f64,
+        start_step: usize,
+        end_step: usize,
+    ) -> Self {
+        assert!(
+            (0.0..1.0).contains(&final_sparsity),
+            "sparsity should be in [0, 1) range"
+        );
+        assert!(start_step <= end_step, "end step should follow start step");
+
+        PruningSchedule {
+            method,
+            initial_sparsity: 0.0,
+            final_sparsity,
+            start_step,
+            end_step,
+            frequency: 1,
+            power: 3.0,
+        }
+    }
+
+    pub fn with_initial_sparsity(mut self, sparsity: f64) -> Self {
+        assert!(
+            (0.0..=self.final_sparsity).contains(&sparsity),
+            "initial sparsity should be in [0, final sparsity] range"
+        );
+        self.initial_sparsity = sparsity;
+        self
+    }
+
+    pub fn with_frequency(mut self, frequency: usize) -> Self {
+        assert!(frequency > 0, "pruning frequency should be positive");
+        self.frequency = frequency;
+        self
+    }
+
+    // 1 for linear schedule
+    pub fn with_power(mut self, power: f64) -> Self {
+        assert!(power > 0.0, "schedule power should be positive");
+        self.power = power;
+        self
+    }
+
+    // sparsity network should be pruned to at given step, or None if it isn't pruned at this step
+    pub fn sparsity(&self, step: usize) -> Option<f64> {
+        if step < self.start_step || step > self.end_step {
+            return None;
+        }
+
+        let since_start = step - self.start_step;
+
+        if step != self.end_step && since_start.checked_rem(self.frequency) != Some(0) {
+            return None;
+        }
+
+        let progress = if self.end_step == self.start_step {
+            1.0
+        } else {
+            since_start as f64 / (self.end_step - self.start_step) as f64
+        };
+
+        Some(
+            self.final_sparsity
+                + (self.initial_sparsity - self.final_sparsity) * (1.0 - progress).powf(self.power),
+        )
+    }
+}
+
+impl Network {
+    // prunes given fraction of weights (or neurons) of each dense and convolution layer by
+    // setting them to zero. pruned parameters stay pruned, and training doesn't update them. the
+    // last layer keeps all its neurons in structured pruning, since they are network outputs
+    pub fn prune(&mut self, method: PruneMethod, sparsity: f64) {
+        assert!(
+            (0.0..1.0).contains(&sparsity),
+            "sparsity should be in [0, 1) range"
+        );
+
+        let mut pruned = self.pruning_mask().to_vec();
+        let mut offset = 0;
+
+        let last_prunable = self
+            .layers
+            .iter()
+            .rposition(|layer| get_neurons(layer.as_ref()).is_some());
+
+        for (layer_idx, layer) in self.layers.iter().enumerate() {
+            let parameters_count = layer.parameters().len();
+
+            if let Some(neurons) = get_neurons(layer.as_ref()) {
+                let pruned = &mut pruned[offset..offset + parameters_count];
+
+                match method {
+                    PruneMethod::Unstructured => prune_weights(neurons, pruned, sparsity),
+                    PruneMethod::Structured if Some(layer_idx) != last_prunable => {
+                        prune_neurons(neurons, pruned, sparsity)
+                    }
+                    PruneMethod::Structured => {}
+                }
+            }
+
+            offset += parameters_count;
+        }
+
+        self.set_pruning_mask(pruned);
+        self.apply_pruning_mask();
+    }
+
+    // fraction of all parameters which are pruned
+    pub fn sparsity(&self) -> f64 {
+        let mask = self.pruning_mask();
+
+        if mask.is_empty() {
+            return 0.0;
+        }
+
+        self.pruned_parameters_count() as f64 / mask.len() as f64
+    }
+
+    pub fn pruned_parameters_count(&self) -> usize {
+        self.pruning_mask().iter().filter(|pruned| **pruned).count()
+    }
+
+    // zeroes values and gradients of pruned parameters, so they don't contribute to forward pass
+    // and aren't updated by optimizer (e.g. by momentum or weight decay)
+    pub(crate) fn apply_pruning_mask(&self) {
+        for (param, pruned) in self.parameters().iter().zip(self.pruning_mask()) {
+            if *pruned {
+                let mut param = param.borrow_mut();
+                param.d = 0.0;
+                param.grad = 0.0;
+            }
+        }
+    }
+}
+
+// layers which consist of neurons, parameters of each neuron are its weights followed by bias
+fn get_neurons(layer: &dyn Layer) -> Option<&[Neuron]> {
+    if let Some(dense) = layer.as_any().downcast_ref::<Dense>() {
+        Some(&dense.neurons)
+    } else {
+        layer
+            .as_any()
+            .downcast_ref::<Conv2D>()
+            .map(|conv| conv.filters.as_slice())
+    }
+}
+
+// prunes weights with the smallest magnitudes, biases are kept
+fn prune_weights(neurons: &[Neuron], pruned: &mut [bool], sparsity: f64) {
+    let mut weights = Vec::new();
+    let mut offset = 0;
+
+    for neuron in neurons {
+        for (idx, weight) in neuron.weights.iter().enumerate() {
+            weights.push((offset + idx, weight.borrow().d.abs()));
+        }
+
+        offset += neuron.weights.len() + 1;
+    }
+
+    // already pruned weights go first, so they stay pruned
+    weights.sort_by(|(a_idx, a), (b_idx, b)| {
+        pruned[*b_idx]
+            .cmp(&pruned[*a_idx])
+            .then_with(|| a.total_cmp(b))
+    });
+
+    let count = (weights.len() as f64 * sparsity).round() as usize;
+
+    for (idx, _) in &weights[..count] {
+        pruned[*idx] = true;
+    }
+}
+
+// prunes neurons with the smallest L2 norms of weights
+fn prune_neurons(neurons: &[Neuron], pruned: &mut [bool], sparsity: f64) {
+    let mut norms = Vec::new();
+    let mut offset = 0;
+
+    for neuron in neurons {
+        let norm = neuron
+            .weights
+            .iter()
+            .map(|w| w.borrow().d.powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        let params_count = neuron.weights.len() + 1;
+        norms.push((offset..offset + params_count, norm));
+
+        offset += params_count;
+    }
+
+    let is_pruned = |range: &std::ops::Range<usize>| pruned[range.clone()].iter().all(|p| *p);
+
+    norms.sort_by(|(a_range, a), (b_range, b)| {
+        is_pruned(b_range)
+            .cmp(&is_pruned(a_range))
+            .then_with(|| a.total_cmp(b))
+    });
+
+    let count = (norms.len() as f64 * sparsity).round() as usize;
+    let ranges: Vec<_> = norms[..count]
+        .iter()
+        .map(|(range, _)| range.clone())
+        .collect();
+
+    for range in ranges {
+        pruned[range].fill(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{layer::activation::Activation, test_utils::get_params};
+
+    use super::*;
+
+    fn set_params(net: &Network, values: &[f64]) {
+        for (param, d) in net.parameters().iter().zip(values) {
+            param.borrow_mut().d = *d;
+        }
+    }
+
+    #[test]
+    fn prune_unstructured() {
+        let mut net = Network::new(vec![3, 2]);
+
+        // neuron weights followed by bias
+        set_params(&net, &[0.5, -0.1, 0.3, 0.01, -0.2, 0.4, -0.05, 0.02]);
+
+        net.prune(PruneMethod::Unstructured, 0.5);
+
+        assert_eq!(
+            get_params(&net),
+            vec![0.5, 0.0, 0.3, 0.01, 0.0, 0.4, 0.0, 0.02]
+        );
+        assert_eq!(net.pruned_parameters_count(), 3);
+        assert_eq!(net.sparsity(), 3.0 / 8.0);
+
+        // pruned weights stay pruned even if they are changed
+        net.parameters()[1].borrow_mut().d = 10.0;
+        net.prune(PruneMethod::Unstructured, 4.0 / 6.0);
+
+        assert_eq!(
+            get_params(&net),
+            vec![0.5, 0.0, 0.0, 0.01, 0.0, 0.4, 0.0, 0.02]
+        );
+    }
+
+    #[test]
+    fn prune_structured() {
+        let mut net = Network::from_layers(
+            vec![2],
+            vec![
+                Box::new(Dense::new(2, 3)),
+                Box::new(Activation::tanh()),
+                Box::new(Dense::new(3, 1)),
+            ],
+        )
+        .unwrap();
+
+        set_params(
+            &net,
+            &[
+                1.0, 1.0, 0.5, // weights norm 1.41
+                0.1, -0.2, 0.5, // weights norm 0.22
+                -1.0, 0.5, 0.5, // weights norm 1.12
+                0.1, 0.2, 0.3, 0.4, // output layer isn't pruned
+            ],
+        );
+
+        net.prune(PruneMethod::Structured, 1.0 / 3.0);
+
+        assert_eq!(
+            get_params(&net),
+            vec![1.0, 1.0, 0.5, 0.0, 0.0, 0.0, -1.0, 0.5, 0.5, 0.1, 0.2, 0.3, 0.4]
+        );
+
+        let outputs = net.forward(&[1.0, 2.0]);
+        assert_eq!(outputs.len(), 1);
+    }
+
+    #[test]
+    fn pruned_gradients() {
+        let mut net = Network::new(vec![2, 1]);
+        set_params(&net, &[0.5, 0.1, 0.2]);
+
+        net.prune(PruneMethod::Unstructured, 0.5);
+
+        let outputs = net.forward(&[1.0, 1.0]);
+        outputs[0].borrow_mut().grad = 1.0;
+        outputs[0].backward();
+
+        net.apply_pruning_mask();
+
+        let grads: Vec<f64> = net.parameters().iter().map(|p| p.borrow().grad).collect();
+        assert_eq!(grads[1], 0.0);
+        assert!(grads[0] != 0.0 && grads[2] != 0.0);
+    }
+
+    #[test]
+    fn serialize_sparse() {
+        let mut net = Network::new(vec![100, 50, 10]);
+
+        let mut dense_bytes = Vec::new();
+        net.serialize_to_writer(&mut dense_bytes).unwrap();
+
+        net.prune(PruneMethod::Unstructured, 0.9);
+
+        let mut bytes = Vec::new();
+        net.serialize_to_writer(&mut bytes).unwrap();
+
+        assert!(bytes.len() < dense_bytes.len() / 3);
+
+        let net2 = Network::deserialize_from_reader(bytes.as_slice()).unwrap();
+
+        assert_eq!(get_params(&net2), get_params(&net));
+        assert_eq!(net2.pruning_mask(), net.pruning_mask());
+
+        // mask is missing
+        let res = Network::deserialize_from_reader(&bytes[..bytes.len() - 1]);
+        assert!(res.is_err());
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        let res = Network::deserialize_from_reader(extra.as_slice());
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn replace_head() {
+        let mut net = Network::new(vec![4, 3, 2]);
+        net.prune(PruneMethod::Unstructured, 0.5);
+
+        let pruned = net.pruning_mask()[..15].to_vec();
+
+        let net = net
+            .replace_head(2, vec![Box::new(Dense::new(3, 5))])
+            .unwrap();
+
+        assert_eq!(&net.pruning_mask()[..15], pruned.as_slice());
+        assert!(net.pruning_mask()[15..].iter().all(|pruned| !pruned));
+    }
+
+    #[test]
+    fn schedule() {
+        let schedule = PruningSchedule::new(PruneMethod::Unstructured, 0.8, 10, 20)
+            .with_frequency(4)
+            .with_power(1.0);
+
+        let sparsities: Vec<_> = (0..25).filter_map(|step| schedule.sparsity(step)).collect();
+        let expected = [0.0, 0.32, 0.64, 0.8];
+
+        assert_eq!(sparsities.len(), expected.len());
+        for (sparsity, expected) in sparsities.iter().zip(expected) {
+            assert!((sparsity - expected).abs() < 1e-12);
+        }
+
+        // cubic schedule prunes faster at the start
+        let cubic = PruningSchedule::new(PruneMethod::Unstructured, 0.8, 0, 10);
+        assert!(cubic.sparsity(5).unwrap() > 0.6);
+
+        let one_shot = PruningSchedule::new(PruneMethod::Structured, 0.5, 3, 3);
+        assert_eq!(one_shot.sparsity(2), None);
+        assert_eq!(one_shot.sparsity(3), Some(0.5));
+        assert_eq!(one_shot.sparsity(4), None);
+    }
+}
@@ -0,0 +1,636 @@
+use std::io::{Read, Write};
+
+use crate::{
+    dense_model::{DenseLayer, DenseModel},
+    error::NetworkError,
+    layer::activation::ActivationKind,
+    network::Network,
+    utils,
+};
+
+// quantized model file starts with its own magic bytes, so it can't be confused with f64 model
+const MAGIC: [u8; 4] = *b"NNQ8";
+const FORMAT_VERSION: u32 = 1;
+
+const MAX_LAYERS_COUNT: u32 = 1024;
+
+// affine mapping between real values and int8 ones: x = scale * (q - zero_point)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantParams {
+    pub scale: f32,
+    pub zero_point: i32,
+}
+
+impl QuantParams {
+    // maps range of values to full int8 range. range is extended to include zero, so zero is
+    // represented exactly (e.g. relu outputs and padding)
+    pub fn from_range(min: f32, max: f32) -> Self {
+        let min = min.min(0.0);
+        let max = max.max(0.0);
+
+        let scale = if max > min { (max - min) / 255.0 } else { 1.0 };
+
+        let zero_point = (i8::MIN as f32 - min / scale)
+            .round()
+            .clamp(i8::MIN as f32, i8::MAX as f32) as i32;
+
+        QuantParams { scale, zero_point }
+    }
+
+    pub fn from_values(values: &[f32]) -> Self {
+        let (min, max) = get_range(values);
+        Self::from_range(min, max)
+    }
+
+    pub fn quantize(&self, x: f32) -> i8 {
+        (x / self.scale + self.zero_point as f32)
+            .round()
+            .clamp(i8::MIN as f32, i8::MAX as f32) as i8
+    }
+
+    pub fn dequantize(&self, q: i8) -> f32 {
+        self.scale * (q as i32 - self.zero_point) as f32
+    }
+
+    fn write(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f32(writer, self.scale)?;
+        utils::write_u32(writer, self.zero_point as u32)
+    }
+
+    fn read(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let scale = utils::read_f32(reader)?;
+        let zero_point = utils::read_u32(reader)? as i32;
+
+        if !scale.is_finite() || scale <= 0.0 {
+            return Err(NetworkError::InvalidQuantizedModel(format!(
+                "invalid scale: {scale}"
+            )));
+        }
+
+        if zero_point < i8::MIN as i32 || zero_point > i8::MAX as i32 {
+            return Err(NetworkError::InvalidQuantizedModel(format!(
+                "invalid zero point: {zero_point}"
+            )));
+        }
+
+        Ok(QuantParams { scale, zero_point })
+    }
+}
+
+struct QuantizedLayer {
+    inputs: usize,
+    outputs: usize,
+    // row-major matrix [outputs x inputs] like in dense model
+    weights: Vec<i8>,
+    weights_params: QuantParams,
+    // sums of quantized weights of each row, which are needed to account for zero points
+    row_sums: Vec<i32>,
+    biases: Vec<f32>,
+    // quantization of inputs found by calibration, or None to find it for each input separately
+    input_params: Option<QuantParams>,
+    activation: Option<ActivationKind>,
+}
+
+impl QuantizedLayer {
+    fn new(
+        layer: &DenseLayer<f32>,
+        input_params: Option<QuantParams>,
+    ) -> Result<Self, NetworkError> {
+        let weights_params = QuantParams::from_values(&layer.weights);
+        let weights = layer
+            .weights
+            .iter()
+            .map(|w| weights_params.quantize(*w))
+            .collect();
+
+        Self::from_parts(
+            layer.inputs,
+            layer.outputs,
+            weights,
+            weights_params,
+            layer.biases.clone(),
+            input_params,
+            layer.activation,
+        )
+    }
+
+    fn from_parts(
+        inputs: usize,
+        outputs: usize,
+        weights: Vec<i8>,
+        weights_params: QuantParams,
+        biases: Vec<f32>,
+        input_params: Option<QuantParams>,
+        activation: Option<ActivationKind>,
+    ) -> Result<Self, NetworkError> {
+        if inputs == 0 || outputs == 0 {
+            return Err(NetworkError::InvalidQuantizedModel(String::from(
+                "empty layer",
+            )));
+        }
+
+        // i32 accumulators can't overflow, since each product is at most 2^16 by absolute value
+        if inputs >= 1 << 14 {
+            return Err(NetworkError::InvalidQuantizedModel(format!(
+                "too many inputs: {inputs}"
+            )));
+        }
+
+        let row_sums = weights
+            .chunks_exact(inputs)
+            .map(|row| row.iter().map(|w| *w as i32).sum())
+            .collect();
+
+        Ok(QuantizedLayer {
+            inputs,
+            outputs,
+            weights,
+            weights_params,
+            row_sums,
+            biases,
+            input_params,
+            activation,
+        })
+    }
+
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        assert_eq!(input.len(), self.inputs, "invalid inputs size");
+
+        let input_params = self
+            .input_params
+            .unwrap_or_else(|| QuantParams::from_values(input));
+
+        let input: Vec<i32> = input
+            .iter()
+            .map(|x| input_params.quantize(*x) as i32)
+            .collect();
+        let input_sum: i32 = input.iter().sum();
+
+        let weights_zero = self.weights_params.zero_point;
+        let input_zero = input_params.zero_point;
+        let scale = self.weights_params.scale * input_params.scale;
+
+        // sum((w - wz) * (x - xz)) = sum(w * x) - xz * sum(w) - wz * sum(x) + n * wz * xz
+        let zero_points_term = self.inputs as i32 * weights_zero * input_zero;
+
+        self.weights
+            .chunks_exact(self.inputs)
+            .zip(&self.row_sums)
+            .zip(&self.biases)
+            .map(|((row, row_sum), bias)| {
+                let mut acc: i32 = 0;
+                for (w, x) in row.iter().zip(&input) {
+                    acc += *w as i32 * x;
+                }
+
+                let acc = acc - input_zero * row_sum - weights_zero * input_sum + zero_points_term;
+                let out = bias + scale * acc as f32;
+
+                match self.activation {
+                    Some(ActivationKind::Tanh) => out.tanh(),
+                    Some(ActivationKind::Relu) => out.max(0.0),
+                    Some(ActivationKind::Sigmoid) => 1.0 / (1.0 + (-out).exp()),
+                    None => out,
+                }
+            })
+            .collect()
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_u32(writer, self.inputs as u32)?;
+        utils::write_u32(writer, self.outputs as u32)?;
+        // zero means no activation
+        utils::write_u32(writer, self.activation.map_or(0, |kind| kind.to_u32() + 1))?;
+
+        self.weights_params.write(writer)?;
+
+        match &self.input_params {
+            Some(input_params) => {
+                utils::write_u32(writer, 1)?;
+                input_params.write(writer)?;
+            }
+            None => utils::write_u32(writer, 0)?,
+        }
+
+        for bias in &self.biases {
+            utils::write_f32(writer, *bias)?;
+        }
+
+        let weights: Vec<u8> = self.weights.iter().map(|w| *w as u8).collect();
+        writer.write_all(&weights)?;
+
+        Ok(())
+    }
+
+    fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let inputs = utils::read_u32(reader)? as usize;
+        let outputs = utils::read_u32(reader)? as usize;
+
+        let activation = match utils::read_u32(reader)? {
+            0 => None,
+            code => Some(ActivationKind::from_u32(code - 1).ok_or_else(|| {
+                NetworkError::InvalidQuantizedModel(format!("unknown activation: {}", code - 1))
+            })?),
+        };
+
+        let weights_params = QuantParams::read(reader)?;
+
+        let input_params = match utils::read_u32(reader)? {
+            0 => None,
+            1 => Some(QuantParams::read(reader)?),
+            flag => {
+                return Err(NetworkError::InvalidQuantizedModel(format!(
+                    "invalid input params flag: {flag}"
+                )))
+            }
+        };
+
+        let mut biases = Vec::new();
+        for _ in 0..outputs {
+            biases.push(utils::read_f32(reader)?);
+        }
+
+        let weights_count = inputs.checked_mul(outputs).ok_or_else(|| {
+            NetworkError::InvalidQuantizedModel(format!("invalid layer size: {inputs}x{outputs}"))
+        })?;
+
+        // corrupted data can claim huge layer, so buffer only grows as much as data is read
+        let mut weights = Vec::new();
+        reader
+            .take(weights_count as u64)
+            .read_to_end(&mut weights)?;
+
+        if weights.len() != weights_count {
+            return Err(NetworkError::UnexpectedEof);
+        }
+
+        Self::from_parts(
+            inputs,
+            outputs,
+            weights.into_iter().map(|w| w as i8).collect(),
+            weights_params,
+            biases,
+            input_params,
+            activation,
+        )
+    }
+}
+
+// network with weights quantized to int8 with scale and zero point per layer, which takes
+// about 8 times less space than f64 model. inputs of each layer are quantized too, either with
+// params found by calibration over sample inputs, or with params of each input found on the fly
+pub struct QuantizedModel {
+    layers: Vec<QuantizedLayer>,
+}
+
+impl QuantizedModel {
+    // only networks supported by dense model can be quantized
+    pub fn quantize(
+        net: &Network,
+        calibration_inputs: Option<&[Vec<f64>]>,
+    ) -> Result<Self, NetworkError> {
+        let model = DenseModel::<f32>::try_from(net)?;
+
+        let input_params: Vec<Option<QuantParams>> = match calibration_inputs {
+            Some(inputs) if !inputs.is_empty() => calibrate(&model, inputs)
+                .into_iter()
+                .map(|(min, max)| Some(QuantParams::from_range(min, max)))
+                .collect(),
+            _ => vec![None; model.layers.len()],
+        };
+
+        let layers = model
+            .layers
+            .iter()
+            .zip(input_params)
+            .map(|(layer, input_params)| QuantizedLayer::new(layer, input_params))
+            .collect::<Result<_, _>>()?;
+
+        Ok(QuantizedModel { layers })
+    }
+
+    pub fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut res = inputs.to_vec();
+
+        for layer in &self.layers {
+            res = layer.forward(&res);
+        }
+
+        res
+    }
+
+    pub fn inputs_count(&self) -> usize {
+        self.layers[0].inputs
+    }
+
+    pub fn outputs_count(&self) -> usize {
+        self.layers.last().unwrap().outputs
+    }
+
+    pub fn parameters_count(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| layer.weights.len() + layer.biases.len())
+            .sum()
+    }
+
+    pub fn serialize_to_writer(&self, mut writer: impl Write) -> Result<(), NetworkError> {
+        writer.write_all(&MAGIC)?;
+        utils::write_u32(&mut writer, FORMAT_VERSION)?;
+        utils::write_u32(&mut writer, self.layers.len() as u32)?;
+
+        for layer in &self.layers {
+            layer.serialize(&mut writer)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn deserialize_from_reader(mut reader: impl Read) -> Result<Self, NetworkError> {
+        let magic = utils::read_magic(&mut reader)?;
+
+        if magic != MAGIC {
+            return Err(NetworkError::BadMagic(magic));
+        }
+
+        let version = utils::read_u32(&mut reader)?;
+
+        if version != FORMAT_VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+
+        let layers_count = utils::read_u32(&mut reader)?;
+
+        if layers_count == 0 || layers_count > MAX_LAYERS_COUNT {
+            return Err(NetworkError::InvalidQuantizedModel(format!(
+                "invalid layers count: {layers_count}"
+            )));
+        }
+
+        let mut layers: Vec<QuantizedLayer> = Vec::new();
+
+        for _ in 0..layers_count {
+            let layer = QuantizedLayer::deserialize(&mut reader)?;
+
+            if let Some(prev) = layers.last() {
+                if prev.outputs != layer.inputs {
+                    return Err(NetworkError::ShapeMismatch {
+                        expected: vec![prev.outputs],
+                        actual: vec![layer.inputs],
+                    });
+                }
+            }
+
+            layers.push(layer);
+        }
+
+        utils::ensure_eof(&mut reader)?;
+
+        Ok(QuantizedModel { layers })
+    }
+}
+
+// range of inputs of each layer over calibration inputs
+fn calibrate(model: &DenseModel<f32>, inputs: &[Vec<f64>]) -> Vec<(f32, f32)> {
+    let mut ranges = vec![(f32::INFINITY, f32::NEG_INFINITY); model.layers.len()];
+
+    for input in inputs {
+        let mut res: Vec<f32> = input.iter().map(|d| *d as f32).collect();
+
+        for (layer, range) in model.layers.iter().zip(ranges.iter_mut()) {
+            let (min, max) = get_range(&res);
+            *range = (range.0.min(min), range.1.max(max));
+
+            res = layer.forward(&res);
+        }
+    }
+
+    ranges
+}
+
+fn get_range(values: &[f32]) -> (f32, f32) {
+    values
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+            (min.min(*d), max.max(*d))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        optim::adam::Adam,
+        test_utils::{calc_classes_loss, create_classes_dataset, predict},
+        trainer::Trainer,
+    };
+
+    use super::*;
+
+    const CLASSES: usize = 3;
+
+    fn calc_accuracy(samples: &[(Vec<f64>, usize)], forward: impl Fn(&[f32]) -> Vec<f32>) -> f64 {
+        let correct = samples
+            .iter()
+            .filter(|(input, label)| {
+                let input: Vec<f32> = input.iter().map(|d| *d as f32).collect();
+                predict(&forward(&input)) == *label
+            })
+            .count();
+
+        correct as f64 / samples.len() as f64
+    }
+
+    fn gen_inputs(count: usize, size: usize) -> Vec<Vec<f64>> {
+        (0..count)
+            .map(|i| {
+                (0..size)
+                    .map(|j| ((i * size + j) as f64 * 0.37).sin())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quant_params() {
+        let params = QuantParams::from_range(-1.0, 3.0);
+
+        assert_eq!(params.quantize(0.0) as i32, params.zero_point);
+        assert_eq!(params.quantize(-1.0), i8::MIN);
+        assert_eq!(params.quantize(3.0), i8::MAX);
+        assert_eq!(params.quantize(100.0), i8::MAX);
+
+        for x in [-0.9, -0.3, 0.5, 1.7, 2.9] {
+            let q = params.quantize(x);
+            assert!((params.dequantize(q) - x).abs() <= params.scale / 2.0 + 1e-6);
+        }
+
+        // range without zero is extended to include it
+        let params = QuantParams::from_range(2.0, 4.0);
+        assert_eq!(params.quantize(0.0), i8::MIN);
+    }
+
+    #[test]
+    fn forward() {
+        let net = Network::new(vec![7, 9, 3]);
+        let inputs = gen_inputs(20, 7);
+
+        let dynamic = QuantizedModel::quantize(&net, None).unwrap();
+        let calibrated = QuantizedModel::quantize(&net, Some(&inputs)).unwrap();
+
+        assert_eq!(dynamic.inputs_count(), 7);
+        assert_eq!(dynamic.outputs_count(), 3);
+        assert_eq!(dynamic.parameters_count(), net.parameters().len());
+
+        for input in &inputs {
+            let expected: Vec<f64> = net.forward(input).iter().map(|v| v.borrow().d).collect();
+            let input: Vec<f32> = input.iter().map(|d| *d as f32).collect();
+
+            for model in [&dynamic, &calibrated] {
+                for (expected, actual) in expected.iter().zip(model.forward(&input)) {
+                    assert!((expected - actual as f64).abs() < 0.05);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn accuracy_drop() {
+        let mut net = Network::new(vec![8, 16, CLASSES]);
+        let train_samples = create_classes_dataset(300, 0, CLASSES, 8);
+        let test_samples = create_classes_dataset(300, 300, CLASSES, 8);
+
+        let mut trainer = Trainer::new(calc_classes_loss, Adam::new(0.01))
+            .epochs(20)
+            .batch_size(10);
+        trainer.fit(&mut net, &train_samples).unwrap();
+
+        let calibration_inputs: Vec<Vec<f64>> = train_samples
+            .iter()
+            .take(50)
+            .map(|(input, _)| input.clone())
+            .collect();
+
+        let model = DenseModel::<f64>::try_from(&net).unwrap();
+        let quantized = QuantizedModel::quantize(&net, Some(&calibration_inputs)).unwrap();
+
+        let accuracy = calc_accuracy(&test_samples, |input| {
+            let input: Vec<f64> = input.iter().map(|d| *d as f64).collect();
+            model.forward(&input).iter().map(|d| *d as f32).collect()
+        });
+        let quantized_accuracy = calc_accuracy(&test_samples, |input| quantized.forward(input));
+
+        assert!(accuracy > 0.9, "accuracy: {accuracy}");
+        assert!(
+            accuracy - quantized_accuracy <= 0.02,
+            "accuracy: {accuracy}, quantized accuracy: {quantized_accuracy}"
+        );
+    }
+
+    // shipped digits model and hand-drawn digits in mnist format, same as in tests of inference
+    // crate
+    const DIGITS_MODEL: &[u8] = include_bytes!(
+        "../../../experiments/digits/nn/train_runner/models/digits-784-200-80-10/digits-784-200-80-10-epoch-6-error-0.04.nm"
+    );
+    const TRAIN_IMAGES: &[u8] =
+        include_bytes!("../../../experiments/digits/data/canvas_digits/train-images-idx3-ubyte");
+    const TEST_IMAGES: &[u8] =
+        include_bytes!("../../../experiments/digits/data/canvas_digits/t10k-images-idx3-ubyte");
+    const TEST_LABELS: &[u8] =
+        include_bytes!("../../../experiments/digits/data/canvas_digits/t10k-labels-idx1-ubyte");
+    const IMAGES_HEADER_SIZE: usize = 16;
+    const LABELS_HEADER_SIZE: usize = 8;
+    const IMAGE_SIZE: usize = 28 * 28;
+
+    // pixels are converted to [-1, 1] range as in training
+    fn read_images(bytes: &[u8]) -> Vec<Vec<f64>> {
+        bytes[IMAGES_HEADER_SIZE..]
+            .chunks_exact(IMAGE_SIZE)
+            .map(|image| image.iter().map(|v| *v as f64 / 127.5 - 1.0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn digits_accuracy_drop() {
+        let net = Network::deserialize_from_reader(DIGITS_MODEL).unwrap();
+
+        let test_samples: Vec<(Vec<f64>, usize)> = read_images(TEST_IMAGES)
+            .into_iter()
+            .zip(&TEST_LABELS[LABELS_HEADER_SIZE..])
+            .map(|(image, label)| (image, *label as usize))
+            .collect();
+        assert_eq!(test_samples.len(), 10);
+
+        // inputs are calibrated on train set of the same digits
+        let model = DenseModel::<f64>::try_from(&net).unwrap();
+        let quantized = QuantizedModel::quantize(&net, Some(&read_images(TRAIN_IMAGES))).unwrap();
+
+        let accuracy = calc_accuracy(&test_samples, |input| {
+            let input: Vec<f64> = input.iter().map(|d| *d as f64).collect();
+            model.forward(&input).iter().map(|d| *d as f32).collect()
+        });
+        let quantized_accuracy = calc_accuracy(&test_samples, |input| quantized.forward(input));
+
+        // test set is small, so each sample is 10% of accuracy and no drop is allowed
+        assert!(
+            quantized_accuracy >= accuracy,
+            "accuracy: {accuracy}, quantized accuracy: {quantized_accuracy}"
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let net = Network::new(vec![7, 9, 3]);
+        let inputs = gen_inputs(5, 7);
+
+        // dynamic and calibrated layers are both covered
+        let mut model = QuantizedModel::quantize(&net, Some(&inputs)).unwrap();
+        model.layers[1].input_params = None;
+
+        let mut bytes = Vec::new();
+        model.serialize_to_writer(&mut bytes).unwrap();
+
+        let mut net_bytes = Vec::new();
+        net.serialize_to_writer(&mut net_bytes).unwrap();
+
+        assert!(bytes.len() * 3 < net_bytes.len());
+
+        let restored = QuantizedModel::deserialize_from_reader(bytes.as_slice()).unwrap();
+
+        for input in &inputs {
+            let input: Vec<f32> = input.iter().map(|d| *d as f32).collect();
+            assert_eq!(model.forward(&input), restored.forward(&input));
+        }
+    }
+
+    #[test]
+    fn deserialization_errors() {
+        let net = Network::new(vec![4, 3, 2]);
+        let model = QuantizedModel::quantize(&net, None).unwrap();
+
+        let mut bytes = Vec::new();
+        model.serialize_to_writer(&mut bytes).unwrap();
+
+        // truncated
+        let res = QuantizedModel::deserialize_from_reader(&bytes[..bytes.len() - 1]);
+        assert!(matches!(res, Err(NetworkError::UnexpectedEof)));
+
+        // trailing bytes
+        let mut extended = bytes.clone();
+        extended.push(0);
+        let res = QuantizedModel::deserialize_from_reader(extended.as_slice());
+        assert!(matches!(res, Err(NetworkError::TrailingBytes)));
+
+        // f64 model
+        let mut net_bytes = Vec::new();
+        net.serialize_to_writer(&mut net_bytes).unwrap();
+        let res = QuantizedModel::deserialize_from_reader(net_bytes.as_slice());
+        assert!(matches!(res, Err(NetworkError::BadMagic(_))));
+
+        // invalid scale of first layer weights, which follows header and layer sizes
+        let mut corrupted = bytes.clone();
+        corrupted[24..28].copy_from_slice(&(-1.0f32).to_ne_bytes());
+        let res = QuantizedModel::deserialize_from_reader(corrupted.as_slice());
+        assert!(matches!(res, Err(NetworkError::InvalidQuantizedModel(_))));
+    }
+}
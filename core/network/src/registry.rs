@@ -0,0 +1,387 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::NetworkError, network::Network};
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+
+// directory of model files with a manifest, which keeps models architecture, training metrics and
+// lineage, so run metadata doesn't have to be encoded into file names
+pub struct ModelRegistry {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    // sequence number of the next registered model
+    next_seq: u64,
+    models: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelEntry {
+    pub id: String,
+    pub name: String,
+    // model file name, relative to registry dir
+    pub file_name: String,
+    pub input_shape: Vec<usize>,
+    pub output_shape: Vec<usize>,
+    pub layers: Vec<LayerEntry>,
+    pub parameters_count: usize,
+    pub metrics: BTreeMap<String, f64>,
+    // id of the model this one was trained from
+    pub parent: Option<String>,
+    // unix time in seconds
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerEntry {
+    pub tag: String,
+    pub activation: Option<String>,
+    pub output_shape: Vec<usize>,
+    pub parameters_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricGoal {
+    // e.g. loss or error rate
+    Minimize,
+    // e.g. accuracy
+    Maximize,
+}
+
+impl ModelRegistry {
+    // opens registry in given dir, creating both dir and empty manifest if there are none
+    pub fn open(dir: &str) -> Result<Self, NetworkError> {
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir)?;
+
+        let manifest_path = dir.join(MANIFEST_FILE_NAME);
+
+        let manifest = if manifest_path.exists() {
+            let json = fs::read_to_string(&manifest_path)?;
+            let manifest: Manifest = serde_json::from_str(&json)
+                .map_err(|err| NetworkError::InvalidManifest(err.to_string()))?;
+
+            if manifest.version != MANIFEST_VERSION {
+                return Err(NetworkError::UnsupportedVersion(manifest.version));
+            }
+
+            manifest
+        } else {
+            Manifest {
+                version: MANIFEST_VERSION,
+                next_seq: 0,
+                models: Vec::new(),
+            }
+        };
+
+        Ok(ModelRegistry { dir, manifest })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // models in order of registration
+    pub fn models(&self) -> &[ModelEntry] {
+        &self.manifest.models
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ModelEntry> {
+        self.manifest.models.iter().find(|entry| entry.id == id)
+    }
+
+    // saves model file and adds it to the manifest, returns id of the model. name becomes part of
+    // file name, so it can't contain path separators or "..". metrics should be finite, as json
+    // has no representation for nan or infinity
+    pub fn register(
+        &mut self,
+        name: &str,
+        net: &Network,
+        metrics: BTreeMap<String, f64>,
+        parent: Option<&str>,
+    ) -> Result<String, NetworkError> {
+        if name.is_empty() || name.contains(['/', '\\', '\0']) || name.contains("..") {
+            return Err(NetworkError::InvalidModelName(String::from(name)));
+        }
+
+        if let Some(parent) = parent {
+            if self.get(parent).is_none() {
+                return Err(NetworkError::ModelNotFound(String::from(parent)));
+            }
+        }
+
+        if let Some((metric, value)) = metrics.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(NetworkError::InvalidMetric(format!("{metric} is {value}")));
+        }
+
+        let id = format!("{name}-{:04}", self.manifest.next_seq);
+        let file_name = format!("{id}.nm");
+
+        net.serialize_to_file_path(self.dir.join(&file_name))?;
+
+        let summary = net.summary();
+
+        let entry = ModelEntry {
+            id: id.clone(),
+            name: String::from(name),
+            file_name,
+            input_shape: summary.input_shape,
+            output_shape: summary.output_shape,
+            layers: summary
+                .layers
+                .into_iter()
+                .map(|layer| LayerEntry {
+                    tag: String::from(layer.tag),
+                    activation: layer.activation.map(|kind| String::from(kind.name())),
+                    output_shape: layer.output_shape,
+                    parameters_count: layer.parameters_count,
+                })
+                .collect(),
+            parameters_count: summary.parameters_count,
+            metrics,
+            parent: parent.map(String::from),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        };
+
+        self.manifest.models.push(entry);
+        self.manifest.next_seq += 1;
+
+        self.save_manifest()?;
+
+        Ok(id)
+    }
+
+    // model with the best value of given metric, models without the metric are skipped
+    pub fn best_by(&self, metric: &str, goal: MetricGoal) -> Option<&ModelEntry> {
+        self.manifest
+            .models
+            .iter()
+            .filter_map(|entry| entry.metrics.get(metric).map(|value| (entry, *value)))
+            .filter(|(_, value)| !value.is_nan())
+            .reduce(|best, candidate| {
+                let better = match goal {
+                    MetricGoal::Minimize => candidate.1 < best.1,
+                    MetricGoal::Maximize => candidate.1 > best.1,
+                };
+
+                if better {
+                    candidate
+                } else {
+                    best
+                }
+            })
+            .map(|(entry, _)| entry)
+    }
+
+    pub fn load(&self, id: &str) -> Result<Network, NetworkError> {
+        let entry = self
+            .get(id)
+            .ok_or_else(|| NetworkError::ModelNotFound(String::from(id)))?;
+
+        Network::deserialize_from_file_path(self.dir.join(&entry.file_name))
+    }
+
+    // the model itself followed by its parent, parent of the parent and so on
+    pub fn lineage(&self, id: &str) -> Vec<&ModelEntry> {
+        let mut lineage = Vec::new();
+        let mut next = self.get(id);
+
+        while let Some(entry) = next {
+            // manifest is edited by hand sometimes, so guard against cycles
+            if lineage.iter().any(|e: &&ModelEntry| e.id == entry.id) {
+                break;
+            }
+
+            lineage.push(entry);
+            next = entry.parent.as_deref().and_then(|parent| self.get(parent));
+        }
+
+        lineage
+    }
+
+    fn save_manifest(&self) -> Result<(), NetworkError> {
+        let json = serde_json::to_string_pretty(&self.manifest)
+            .map_err(|err| NetworkError::InvalidManifest(err.to_string()))?;
+
+        // write to temporary file first, so failed write doesn't corrupt existing manifest
+        let tmp_path = self.dir.join(format!("{MANIFEST_FILE_NAME}.tmp"));
+        fs::write(&tmp_path, json)?;
+        fs::rename(tmp_path, self.dir.join(MANIFEST_FILE_NAME))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn create_registry_dir(name: &str) -> String {
+        let dir = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        String::from(dir.to_str().unwrap())
+    }
+
+    fn metrics(error: f64) -> BTreeMap<String, f64> {
+        BTreeMap::from([(String::from("error"), error)])
+    }
+
+    #[test]
+    fn register_and_load() {
+        let dir = create_registry_dir("network_registry_register_test");
+
+        let net = Network::new(vec![3, 4, 2]);
+
+        let mut registry = ModelRegistry::open(&dir).unwrap();
+        let id = registry
+            .register("digits", &net, metrics(0.1), None)
+            .unwrap();
+
+        assert_eq!(id, "digits-0000");
+
+        // reopened registry reads manifest
+        let registry = ModelRegistry::open(&dir).unwrap();
+        let entry = registry.get(&id).unwrap();
+
+        assert_eq!(entry.input_shape, vec![3]);
+        assert_eq!(entry.output_shape, vec![2]);
+        assert_eq!(entry.parameters_count, 26);
+        assert_eq!(entry.layers.len(), 4);
+        assert_eq!(entry.layers[1].activation.as_deref(), Some("tanh"));
+        assert_eq!(entry.metrics["error"], 0.1);
+
+        let restored = registry.load(&id).unwrap();
+
+        for (param1, param2) in net.parameters().iter().zip(restored.parameters()) {
+            assert_eq!(param1.borrow().d, param2.borrow().d);
+        }
+
+        assert!(matches!(
+            registry.load("unknown"),
+            Err(NetworkError::ModelNotFound(_))
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn best_by_and_lineage() {
+        let dir = create_registry_dir("network_registry_best_test");
+
+        let net = Network::new(vec![2, 2]);
+        let mut registry = ModelRegistry::open(&dir).unwrap();
+
+        let first = registry.register("net", &net, metrics(0.3), None).unwrap();
+        let second = registry
+            .register("net", &net, metrics(0.1), Some(&first))
+            .unwrap();
+        let third = registry
+            .register("net", &net, metrics(0.2), Some(&second))
+            .unwrap();
+        registry
+            .register("net", &net, BTreeMap::new(), None)
+            .unwrap();
+
+        assert_eq!(
+            registry.best_by("error", MetricGoal::Minimize).unwrap().id,
+            second
+        );
+        assert_eq!(
+            registry.best_by("error", MetricGoal::Maximize).unwrap().id,
+            first
+        );
+        assert!(registry.best_by("accuracy", MetricGoal::Maximize).is_none());
+
+        let lineage: Vec<&str> = registry
+            .lineage(&third)
+            .iter()
+            .map(|entry| entry.id.as_str())
+            .collect();
+        assert_eq!(
+            lineage,
+            vec![third.as_str(), second.as_str(), first.as_str()]
+        );
+
+        assert!(matches!(
+            registry.register("net", &net, BTreeMap::new(), Some("unknown")),
+            Err(NetworkError::ModelNotFound(_))
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn register_non_finite_metric() {
+        let dir = create_registry_dir("network_registry_non_finite_test");
+
+        let net = Network::new(vec![2, 2]);
+        let mut registry = ModelRegistry::open(&dir).unwrap();
+        let id = registry.register("net", &net, metrics(0.1), None).unwrap();
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                registry.register("net", &net, metrics(value), Some(&id)),
+                Err(NetworkError::InvalidMetric(_))
+            ));
+        }
+
+        // nothing is written, so registry still opens
+        let registry = ModelRegistry::open(&dir).unwrap();
+
+        assert_eq!(registry.models().len(), 1);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn register_invalid_name() {
+        let dir = create_registry_dir("network_registry_invalid_name_test");
+
+        let net = Network::new(vec![2, 2]);
+        let mut registry = ModelRegistry::open(&dir).unwrap();
+
+        for name in ["", "../net", "nets/net", "nets\\net", ".."] {
+            assert!(matches!(
+                registry.register(name, &net, metrics(0.1), None),
+                Err(NetworkError::InvalidModelName(_))
+            ));
+        }
+
+        // nothing is written outside of registry dir or into it
+        assert!(!env::temp_dir().join("net-0000.nm").exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn open_invalid_manifest() {
+        let dir = create_registry_dir("network_registry_invalid_test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(PathBuf::from(&dir).join(MANIFEST_FILE_NAME), "{").unwrap();
+
+        let res = ModelRegistry::open(&dir);
+
+        assert!(matches!(res, Err(NetworkError::InvalidManifest(_))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use autograd::val::BVal;
+
+// penalty on parameters values, which is added to the loss to keep weights small and reduce
+// overfitting. lambda sets strength of the penalty
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Regularization {
+    L1(f64),
+    L2(f64),
+}
+
+impl Regularization {
+    pub fn penalty(&self, parameters: &[BVal]) -> BVal {
+        match *self {
+            Regularization::L1(lambda) => l1_penalty(parameters, lambda),
+            Regularization::L2(lambda) => l2_penalty(parameters, lambda),
+        }
+    }
+}
+
+// lambda * sum(|p|). pushes parameters to exact zeros, ie. makes network sparse
+pub fn l1_penalty(parameters: &[BVal], lambda: f64) -> BVal {
+    let terms = parameters.iter().map(|param| param.abs()).collect();
+
+    &sum(terms) * lambda
+}
+
+// lambda * sum(p^2). pulls big parameters down harder than small ones (weight decay)
+pub fn l2_penalty(parameters: &[BVal], lambda: f64) -> BVal {
+    // not multiplying param by itself, since autograd can't borrow same value twice
+    let terms = parameters.iter().map(|param| param.pow(2.0)).collect();
+
+    &sum(terms) * lambda
+}
+
+// terms are added pairwise, so depth of the graph is logarithmic of parameters count. backward
+// pass walks the graph recursively, and chain of additions overflows the stack on large networks
+fn sum(mut terms: Vec<BVal>) -> BVal {
+    if terms.is_empty() {
+        return BVal::new(0.0);
+    }
+
+    while terms.len() > 1 {
+        terms = terms
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => a + b,
+                [a] => a.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    terms.pop().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::Network;
+
+    use super::*;
+
+    fn create_parameters() -> Vec<BVal> {
+        vec![BVal::new(-2.0), BVal::new(0.5), BVal::new(0.0)]
+    }
+
+    #[test]
+    fn l1() {
+        let params = create_parameters();
+
+        let penalty = l1_penalty(&params, 0.1);
+        assert!((penalty.borrow().d - 0.25).abs() < 1e-12);
+
+        penalty.borrow_mut().grad = 1.0;
+        penalty.backward();
+
+        let grads: Vec<f64> = params.iter().map(|p| p.borrow().grad).collect();
+        assert_eq!(grads, vec![-0.1, 0.1, 0.0]);
+    }
+
+    #[test]
+    fn l2() {
+        let params = create_parameters();
+
+        let penalty = l2_penalty(&params, 0.1);
+        assert!((penalty.borrow().d - 0.425).abs() < 1e-12);
+
+        penalty.borrow_mut().grad = 1.0;
+        penalty.backward();
+
+        let grads: Vec<f64> = params.iter().map(|p| p.borrow().grad).collect();
+        assert_eq!(grads, vec![-0.4, 0.1, 0.0]);
+    }
+
+    #[test]
+    fn penalty_of_large_network() {
+        // same size as digits models, with graph deep enough to overflow the stack if
+        // penalty terms are chained
+        let net = Network::new(vec![784, 300, 10]);
+
+        for regularization in [Regularization::L1(1e-4), Regularization::L2(1e-4)] {
+            net.reset_grad();
+
+            let penalty = regularization.penalty(net.parameters());
+            penalty.borrow_mut().grad = 1.0;
+            penalty.backward();
+
+            let param = net.parameters()[1].borrow();
+            let expected_grad = match regularization {
+                Regularization::L1(lambda) => lambda * param.d.signum(),
+                Regularization::L2(lambda) => 2.0 * lambda * param.d,
+            };
+
+            assert!((param.grad - expected_grad).abs() < 1e-12);
+        }
+
+        assert_eq!(l2_penalty(&[], 0.1).borrow().d, 0.0);
+    }
+
+    #[test]
+    fn penalty_shrinks_parameters() {
+        let net = Network::new(vec![3, 4, 1]);
+
+        let norm = |net: &Network| -> f64 {
+            net.parameters()
+                .iter()
+                .map(|p| p.borrow().d.powi(2))
+                .sum::<f64>()
+        };
+
+        let initial_norm = norm(&net);
+
+        for _ in 0..10 {
+            net.reset_grad();
+
+            let penalty = Regularization::L2(0.1).penalty(net.parameters());
+            penalty.borrow_mut().grad = 1.0;
+            penalty.backward();
+
+            for param in net.parameters() {
+                let grad = param.borrow().grad;
+                param.borrow_mut().d -= 0.5 * grad;
+            }
+        }
+
+        assert!(norm(&net) < initial_norm * 0.5);
+    }
+}
@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::utils;
+
+thread_local! {
+    // source of randomness for parameters initialization, dropout masks and dataset shuffling.
+    // networks are bound to the thread they were created on, so generator is per thread too
+    static RNG: RefCell<ChaCha8Rng> = RefCell::new(ChaCha8Rng::from_entropy());
+}
+
+// makes everything random on current thread reproducible, e.g. two training runs started with
+// same seed create same networks and train them on samples in same order. dropout layers get
+// their generators from it too, and data-parallel training reseeds replicas for each shard from
+// generators of the main network, so dropout masks are reproducible with any threads count
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = ChaCha8Rng::seed_from_u64(seed));
+}
+
+// seed for independent generator, e.g. the one of dropout layer
+pub fn gen_seed() -> u64 {
+    with_rng(|rng| rng.gen())
+}
+
+// normally distributed number with zero mean, e.g. noise of generative models
+pub fn gen_normal(deviation: f64) -> f64 {
+    utils::gen_rand_normal(deviation)
+}
+
+pub(crate) fn with_rng<T>(f: impl FnOnce(&mut ChaCha8Rng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{network::Network, test_utils::get_params};
+
+    use super::*;
+
+    #[test]
+    fn seeded_network() {
+        seed(42);
+        let params1 = get_params(&Network::new(vec![3, 4, 2]));
+        let params2 = get_params(&Network::new(vec![3, 4, 2]));
+
+        seed(42);
+        let params3 = get_params(&Network::new(vec![3, 4, 2]));
+
+        assert_ne!(params1, params2);
+        assert_eq!(params1, params3);
+    }
+}
@@ -0,0 +1,183 @@
+pub mod constant;
+pub mod cosine_warm_restarts;
+pub mod exponential;
+pub mod linear_decay;
+pub mod linear_warmup;
+pub mod reduce_on_plateau;
+pub mod step_decay;
+
+use std::io::{Read, Write};
+
+use crate::{error::NetworkError, utils};
+
+use self::{
+    constant::Constant, cosine_warm_restarts::CosineWarmRestarts, exponential::Exponential,
+    linear_decay::LinearDecay, linear_warmup::LinearWarmup, reduce_on_plateau::ReduceOnPlateau,
+    step_decay::StepDecay,
+};
+
+// scheduler tags are short identifiers, so longer ones can only come from corrupted data
+const MAX_TAG_LEN: usize = 64;
+
+// schedulers can wrap other ones (e.g. warmup), which is limited to keep corrupted data from
+// nesting them endlessly
+const MAX_NESTING_DEPTH: usize = 8;
+
+// sets learning rate during training. schedules are driven by global step, i.e. number of
+// batches trained before, which doesn't restart with epochs, so training can be resumed from
+// checkpoint at any step
+pub trait LrScheduler {
+    // unique name of scheduler type, which identifies it in checkpoint
+    fn tag(&self) -> &'static str;
+
+    // learning rate for the batch of given global step
+    fn learning_rate(&self, step: usize) -> f64;
+
+    // lets schedulers react on training progress, loss is the monitored one of finished epoch
+    fn on_epoch_end(&mut self, _loss: f64) {}
+
+    // writes scheduler config and state, so it can be restored with deserialize_scheduler().
+    // scheduler tag is written by the caller
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError>;
+}
+
+pub fn serialize_scheduler(
+    scheduler: &dyn LrScheduler,
+    writer: &mut dyn Write,
+) -> Result<(), NetworkError> {
+    utils::write_string(writer, scheduler.tag())?;
+    scheduler.serialize(writer)
+}
+
+pub fn deserialize_scheduler(reader: &mut dyn Read) -> Result<Box<dyn LrScheduler>, NetworkError> {
+    deserialize_nested_scheduler(reader, 0)
+}
+
+// new scheduler types should be registered here, so they can be read from checkpoint
+pub(crate) fn deserialize_nested_scheduler(
+    reader: &mut dyn Read,
+    depth: usize,
+) -> Result<Box<dyn LrScheduler>, NetworkError> {
+    if depth > MAX_NESTING_DEPTH {
+        return Err(NetworkError::InvalidScheduler(String::from(
+            "too deep nesting of schedulers",
+        )));
+    }
+
+    let tag = utils::read_string(reader, MAX_TAG_LEN)?
+        .ok_or_else(|| NetworkError::InvalidScheduler(String::from("invalid scheduler tag")))?;
+
+    let scheduler: Box<dyn LrScheduler> = match tag.as_str() {
+        Constant::TAG => Box::new(Constant::deserialize(reader)?),
+        StepDecay::TAG => Box::new(StepDecay::deserialize(reader)?),
+        Exponential::TAG => Box::new(Exponential::deserialize(reader)?),
+        CosineWarmRestarts::TAG => Box::new(CosineWarmRestarts::deserialize(reader)?),
+        LinearDecay::TAG => Box::new(LinearDecay::deserialize(reader)?),
+        LinearWarmup::TAG => Box::new(LinearWarmup::deserialize(reader, depth)?),
+        ReduceOnPlateau::TAG => Box::new(ReduceOnPlateau::deserialize(reader)?),
+        _ => return Err(NetworkError::UnknownScheduler(tag)),
+    };
+
+    Ok(scheduler)
+}
+
+// reads count of steps, which should be positive
+pub(crate) fn read_steps(reader: &mut dyn Read, name: &str) -> Result<usize, NetworkError> {
+    let steps = utils::read_u32(reader)? as usize;
+
+    if steps == 0 {
+        return Err(NetworkError::InvalidScheduler(format!(
+            "{name} should be positive"
+        )));
+    }
+
+    Ok(steps)
+}
+
+pub(crate) fn write_steps(writer: &mut dyn Write, steps: usize) -> Result<(), NetworkError> {
+    let steps = u32::try_from(steps)
+        .map_err(|_| NetworkError::InvalidScheduler(format!("too many steps: {steps}")))?;
+
+    utils::write_u32(writer, steps)
+}
+
+// reads learning rate or other factor, which should be finite
+pub(crate) fn read_finite(reader: &mut dyn Read, name: &str) -> Result<f64, NetworkError> {
+    let d = utils::read_f64(reader)?;
+
+    if !d.is_finite() {
+        return Err(NetworkError::InvalidScheduler(format!(
+            "{name} should be finite"
+        )));
+    }
+
+    Ok(d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialization() {
+        let mut plateau = ReduceOnPlateau::new(0.1, 0.5, 0, 0.001);
+        plateau.on_epoch_end(1.0);
+        plateau.on_epoch_end(2.0);
+
+        let schedulers: Vec<Box<dyn LrScheduler>> = vec![
+            Box::new(Constant::new(0.1)),
+            Box::new(StepDecay::new(0.1, 0.5, 10)),
+            Box::new(Exponential::new(0.1, 0.99)),
+            Box::new(CosineWarmRestarts::new(0.1, 0.001, 10, 2)),
+            Box::new(LinearDecay::new(0.1, 0.01, 100)),
+            Box::new(LinearWarmup::new(5, StepDecay::new(0.1, 0.5, 10))),
+            Box::new(plateau),
+        ];
+
+        let mut bytes = Vec::new();
+        for scheduler in &schedulers {
+            serialize_scheduler(scheduler.as_ref(), &mut bytes).unwrap();
+        }
+
+        let mut reader = bytes.as_slice();
+        for scheduler in &schedulers {
+            let restored = deserialize_scheduler(&mut reader).unwrap();
+
+            assert_eq!(restored.tag(), scheduler.tag());
+
+            for step in [0, 1, 4, 5, 9, 10, 33, 1000] {
+                assert_eq!(
+                    restored.learning_rate(step),
+                    scheduler.learning_rate(step),
+                    "scheduler: {}, step: {step}",
+                    scheduler.tag()
+                );
+            }
+        }
+
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn deserialization_unknown_scheduler() {
+        let mut bytes = Vec::new();
+        utils::write_string(&mut bytes, "unknown").unwrap();
+
+        let res = deserialize_scheduler(&mut bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::UnknownScheduler(tag)) if tag == "unknown"));
+    }
+
+    #[test]
+    fn deserialization_too_deep_nesting() {
+        let mut bytes = Vec::new();
+        for _ in 0..=MAX_NESTING_DEPTH {
+            utils::write_string(&mut bytes, LinearWarmup::TAG).unwrap();
+            utils::write_u32(&mut bytes, 1).unwrap();
+        }
+
+        let res = deserialize_scheduler(&mut bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::InvalidScheduler(_))));
+    }
+}
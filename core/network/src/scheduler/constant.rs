@@ -0,0 +1,52 @@
+use std::io::{Read, Write};
+
+use crate::{error::NetworkError, utils};
+
+use super::{read_finite, LrScheduler};
+
+pub struct Constant {
+    pub learning_rate: f64,
+}
+
+impl Constant {
+    pub const TAG: &'static str = "constant";
+
+    pub fn new(learning_rate: f64) -> Self {
+        Constant { learning_rate }
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let learning_rate = read_finite(reader, "learning rate")?;
+
+        Ok(Constant::new(learning_rate))
+    }
+}
+
+impl LrScheduler for Constant {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn learning_rate(&self, _step: usize) -> f64 {
+        self.learning_rate
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.learning_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialization_invalid_learning_rate() {
+        let mut bytes = Vec::new();
+        utils::write_f64(&mut bytes, f64::NAN).unwrap();
+
+        let res = Constant::deserialize(&mut bytes.as_slice());
+
+        assert!(matches!(res, Err(NetworkError::InvalidScheduler(_))));
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    f64::consts::PI,
+    io::{Read, Write},
+};
+
+use crate::{error::NetworkError, utils};
+
+use super::{read_finite, read_steps, write_steps, LrScheduler};
+
+// cosine annealing from max to min learning rate, which restarts from max one at the end of each
+// cycle. first cycle takes period steps, and each next one is period_mult times longer
+pub struct CosineWarmRestarts {
+    pub max_learning_rate: f64,
+    pub min_learning_rate: f64,
+    pub period: usize,
+    pub period_mult: usize,
+}
+
+impl CosineWarmRestarts {
+    pub const TAG: &'static str = "cosine_warm_restarts";
+
+    pub fn new(
+        max_learning_rate: f64,
+        min_learning_rate: f64,
+        period: usize,
+        period_mult: usize,
+    ) -> Self {
+        assert!(period > 0, "period should be positive");
+        assert!(period_mult > 0, "period multiplier should be positive");
+
+        CosineWarmRestarts {
+            max_learning_rate,
+            min_learning_rate,
+            period,
+            period_mult,
+        }
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let max_learning_rate = read_finite(reader, "max learning rate")?;
+        let min_learning_rate = read_finite(reader, "min learning rate")?;
+        let period = read_steps(reader, "period")?;
+        let period_mult = read_steps(reader, "period multiplier")?;
+
+        Ok(CosineWarmRestarts::new(
+            max_learning_rate,
+            min_learning_rate,
+            period,
+            period_mult,
+        ))
+    }
+
+    // step within current cycle and length of the cycle
+    fn get_cycle_position(&self, mut step: usize) -> (usize, usize) {
+        let mut period = self.period;
+
+        while step >= period {
+            step -= period;
+            period = period.saturating_mul(self.period_mult);
+        }
+
+        (step, period)
+    }
+}
+
+impl LrScheduler for CosineWarmRestarts {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn learning_rate(&self, step: usize) -> f64 {
+        let (step, period) = self.get_cycle_position(step);
+        let progress = step as f64 / period as f64;
+
+        self.min_learning_rate
+            + (self.max_learning_rate - self.min_learning_rate) * (1.0 + (PI * progress).cos())
+                / 2.0
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.max_learning_rate)?;
+        utils::write_f64(writer, self.min_learning_rate)?;
+        write_steps(writer, self.period)?;
+        write_steps(writer, self.period_mult)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learning_rate() {
+        let scheduler = CosineWarmRestarts::new(1.0, 0.0, 4, 2);
+
+        // first cycle is 4 steps, second one is 8 steps
+        assert_eq!(scheduler.learning_rate(0), 1.0);
+        assert!((scheduler.learning_rate(2) - 0.5).abs() < 1e-12);
+        assert_eq!(scheduler.learning_rate(4), 1.0);
+        assert!((scheduler.learning_rate(8) - 0.5).abs() < 1e-12);
+        assert_eq!(scheduler.learning_rate(12), 1.0);
+    }
+}
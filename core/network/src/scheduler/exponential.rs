@@ -0,0 +1,57 @@
+use std::io::{Read, Write};
+
+use crate::{error::NetworkError, utils};
+
+use super::{read_finite, LrScheduler};
+
+// multiplies learning rate by gamma every step
+pub struct Exponential {
+    pub initial_learning_rate: f64,
+    pub gamma: f64,
+}
+
+impl Exponential {
+    pub const TAG: &'static str = "exponential";
+
+    pub fn new(initial_learning_rate: f64, gamma: f64) -> Self {
+        Exponential {
+            initial_learning_rate,
+            gamma,
+        }
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let initial_learning_rate = read_finite(reader, "learning rate")?;
+        let gamma = read_finite(reader, "gamma")?;
+
+        Ok(Exponential::new(initial_learning_rate, gamma))
+    }
+}
+
+impl LrScheduler for Exponential {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn learning_rate(&self, step: usize) -> f64 {
+        self.initial_learning_rate * self.gamma.powf(step as f64)
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.initial_learning_rate)?;
+        utils::write_f64(writer, self.gamma)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learning_rate() {
+        let scheduler = Exponential::new(0.1, 0.9);
+
+        assert_eq!(scheduler.learning_rate(0), 0.1);
+        assert!((scheduler.learning_rate(2) - 0.081).abs() < 1e-12);
+    }
+}
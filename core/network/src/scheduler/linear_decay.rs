@@ -0,0 +1,73 @@
+use std::io::{Read, Write};
+
+use crate::{error::NetworkError, utils};
+
+use super::{read_finite, read_steps, write_steps, LrScheduler};
+
+// changes learning rate linearly from initial to final one over given steps, and keeps final one
+// afterwards
+pub struct LinearDecay {
+    pub initial_learning_rate: f64,
+    pub final_learning_rate: f64,
+    pub steps: usize,
+}
+
+impl LinearDecay {
+    pub const TAG: &'static str = "linear_decay";
+
+    pub fn new(initial_learning_rate: f64, final_learning_rate: f64, steps: usize) -> Self {
+        assert!(steps > 0, "steps count should be positive");
+
+        LinearDecay {
+            initial_learning_rate,
+            final_learning_rate,
+            steps,
+        }
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let initial_learning_rate = read_finite(reader, "initial learning rate")?;
+        let final_learning_rate = read_finite(reader, "final learning rate")?;
+        let steps = read_steps(reader, "steps count")?;
+
+        Ok(LinearDecay::new(
+            initial_learning_rate,
+            final_learning_rate,
+            steps,
+        ))
+    }
+}
+
+impl LrScheduler for LinearDecay {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn learning_rate(&self, step: usize) -> f64 {
+        let progress = step.min(self.steps) as f64 / self.steps as f64;
+
+        self.initial_learning_rate
+            - (self.initial_learning_rate - self.final_learning_rate) * progress
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.initial_learning_rate)?;
+        utils::write_f64(writer, self.final_learning_rate)?;
+        write_steps(writer, self.steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learning_rate() {
+        let scheduler = LinearDecay::new(0.1, 0.05, 10);
+
+        assert_eq!(scheduler.learning_rate(0), 0.1);
+        assert!((scheduler.learning_rate(5) - 0.075).abs() < 1e-12);
+        assert_eq!(scheduler.learning_rate(10), 0.05);
+        assert_eq!(scheduler.learning_rate(20), 0.05);
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::{Read, Write};
+
+use crate::error::NetworkError;
+
+use super::{
+    deserialize_nested_scheduler, read_steps, serialize_scheduler, write_steps, LrScheduler,
+};
+
+// grows learning rate linearly during first warmup_steps up to the initial rate of wrapped
+// scheduler, which then starts from its own step 0
+pub struct LinearWarmup {
+    pub warmup_steps: usize,
+    pub scheduler: Box<dyn LrScheduler>,
+}
+
+impl LinearWarmup {
+    pub const TAG: &'static str = "linear_warmup";
+
+    pub fn new(warmup_steps: usize, scheduler: impl LrScheduler + 'static) -> Self {
+        Self::from_boxed(warmup_steps, Box::new(scheduler))
+    }
+
+    pub fn from_boxed(warmup_steps: usize, scheduler: Box<dyn LrScheduler>) -> Self {
+        assert!(warmup_steps > 0, "warmup steps count should be positive");
+
+        LinearWarmup {
+            warmup_steps,
+            scheduler,
+        }
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read, depth: usize) -> Result<Self, NetworkError> {
+        let warmup_steps = read_steps(reader, "warmup steps count")?;
+        let scheduler = deserialize_nested_scheduler(reader, depth + 1)?;
+
+        Ok(LinearWarmup::from_boxed(warmup_steps, scheduler))
+    }
+}
+
+impl LrScheduler for LinearWarmup {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn learning_rate(&self, step: usize) -> f64 {
+        if step < self.warmup_steps {
+            let progress = (step + 1) as f64 / (self.warmup_steps + 1) as f64;
+            self.scheduler.learning_rate(0) * progress
+        } else {
+            self.scheduler.learning_rate(step - self.warmup_steps)
+        }
+    }
+
+    fn on_epoch_end(&mut self, loss: f64) {
+        self.scheduler.on_epoch_end(loss);
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        write_steps(writer, self.warmup_steps)?;
+        serialize_scheduler(self.scheduler.as_ref(), writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scheduler::constant::Constant;
+
+    use super::*;
+
+    #[test]
+    fn learning_rate() {
+        let scheduler = LinearWarmup::new(3, Constant::new(0.8));
+
+        let rates: Vec<f64> = (0..5).map(|step| scheduler.learning_rate(step)).collect();
+
+        assert_eq!(rates, vec![0.2, 0.4, 0.6000000000000001, 0.8, 0.8]);
+    }
+}
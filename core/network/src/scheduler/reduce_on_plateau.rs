@@ -0,0 +1,126 @@
+use std::io::{Read, Write};
+
+use crate::{error::NetworkError, utils};
+
+use super::{read_finite, LrScheduler};
+
+// multiplies learning rate by factor when monitored loss hasn't improved by at least min_delta
+// for more than patience epochs, but doesn't go below min learning rate
+pub struct ReduceOnPlateau {
+    learning_rate: f64,
+    factor: f64,
+    patience: usize,
+    min_learning_rate: f64,
+    min_delta: f64,
+    best_loss: f64,
+    bad_epochs: usize,
+}
+
+impl ReduceOnPlateau {
+    pub const TAG: &'static str = "reduce_on_plateau";
+
+    pub fn new(learning_rate: f64, factor: f64, patience: usize, min_learning_rate: f64) -> Self {
+        assert!(factor > 0.0 && factor < 1.0, "factor should be in (0, 1)");
+
+        ReduceOnPlateau {
+            learning_rate,
+            factor,
+            patience,
+            min_learning_rate,
+            min_delta: 0.0,
+            best_loss: f64::INFINITY,
+            bad_epochs: 0,
+        }
+    }
+
+    pub fn with_min_delta(mut self, min_delta: f64) -> Self {
+        self.min_delta = min_delta;
+        self
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let learning_rate = read_finite(reader, "learning rate")?;
+        let factor = read_finite(reader, "factor")?;
+        let patience = utils::read_u32(reader)? as usize;
+        let min_learning_rate = read_finite(reader, "min learning rate")?;
+        let min_delta = read_finite(reader, "min delta")?;
+        // infinite before first epoch
+        let best_loss = utils::read_f64(reader)?;
+        let bad_epochs = utils::read_u32(reader)? as usize;
+
+        if factor <= 0.0 || factor >= 1.0 {
+            return Err(NetworkError::InvalidScheduler(format!(
+                "invalid factor: {factor}"
+            )));
+        }
+
+        Ok(ReduceOnPlateau {
+            learning_rate,
+            factor,
+            patience,
+            min_learning_rate,
+            min_delta,
+            best_loss,
+            bad_epochs,
+        })
+    }
+}
+
+impl LrScheduler for ReduceOnPlateau {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn learning_rate(&self, _step: usize) -> f64 {
+        self.learning_rate
+    }
+
+    fn on_epoch_end(&mut self, loss: f64) {
+        if loss < self.best_loss - self.min_delta {
+            self.best_loss = loss;
+            self.bad_epochs = 0;
+            return;
+        }
+
+        self.bad_epochs += 1;
+
+        if self.bad_epochs > self.patience {
+            self.learning_rate = (self.learning_rate * self.factor).max(self.min_learning_rate);
+            self.bad_epochs = 0;
+        }
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.learning_rate)?;
+        utils::write_f64(writer, self.factor)?;
+        utils::write_u32(writer, self.patience as u32)?;
+        utils::write_f64(writer, self.min_learning_rate)?;
+        utils::write_f64(writer, self.min_delta)?;
+        utils::write_f64(writer, self.best_loss)?;
+        utils::write_u32(writer, self.bad_epochs as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learning_rate() {
+        let mut scheduler = ReduceOnPlateau::new(0.1, 0.5, 1, 0.03);
+
+        let rates: Vec<f64> = [1.0, 0.9, 0.95, 0.95, 0.8, 0.9, 0.9, 0.9, 0.9]
+            .iter()
+            .map(|loss| {
+                scheduler.on_epoch_end(*loss);
+                scheduler.learning_rate(0)
+            })
+            .collect();
+
+        // reduced after two bad epochs in a row, but not below min rate
+        assert_eq!(
+            rates,
+            vec![0.1, 0.1, 0.1, 0.05, 0.05, 0.05, 0.03, 0.03, 0.03]
+        );
+    }
+}
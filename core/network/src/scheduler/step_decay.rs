@@ -0,0 +1,65 @@
+use std::io::{Read, Write};
+
+use crate::{error::NetworkError, utils};
+
+use super::{read_finite, read_steps, write_steps, LrScheduler};
+
+// multiplies learning rate by gamma every step_size steps
+pub struct StepDecay {
+    pub initial_learning_rate: f64,
+    pub gamma: f64,
+    pub step_size: usize,
+}
+
+impl StepDecay {
+    pub const TAG: &'static str = "step_decay";
+
+    pub fn new(initial_learning_rate: f64, gamma: f64, step_size: usize) -> Self {
+        assert!(step_size > 0, "step size should be positive");
+
+        StepDecay {
+            initial_learning_rate,
+            gamma,
+            step_size,
+        }
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<Self, NetworkError> {
+        let initial_learning_rate = read_finite(reader, "learning rate")?;
+        let gamma = read_finite(reader, "gamma")?;
+        let step_size = read_steps(reader, "step size")?;
+
+        Ok(StepDecay::new(initial_learning_rate, gamma, step_size))
+    }
+}
+
+impl LrScheduler for StepDecay {
+    fn tag(&self) -> &'static str {
+        Self::TAG
+    }
+
+    fn learning_rate(&self, step: usize) -> f64 {
+        let decays = (step / self.step_size) as i32;
+        self.initial_learning_rate * self.gamma.powi(decays)
+    }
+
+    fn serialize(&self, writer: &mut dyn Write) -> Result<(), NetworkError> {
+        utils::write_f64(writer, self.initial_learning_rate)?;
+        utils::write_f64(writer, self.gamma)?;
+        write_steps(writer, self.step_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learning_rate() {
+        let scheduler = StepDecay::new(0.1, 0.5, 3);
+
+        let rates: Vec<f64> = (0..7).map(|step| scheduler.learning_rate(step)).collect();
+
+        assert_eq!(rates, vec![0.1, 0.1, 0.1, 0.05, 0.05, 0.05, 0.025]);
+    }
+}
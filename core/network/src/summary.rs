@@ -0,0 +1,287 @@
+use std::fmt;
+
+use autograd::val::BVal;
+
+use crate::{
+    layer::activation::{Activation, ActivationKind},
+    network::Network,
+};
+
+// parameters with smaller absolute values are counted as near zero, e.g. after pruning or
+// strong L1 regularization
+const NEAR_ZERO_THRESHOLD: f64 = 1e-3;
+
+// structured description of the network, see Network::summary()
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkSummary {
+    pub input_shape: Vec<usize>,
+    pub output_shape: Vec<usize>,
+    pub layers: Vec<LayerSummary>,
+    pub parameters_count: usize,
+    // parameters of layers which aren't frozen
+    pub trainable_parameters_count: usize,
+    // statistics of all parameters, or None if network has no parameters
+    pub weight_stats: Option<WeightStats>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerSummary {
+    pub tag: &'static str,
+    pub input_shape: Vec<usize>,
+    pub output_shape: Vec<usize>,
+    // activation function, for activation layers
+    pub activation: Option<ActivationKind>,
+    pub parameters_count: usize,
+    pub trainable: bool,
+    pub weight_stats: Option<WeightStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightStats {
+    pub mean: f64,
+    // population standard deviation
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+    // fraction of parameters with absolute value below NEAR_ZERO_THRESHOLD
+    pub near_zero: f64,
+}
+
+impl WeightStats {
+    // None for empty list of parameters
+    pub fn from_parameters(parameters: &[BVal]) -> Option<Self> {
+        if parameters.is_empty() {
+            return None;
+        }
+
+        let values: Vec<f64> = parameters.iter().map(|p| p.borrow().d).collect();
+        let count = values.len() as f64;
+
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = values.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / count;
+
+        Some(WeightStats {
+            mean,
+            std: variance.sqrt(),
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            near_zero: values
+                .iter()
+                .filter(|d| d.abs() < NEAR_ZERO_THRESHOLD)
+                .count() as f64
+                / count,
+        })
+    }
+}
+
+impl LayerSummary {
+    // layer tag with activation function, e.g. "activation (tanh)"
+    pub fn name(&self) -> String {
+        match self.activation {
+            Some(kind) => format!("{} ({})", self.tag, kind.name()),
+            None => String::from(self.tag),
+        }
+    }
+}
+
+impl NetworkSummary {
+    pub(crate) fn new(net: &Network) -> Self {
+        let mut layers = Vec::new();
+        let mut shape = net.input_shape().to_vec();
+
+        for (idx, layer) in net.layers.iter().enumerate() {
+            // shapes were checked when network was created
+            let output_shape = layer
+                .output_shape(&shape)
+                .expect("layer can't take outputs of previous one");
+            let parameters = layer.parameters();
+
+            layers.push(LayerSummary {
+                tag: layer.tag(),
+                input_shape: shape,
+                output_shape: output_shape.clone(),
+                activation: layer
+                    .as_any()
+                    .downcast_ref::<Activation>()
+                    .map(|activation| activation.kind),
+                parameters_count: parameters.len(),
+                trainable: net.is_trainable(idx),
+                weight_stats: WeightStats::from_parameters(&parameters),
+            });
+
+            shape = output_shape;
+        }
+
+        NetworkSummary {
+            input_shape: net.input_shape().to_vec(),
+            output_shape: shape,
+            layers,
+            parameters_count: net.parameters().len(),
+            trainable_parameters_count: net.trainable_parameters().len(),
+            weight_stats: WeightStats::from_parameters(net.parameters()),
+        }
+    }
+}
+
+impl fmt::Display for NetworkSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>3}  {:<20} {:<16} {:<16} {:>10}  {:>9} {:>9} {:>9} {:>9} {:>6}",
+            "#", "layer", "input", "output", "params", "mean", "std", "min", "max", "zeros"
+        )?;
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let mut name = layer.name();
+
+            if !layer.trainable {
+                name += " *";
+            }
+
+            write!(
+                f,
+                "{:>3}  {:<20} {:<16} {:<16} {:>10}",
+                idx,
+                name,
+                format_shape(&layer.input_shape),
+                format_shape(&layer.output_shape),
+                layer.parameters_count
+            )?;
+
+            if let Some(stats) = &layer.weight_stats {
+                write!(f, "  {stats}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        write!(
+            f,
+            "input: {}, output: {}, parameters: {}",
+            format_shape(&self.input_shape),
+            format_shape(&self.output_shape),
+            self.parameters_count
+        )?;
+
+        // frozen layers are marked with asterisk
+        if self.trainable_parameters_count != self.parameters_count {
+            write!(f, ", trainable: {}", self.trainable_parameters_count)?;
+        }
+
+        writeln!(f)?;
+
+        if let Some(stats) = &self.weight_stats {
+            write!(
+                f,
+                "weights: mean = {:.4}, std = {:.4}, min = {:.4}, max = {:.4}, near zero = {:.2}%",
+                stats.mean,
+                stats.std,
+                stats.min,
+                stats.max,
+                stats.near_zero * 100.0
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for WeightStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>9.4} {:>9.4} {:>9.4} {:>9.4} {:>5.1}%",
+            self.mean,
+            self.std,
+            self.min,
+            self.max,
+            self.near_zero * 100.0
+        )
+    }
+}
+
+fn format_shape(shape: &[usize]) -> String {
+    let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+    format!("[{}]", dims.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layer::{dense::Dense, flatten::Flatten, Layer};
+
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Flatten::new()),
+            Box::new(Dense::new(4, 2)),
+            Box::new(Activation::relu()),
+        ];
+        let net = Network::from_layers(vec![2, 2], layers).unwrap();
+
+        let values = [0.0, 0.5, -0.5, 1.0, -1.0, 0.0005, 2.0, -2.0, 0.0, 0.5];
+        for (param, d) in net.parameters().iter().zip(values) {
+            param.borrow_mut().d = d;
+        }
+
+        let summary = net.summary();
+
+        assert_eq!(summary.input_shape, vec![2, 2]);
+        assert_eq!(summary.output_shape, vec![2]);
+        assert_eq!(summary.parameters_count, 10);
+        assert_eq!(summary.trainable_parameters_count, 10);
+
+        let tags: Vec<&str> = summary.layers.iter().map(|layer| layer.tag).collect();
+        assert_eq!(tags, vec!["flatten", "dense", "activation"]);
+
+        assert_eq!(summary.layers[0].output_shape, vec![4]);
+        assert_eq!(summary.layers[1].input_shape, vec![4]);
+        assert_eq!(summary.layers[1].parameters_count, 10);
+        assert_eq!(summary.layers[2].activation, Some(ActivationKind::Relu));
+        assert_eq!(summary.layers[2].weight_stats, None);
+
+        let stats = summary.weight_stats.unwrap();
+        assert!((stats.mean - 0.05005).abs() < 1e-12);
+        assert_eq!(stats.min, -2.0);
+        assert_eq!(stats.max, 2.0);
+        assert_eq!(stats.near_zero, 0.3);
+        assert_eq!(summary.layers[1].weight_stats, Some(stats));
+    }
+
+    #[test]
+    fn weight_stats() {
+        let parameters: Vec<BVal> = [1.0, 3.0].iter().map(|d| BVal::new(*d)).collect();
+
+        let stats = WeightStats::from_parameters(&parameters).unwrap();
+
+        assert_eq!(stats.mean, 2.0);
+        assert_eq!(stats.std, 1.0);
+        assert_eq!(stats.near_zero, 0.0);
+        assert_eq!(WeightStats::from_parameters(&[]), None);
+    }
+
+    #[test]
+    fn display() {
+        let net = Network::new(vec![3, 2]);
+
+        let text = net.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].contains("dense"));
+        assert!(lines[1].contains("[3]"));
+        assert!(lines[2].contains("activation (tanh)"));
+        assert!(lines[3].starts_with("input: [3], output: [2], parameters: 8"));
+        assert!(lines[4].starts_with("weights: mean = "));
+
+        let mut net = net;
+        net.set_trainable(0, false);
+
+        let text = net.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines[1].contains("dense *"));
+        assert!(lines[3].ends_with("parameters: 8, trainable: 0"));
+    }
+}
@@ -0,0 +1,101 @@
+// fixtures shared by tests of training code
+
+use autograd::val::BVal;
+
+use crate::network::Network;
+
+// points of two classes on both sides of a line, expected output is 1 or -1
+pub fn create_dataset(count: usize) -> Vec<(Vec<f64>, f64)> {
+    (0..count)
+        .map(|idx| {
+            let x = (idx as f64 * 0.61).sin();
+            let y = (idx as f64 * 1.37).cos();
+            let expected = if x + 0.5 * y > 0.1 { 1.0 } else { -1.0 };
+            (vec![x, y], expected)
+        })
+        .collect()
+}
+
+// squared error of the single output, and whether it has the right sign
+pub fn calc_loss(net: &Network, (input, expected): &(Vec<f64>, f64)) -> (BVal, Option<bool>) {
+    let output = &net.forward(input)[0];
+    let loss = (*expected - output).pow(2.0);
+    let correct = (output.borrow().d > 0.0) == (*expected > 0.0);
+    (loss, Some(correct))
+}
+
+// samples of given number of classes, each one is a noisy copy of class prototype. samples
+// with different offsets don't repeat each other
+pub fn create_classes_dataset(
+    count: usize,
+    offset: usize,
+    classes_count: usize,
+    inputs_count: usize,
+) -> Vec<(Vec<f64>, usize)> {
+    (offset..offset + count)
+        .map(|idx| {
+            let label = idx % classes_count;
+            let input = (0..inputs_count)
+                .map(|j| {
+                    let prototype = ((label * inputs_count + j) as f64 * 1.3).sin();
+                    let noise = ((idx * inputs_count + j) as f64 * 0.77).sin() * 0.4;
+                    prototype + noise
+                })
+                .collect();
+            (input, label)
+        })
+        .collect()
+}
+
+// squared error of outputs against one-hot target of 1 and -1, and whether the biggest output
+// is the label one
+pub fn calc_classes_loss(
+    net: &Network,
+    (input, label): &(Vec<f64>, usize),
+) -> (BVal, Option<bool>) {
+    let output = net.forward(input);
+
+    let mut loss = BVal::new(0.0);
+    for (idx, out) in output.iter().enumerate() {
+        let expected = if idx == *label { 1.0 } else { -1.0 };
+        loss = &loss + &(expected - out).pow(2.0);
+    }
+
+    let outputs: Vec<f64> = output.iter().map(|out| out.borrow().d).collect();
+
+    (loss, Some(predict(&outputs) == *label))
+}
+
+// index of the biggest output
+pub fn predict<T: PartialOrd>(outputs: &[T]) -> usize {
+    let mut best = 0;
+    for (idx, out) in outputs.iter().enumerate() {
+        if *out > outputs[best] {
+            best = idx;
+        }
+    }
+    best
+}
+
+// copy through model file, with the same state of dropout generators, which isn't part of it
+pub fn clone_network(net: &Network) -> Network {
+    let mut bytes = Vec::new();
+    net.serialize_to_writer(&mut bytes).unwrap();
+    let clone = Network::deserialize_from_reader(bytes.as_slice()).unwrap();
+
+    for (layer, cloned_layer) in net.layers.iter().zip(&clone.layers) {
+        if let (Some(rng), Some(cloned_rng)) = (layer.rng(), cloned_layer.rng()) {
+            *cloned_rng.borrow_mut() = rng.borrow().clone();
+        }
+    }
+
+    clone
+}
+
+pub fn get_params(net: &Network) -> Vec<f64> {
+    net.parameters().iter().map(|p| p.borrow().d).collect()
+}
+
+pub fn get_grads(net: &Network) -> Vec<f64> {
+    net.parameters().iter().map(|p| p.borrow().grad).collect()
+}
@@ -0,0 +1,1033 @@
+pub mod callbacks;
+pub mod checkpoint;
+
+use std::{iter, mem, slice, sync::Arc, time::Instant};
+
+use autograd::val::BVal;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    error::NetworkError,
+    layer::batch_norm1d::BatchNorm1D,
+    network::Network,
+    optim::Optimizer,
+    parallel::{self, BatchResult, DataParallel},
+    prune::PruningSchedule,
+    regularization::Regularization,
+    rng,
+    scheduler::LrScheduler,
+};
+
+// number of samples processed at once by one worker in multi-threaded mode. it doesn't depend on
+// threads count, so training results don't depend on it either
+const DEFAULT_SHARD_SIZE: usize = 2;
+
+// source of training samples, which is iterated once per epoch. batches are taken from the
+// iterator one by one, and the last one may be smaller than the batch size
+pub trait Dataset {
+    type Sample;
+
+    fn samples(&self) -> Box<dyn Iterator<Item = Self::Sample> + '_>;
+}
+
+impl<S: Clone> Dataset for Vec<S> {
+    type Sample = S;
+
+    fn samples(&self) -> Box<dyn Iterator<Item = S> + '_> {
+        Box::new(self.as_slice().iter().cloned())
+    }
+}
+
+// loss of one sample, and whether network prediction for it is correct, or None if it doesn't
+// make sense (e.g. for regression)
+pub type LossFn<S> = dyn Fn(&Network, &S) -> (BVal, Option<bool>) + Send + Sync;
+
+// losses of samples of one shard, computed at once with Network::forward_batch(), which batch norm
+// needs to normalize with batch statistics while training (see parallel::BatchLossFn)
+pub type BatchLossFn<S> = parallel::BatchLossFn<S, Option<bool>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+// hooks into training loop. callbacks are called in order they were added, and training stops
+// after current batch or epoch if any of them returns Control::Stop
+pub trait Callback {
+    fn on_batch_end(
+        &mut self,
+        _net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        _metrics: &BatchMetrics,
+    ) -> Result<Control, NetworkError> {
+        Ok(Control::Continue)
+    }
+
+    fn on_epoch_end(
+        &mut self,
+        _net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        _metrics: &EpochMetrics,
+    ) -> Result<Control, NetworkError> {
+        Ok(Control::Continue)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchMetrics {
+    pub epoch: usize,
+    pub batch: usize,
+    // global step, i.e. number of batches trained before this one
+    pub step: usize,
+    pub samples: usize,
+    // sum of samples losses and regularization penalty
+    pub loss: f64,
+    // number of wrong predictions, if loss function reports them
+    pub errors: Option<usize>,
+    // learning rate the batch was trained with
+    pub learning_rate: f64,
+    pub duration_ms: u128,
+}
+
+impl BatchMetrics {
+    pub fn errors_percent(&self) -> Option<f64> {
+        self.errors
+            .map(|errors| errors as f64 / self.samples as f64 * 100.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochMetrics {
+    pub epoch: usize,
+    pub batches: usize,
+    pub samples: usize,
+    // mean loss of training samples, without regularization penalty
+    pub loss: f64,
+    pub errors: Option<usize>,
+    pub validation: Option<ValidationMetrics>,
+    pub duration_ms: u128,
+}
+
+impl EpochMetrics {
+    pub fn errors_percent(&self) -> Option<f64> {
+        self.errors
+            .map(|errors| errors as f64 / self.samples as f64 * 100.0)
+    }
+
+    // loss to judge progress by, i.e. validation one if there is validation dataset
+    pub fn monitored_loss(&self) -> f64 {
+        self.validation
+            .as_ref()
+            .map_or(self.loss, |validation| validation.loss)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationMetrics {
+    pub samples: usize,
+    // mean loss of validation samples
+    pub loss: f64,
+    pub errors: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrainReport {
+    pub epochs: Vec<EpochMetrics>,
+    // whether training was stopped by a callback before running all epochs
+    pub stopped: bool,
+}
+
+// training loop shared by experiments: splits dataset into batches, runs forward and backward
+// passes (in multiple threads if asked), adds regularization penalty, updates parameters with
+// the optimizer and reports metrics to callbacks
+pub struct Trainer<S> {
+    loss_fn: Arc<BatchLossFn<S>>,
+    // whether loss function runs network on each sample separately, see with_batch_loss()
+    per_sample_loss: bool,
+    optimizer: Box<dyn Optimizer>,
+    epochs: usize,
+    batch_size: usize,
+    threads: usize,
+    shard_size: usize,
+    regularization: Option<Regularization>,
+    scheduler: Option<Box<dyn LrScheduler>>,
+    validation: Option<Box<dyn Dataset<Sample = S>>>,
+    shuffle: Option<Shuffle>,
+    pruning: Option<PruningSchedule>,
+    callbacks: Vec<Box<dyn Callback>>,
+    // file to save training checkpoint to each nth batch (if set) and at the end of each epoch
+    checkpoint: Option<(String, Option<usize>)>,
+    // number of batches trained by all fit() calls
+    step: usize,
+    progress: Progress,
+}
+
+// position of training, so fit() can continue interrupted training from the same batch
+#[derive(Debug, Clone, PartialEq)]
+struct Progress {
+    epoch: usize,
+    // number of batches of the epoch trained so far, and their metrics
+    batch: usize,
+    samples: usize,
+    loss: f64,
+    errors: Option<usize>,
+}
+
+impl Progress {
+    fn new(epoch: usize) -> Self {
+        Progress {
+            epoch,
+            batch: 0,
+            samples: 0,
+            loss: 0.0,
+            errors: Some(0),
+        }
+    }
+}
+
+// order of samples is generated from the seed and epoch index, so it doesn't depend on how many
+// epochs were trained before, e.g. when training is resumed from checkpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Shuffle {
+    buffer_size: usize,
+    seed: u64,
+}
+
+impl<S> Trainer<S>
+where
+    S: Clone + Send + 'static,
+{
+    pub fn new<F>(loss_fn: F, optimizer: impl Optimizer + 'static) -> Self
+    where
+        F: Fn(&Network, &S) -> (BVal, Option<bool>) + Send + Sync + 'static,
+    {
+        let mut trainer = Self::with_batch_loss(parallel::per_sample_loss(loss_fn), optimizer);
+        trainer.per_sample_loss = true;
+        trainer
+    }
+
+    // loss function gets whole shard of samples (see shard_size()), so networks with batch norm
+    // can be trained with batch statistics of the shard
+    pub fn with_batch_loss<F>(loss_fn: F, optimizer: impl Optimizer + 'static) -> Self
+    where
+        F: Fn(&Network, &[S]) -> Vec<(BVal, Option<bool>)> + Send + Sync + 'static,
+    {
+        Trainer {
+            loss_fn: Arc::new(loss_fn),
+            per_sample_loss: false,
+            optimizer: Box::new(optimizer),
+            epochs: 1,
+            batch_size: 1,
+            threads: 1,
+            shard_size: DEFAULT_SHARD_SIZE,
+            regularization: None,
+            scheduler: None,
+            validation: None,
+            shuffle: None,
+            pruning: None,
+            callbacks: Vec::new(),
+            checkpoint: None,
+            step: 0,
+            progress: Progress::new(0),
+        }
+    }
+
+    pub fn epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch size should be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    // single thread builds computation graph right on the network, while multiple threads train
+    // network replicas. results are the same for any threads count
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "threads count should be positive");
+        self.threads = threads;
+        self
+    }
+
+    // batch norm normalizes each shard with its own statistics, so it's the batch size for it
+    pub fn shard_size(mut self, shard_size: usize) -> Self {
+        assert!(shard_size > 0, "shard size should be positive");
+        self.shard_size = shard_size;
+        self
+    }
+
+    pub fn regularization(mut self, regularization: Option<Regularization>) -> Self {
+        self.regularization = regularization;
+        self
+    }
+
+    // sets learning rate of the optimizer before each batch
+    pub fn scheduler(mut self, scheduler: impl LrScheduler + 'static) -> Self {
+        self.scheduler = Some(Box::new(scheduler));
+        self
+    }
+
+    // dataset evaluated after each epoch in eval mode, without updating the network
+    pub fn validation(mut self, dataset: impl Dataset<Sample = S> + 'static) -> Self {
+        self.validation = Some(Box::new(dataset));
+        self
+    }
+
+    // shuffles samples of each epoch within buffer of given size, so dataset doesn't have to fit
+    // in memory. buffer as big as dataset gives uniform shuffle. seed is taken from rng::seed()
+    pub fn shuffle(mut self, buffer_size: usize) -> Self {
+        assert!(buffer_size > 0, "shuffle buffer size should be positive");
+        self.shuffle = Some(Shuffle {
+            buffer_size,
+            seed: rng::gen_seed(),
+        });
+        self
+    }
+
+    // prunes network after optimizer step according to schedule of global steps
+    pub fn pruning(mut self, schedule: PruningSchedule) -> Self {
+        self.pruning = Some(schedule);
+        self
+    }
+
+    pub fn callback(mut self, callback: impl Callback + 'static) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    // saves training checkpoint with save_checkpoint_to_file() each nth batch (if set) and at the
+    // end of each epoch, so training can be resumed after interruption
+    pub fn checkpoint(mut self, file_path: &str, each_nth_batch: Option<usize>) -> Self {
+        self.checkpoint = Some((String::from(file_path), each_nth_batch));
+        self
+    }
+
+    pub fn optimizer(&self) -> &dyn Optimizer {
+        self.optimizer.as_ref()
+    }
+
+    pub fn lr_scheduler(&self) -> Option<&dyn LrScheduler> {
+        self.scheduler.as_deref()
+    }
+
+    pub fn global_step(&self) -> usize {
+        self.step
+    }
+
+    // lets training be resumed from checkpoint, so schedules continue from the same step
+    pub fn set_global_step(&mut self, step: usize) {
+        self.step = step;
+    }
+
+    // index of the epoch fit() starts or continues with
+    pub fn epoch(&self) -> usize {
+        self.progress.epoch
+    }
+
+    // trains network until the last epoch. if previous fit() call was stopped by a callback, or
+    // trainer was resumed from checkpoint, training continues from the same position
+    pub fn fit(
+        &mut self,
+        net: &mut Network,
+        dataset: &dyn Dataset<Sample = S>,
+    ) -> Result<TrainReport, NetworkError> {
+        // single sample runs through batch norm with running statistics, which would never be
+        // updated, so training it needs batch loss
+        let trains_batch_norm = net.layers.iter().enumerate().any(|(idx, layer)| {
+            net.is_trainable(idx) && layer.as_any().downcast_ref::<BatchNorm1D>().is_some()
+        });
+
+        if self.per_sample_loss && trains_batch_norm {
+            return Err(NetworkError::UnsupportedLayer(format!(
+                "{} with per-sample loss, use Trainer::with_batch_loss()",
+                BatchNorm1D::TAG
+            )));
+        }
+
+        let pool = if self.threads > 1 {
+            let loss_fn = self.loss_fn.clone();
+            Some(DataParallel::with_batch_loss(
+                net,
+                self.threads,
+                self.shard_size,
+                move |net: &Network, samples: &[S]| loss_fn(net, samples),
+            ))
+        } else {
+            None
+        };
+
+        net.train();
+
+        let res = self.run_epochs(net, dataset, pool.as_ref());
+
+        net.eval();
+
+        res
+    }
+
+    fn run_epochs(
+        &mut self,
+        net: &mut Network,
+        dataset: &dyn Dataset<Sample = S>,
+        pool: Option<&DataParallel<S, Option<bool>>>,
+    ) -> Result<TrainReport, NetworkError> {
+        let mut report = TrainReport::default();
+
+        for epoch in self.progress.epoch..self.epochs {
+            let epoch_start = Instant::now();
+
+            let mut samples_it = match self.shuffle {
+                Some(shuffle) => {
+                    let mut rng = ChaCha8Rng::seed_from_u64(shuffle.seed);
+                    rng.set_stream(epoch as u64);
+                    shuffle_samples(dataset.samples(), shuffle.buffer_size, rng)
+                }
+                None => dataset.samples(),
+            };
+
+            // skip samples of batches trained before interruption
+            samples_it
+                .by_ref()
+                .take(self.progress.batch * self.batch_size)
+                .for_each(drop);
+
+            loop {
+                let batch: Vec<S> = samples_it.by_ref().take(self.batch_size).collect();
+
+                if batch.is_empty() {
+                    break;
+                }
+
+                let batch_start = Instant::now();
+
+                // forward / backward
+                net.reset_grad();
+
+                let res = match pool {
+                    // workers add gradients of the batch right to network parameters
+                    Some(pool) => pool.compute(net, &batch),
+                    None => {
+                        parallel::compute_sequential(net, &batch, self.shard_size, &*self.loss_fn)
+                    }
+                };
+
+                let errors = count_errors(&res);
+
+                // penalty gets its own backward pass, so its gradients are added to the batch ones
+                let mut batch_loss = res.loss;
+
+                if let Some(regularization) = self.regularization {
+                    let penalty = regularization.penalty(net.trainable_parameters());
+
+                    penalty.borrow_mut().grad = 1.0;
+                    penalty.backward();
+
+                    batch_loss += penalty.borrow().d;
+                }
+
+                // update
+                if let Some(scheduler) = &self.scheduler {
+                    self.optimizer
+                        .set_learning_rate(scheduler.learning_rate(self.step));
+                }
+
+                let learning_rate = self.optimizer.learning_rate();
+
+                // pruned parameters get neither gradients nor updates
+                net.apply_pruning_mask();
+                self.optimizer.step(net.trainable_parameters());
+                net.apply_pruning_mask();
+
+                if let Some(schedule) = &self.pruning {
+                    if let Some(sparsity) = schedule.sparsity(self.step) {
+                        net.prune(schedule.method, sparsity);
+                    }
+                }
+
+                let metrics = BatchMetrics {
+                    epoch,
+                    batch: self.progress.batch,
+                    step: self.step,
+                    samples: batch.len(),
+                    loss: batch_loss,
+                    errors,
+                    learning_rate,
+                    duration_ms: batch_start.elapsed().as_millis(),
+                };
+
+                self.progress.batch += 1;
+                self.progress.samples += batch.len();
+                self.progress.loss += res.loss;
+                self.progress.errors = self.progress.errors.zip(errors).map(|(a, b)| a + b);
+                self.step += 1;
+
+                if let Some((file_path, Some(each_nth_batch))) = &self.checkpoint {
+                    if self.progress.batch.checked_rem(*each_nth_batch) == Some(0) {
+                        self.save_checkpoint_to_file(net, file_path)?;
+                    }
+                }
+
+                let mut control = Control::Continue;
+                for callback in &mut self.callbacks {
+                    if callback.on_batch_end(net, self.optimizer.as_mut(), &metrics)?
+                        == Control::Stop
+                    {
+                        control = Control::Stop;
+                    }
+                }
+
+                if control == Control::Stop {
+                    report.stopped = true;
+                    return Ok(report);
+                }
+            }
+
+            let validation = match &self.validation {
+                Some(validation) => {
+                    net.eval();
+                    let metrics = validate(net, validation.as_ref(), &*self.loss_fn);
+                    net.train();
+                    Some(metrics)
+                }
+                None => None,
+            };
+
+            let metrics = EpochMetrics {
+                epoch,
+                batches: self.progress.batch,
+                samples: self.progress.samples,
+                loss: self.progress.loss / self.progress.samples.max(1) as f64,
+                errors: self.progress.errors,
+                validation,
+                duration_ms: epoch_start.elapsed().as_millis(),
+            };
+
+            if let Some(scheduler) = &mut self.scheduler {
+                scheduler.on_epoch_end(metrics.monitored_loss());
+            }
+
+            self.progress = Progress::new(epoch + 1);
+
+            if let Some((file_path, _)) = &self.checkpoint {
+                self.save_checkpoint_to_file(net, file_path)?;
+            }
+
+            let mut control = Control::Continue;
+            for callback in &mut self.callbacks {
+                if callback.on_epoch_end(net, self.optimizer.as_mut(), &metrics)? == Control::Stop {
+                    control = Control::Stop;
+                }
+            }
+
+            report.epochs.push(metrics);
+
+            if control == Control::Stop {
+                report.stopped = epoch + 1 < self.epochs;
+                return Ok(report);
+            }
+        }
+
+        // next fit() trains all epochs again
+        self.progress = Progress::new(0);
+
+        Ok(report)
+    }
+}
+
+// number of wrong predictions, or None if loss function doesn't report them
+fn count_errors(res: &BatchResult<Option<bool>>) -> Option<usize> {
+    res.outputs.iter().try_fold(0, |errors, correct| {
+        correct.map(|correct| errors + !correct as usize)
+    })
+}
+
+fn validate<S>(
+    net: &Network,
+    dataset: &dyn Dataset<Sample = S>,
+    loss_fn: &BatchLossFn<S>,
+) -> ValidationMetrics {
+    let mut samples = 0;
+    let mut loss = 0.0;
+    let mut errors = Some(0);
+
+    for sample in dataset.samples() {
+        // graph of each sample is dropped right away, since no backward pass is needed
+        let (sample_loss, correct) = loss_fn(net, slice::from_ref(&sample))
+            .pop()
+            .expect("loss function should return loss of each sample");
+
+        samples += 1;
+        loss += sample_loss.borrow().d;
+        errors = errors
+            .zip(correct)
+            .map(|(errors, correct)| errors + !correct as usize);
+    }
+
+    ValidationMetrics {
+        samples,
+        loss: loss / samples.max(1) as f64,
+        errors,
+    }
+}
+
+// takes samples into the buffer and emits them from random positions of it, replacing emitted
+// ones with the next samples
+fn shuffle_samples<'a, T: 'a>(
+    mut samples: Box<dyn Iterator<Item = T> + 'a>,
+    buffer_size: usize,
+    mut rng: ChaCha8Rng,
+) -> Box<dyn Iterator<Item = T> + 'a> {
+    let mut buffer: Vec<T> = samples.by_ref().take(buffer_size).collect();
+
+    Box::new(iter::from_fn(move || {
+        if buffer.is_empty() {
+            return None;
+        }
+
+        let idx = rng.gen_range(0..buffer.len());
+
+        match samples.next() {
+            Some(sample) => Some(mem::replace(&mut buffer[idx], sample)),
+            None => Some(buffer.swap_remove(idx)),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        layer::{activation::Activation, dense::Dense, dropout::Dropout, Layer},
+        optim::{adam::Adam, sgd::Sgd},
+        prune::PruneMethod,
+        scheduler::step_decay::StepDecay,
+        test_utils::{calc_loss, clone_network, create_dataset, get_params},
+    };
+
+    use super::{callbacks::EarlyStopping, *};
+
+    #[derive(Default)]
+    struct Recorder {
+        batches: Vec<(usize, usize)>,
+        epochs: Vec<usize>,
+    }
+
+    impl Callback for Rc<RefCell<Recorder>> {
+        fn on_batch_end(
+            &mut self,
+            _net: &Network,
+            _optimizer: &mut dyn Optimizer,
+            metrics: &BatchMetrics,
+        ) -> Result<Control, NetworkError> {
+            self.borrow_mut()
+                .batches
+                .push((metrics.epoch, metrics.batch));
+            Ok(Control::Continue)
+        }
+
+        fn on_epoch_end(
+            &mut self,
+            _net: &Network,
+            _optimizer: &mut dyn Optimizer,
+            metrics: &EpochMetrics,
+        ) -> Result<Control, NetworkError> {
+            self.borrow_mut().epochs.push(metrics.epoch);
+            Ok(Control::Continue)
+        }
+    }
+
+    #[test]
+    fn fit() {
+        let mut net = Network::new(vec![2, 8, 1]);
+        let dataset = create_dataset(100);
+
+        let mut trainer = Trainer::new(calc_loss, Adam::new(0.05))
+            .epochs(30)
+            .batch_size(10);
+
+        let report = trainer.fit(&mut net, &dataset).unwrap();
+
+        assert_eq!(report.epochs.len(), 30);
+        assert!(!report.stopped);
+        assert!(!net.is_training());
+
+        let first = &report.epochs[0];
+        let last = &report.epochs[29];
+
+        assert_eq!(last.batches, 10);
+        assert_eq!(last.samples, 100);
+        assert!(last.loss < first.loss * 0.5);
+        assert!(last.errors_percent().unwrap() < 10.0);
+    }
+
+    #[test]
+    fn fit_frozen() {
+        for threads in [1, 2] {
+            let mut net = Network::new(vec![2, 8, 1]);
+            net.set_trainable(0, false);
+
+            let frozen: Vec<f64> = net.layers[0]
+                .parameters()
+                .iter()
+                .map(|p| p.borrow().d)
+                .collect();
+            let head = get_params(&net)[frozen.len()..].to_vec();
+
+            let mut trainer = Trainer::new(calc_loss, Adam::new(0.05))
+                .epochs(3)
+                .batch_size(10)
+                .threads(threads)
+                .regularization(Some(Regularization::L2(0.01)));
+
+            trainer.fit(&mut net, &create_dataset(50)).unwrap();
+
+            // only the head is updated
+            let params = get_params(&net);
+            assert_eq!(params[..frozen.len()], frozen);
+            assert_ne!(params[frozen.len()..], head);
+        }
+    }
+
+    #[test]
+    fn fit_pruned() {
+        for threads in [1, 2] {
+            let mut net = Network::new(vec![2, 8, 1]);
+
+            let schedule =
+                PruningSchedule::new(PruneMethod::Unstructured, 0.5, 0, 10).with_frequency(2);
+
+            let mut trainer = Trainer::new(calc_loss, Adam::new(0.05))
+                .epochs(3)
+                .batch_size(10)
+                .threads(threads)
+                .regularization(Some(Regularization::L2(0.01)))
+                .pruning(schedule);
+
+            trainer.fit(&mut net, &create_dataset(50)).unwrap();
+
+            // half of the weights of each dense layer, biases are kept
+            assert_eq!(net.pruned_parameters_count(), 8 + 4);
+
+            // pruned weights aren't updated after pruning
+            for (param, pruned) in net.parameters().iter().zip(net.pruning_mask()) {
+                if *pruned {
+                    assert_eq!(param.borrow().d, 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fit_batch_norm() {
+        let calc_batch_loss = |net: &Network, samples: &[(Vec<f64>, f64)]| {
+            let inputs: Vec<Vec<f64>> = samples.iter().map(|(input, _)| input.clone()).collect();
+
+            net.forward_batch(&inputs)
+                .iter()
+                .zip(samples)
+                .map(|(output, (_, expected))| {
+                    let output = &output[0];
+                    let loss = (*expected - output).pow(2.0);
+                    let correct = (output.borrow().d > 0.0) == (*expected > 0.0);
+                    (loss, Some(correct))
+                })
+                .collect::<Vec<(BVal, Option<bool>)>>()
+        };
+
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(2, 6)),
+            Box::new(BatchNorm1D::new(6)),
+            Box::new(Activation::tanh()),
+            Box::new(Dense::new(6, 1)),
+        ];
+        let base = Network::from_layers(vec![2], layers).unwrap();
+
+        let running_stats = |net: &Network| {
+            let norm = net.layers[1]
+                .as_any()
+                .downcast_ref::<BatchNorm1D>()
+                .unwrap();
+            (
+                norm.running_mean.borrow().clone(),
+                norm.running_var.borrow().clone(),
+            )
+        };
+
+        let initial_stats = running_stats(&base);
+        let mut results = Vec::new();
+
+        for threads in [1, 2, 3] {
+            let mut net = clone_network(&base);
+
+            let mut trainer = Trainer::with_batch_loss(calc_batch_loss, Adam::new(0.05))
+                .epochs(20)
+                .batch_size(10)
+                .shard_size(5)
+                .threads(threads);
+
+            let report = trainer.fit(&mut net, &create_dataset(50)).unwrap();
+
+            assert!(report.epochs[19].loss < report.epochs[0].loss * 0.6);
+            results.push((get_params(&net), running_stats(&net)));
+        }
+
+        // running statistics are collected from batches, same for any threads count
+        assert_ne!(results[0].1, initial_stats);
+
+        for res in &results[1..] {
+            assert_eq!(*res, results[0]);
+        }
+
+        // per-sample loss never uses batch statistics
+        let mut trainer = Trainer::new(calc_loss, Adam::new(0.05));
+
+        assert!(matches!(
+            trainer.fit(&mut clone_network(&base), &create_dataset(10)),
+            Err(NetworkError::UnsupportedLayer(_))
+        ));
+
+        // unless batch norm is frozen
+        let mut net = clone_network(&base);
+        net.set_trainable(1, false);
+
+        trainer.fit(&mut net, &create_dataset(10)).unwrap();
+        assert_eq!(running_stats(&net), initial_stats);
+    }
+
+    #[test]
+    fn fit_seeded() {
+        let dataset = create_dataset(40);
+
+        let run = |seed: u64| -> Vec<f64> {
+            rng::seed(seed);
+
+            let layers: Vec<Box<dyn Layer>> = vec![
+                Box::new(Dense::new(2, 6)),
+                Box::new(Activation::tanh()),
+                Box::new(Dropout::new(0.3)),
+                Box::new(Dense::new(6, 1)),
+            ];
+            let mut net = Network::from_layers(vec![2], layers).unwrap();
+
+            let mut trainer = Trainer::new(calc_loss, Adam::new(0.05))
+                .epochs(2)
+                .batch_size(5)
+                .shuffle(10);
+
+            trainer.fit(&mut net, &dataset).unwrap();
+            get_params(&net)
+        };
+
+        // initialization, dropout masks and samples order are the same for the same seed
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn shuffle() {
+        let samples: Vec<usize> = (0..20).collect();
+
+        let shuffle = |buffer_size: usize, stream: u64| -> Vec<usize> {
+            let mut rng = ChaCha8Rng::seed_from_u64(42);
+            rng.set_stream(stream);
+            shuffle_samples(Box::new(samples.iter().copied()), buffer_size, rng).collect()
+        };
+
+        let shuffled = shuffle(5, 0);
+        assert_ne!(shuffled, samples);
+        assert_eq!(shuffled, shuffle(5, 0));
+        assert_ne!(shuffled, shuffle(5, 1));
+
+        let mut sorted = shuffled.clone();
+        sorted.sort();
+        assert_eq!(sorted, samples);
+
+        // sample can't be emitted before the buffer gets to it
+        assert!(shuffled.iter().enumerate().all(|(idx, n)| *n < idx + 5));
+
+        let mut sorted = shuffle(100, 0);
+        sorted.sort();
+        assert_eq!(sorted, samples);
+    }
+
+    #[test]
+    fn fit_callbacks() {
+        let mut net = Network::new(vec![2, 3, 1]);
+        let dataset = create_dataset(7);
+        let recorder = Rc::new(RefCell::new(Recorder::default()));
+
+        let mut trainer = Trainer::new(calc_loss, Sgd::new(0.01))
+            .epochs(2)
+            .batch_size(3)
+            .callback(recorder.clone());
+
+        trainer.fit(&mut net, &dataset).unwrap();
+
+        // last batch of each epoch is smaller
+        assert_eq!(
+            recorder.borrow().batches,
+            vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(recorder.borrow().epochs, vec![0, 1]);
+    }
+
+    #[test]
+    fn fit_scheduler() {
+        let mut net = Network::new(vec![2, 3, 1]);
+        let dataset = create_dataset(9);
+        let recorder = Rc::new(RefCell::new(Vec::new()));
+
+        struct RatesRecorder(Rc<RefCell<Vec<(usize, f64)>>>);
+
+        impl Callback for RatesRecorder {
+            fn on_batch_end(
+                &mut self,
+                _net: &Network,
+                _optimizer: &mut dyn Optimizer,
+                metrics: &BatchMetrics,
+            ) -> Result<Control, NetworkError> {
+                self.0
+                    .borrow_mut()
+                    .push((metrics.step, metrics.learning_rate));
+                Ok(Control::Continue)
+            }
+        }
+
+        let mut trainer = Trainer::new(calc_loss, Sgd::new(1.0))
+            .epochs(2)
+            .batch_size(3)
+            .scheduler(StepDecay::new(0.1, 0.5, 2))
+            .callback(RatesRecorder(recorder.clone()));
+
+        trainer.fit(&mut net, &dataset).unwrap();
+
+        // schedule doesn't restart with epochs
+        assert_eq!(
+            *recorder.borrow(),
+            vec![
+                (0, 0.1),
+                (1, 0.1),
+                (2, 0.05),
+                (3, 0.05),
+                (4, 0.025),
+                (5, 0.025)
+            ]
+        );
+        assert_eq!(trainer.global_step(), 6);
+
+        // next fit continues from the global step
+        recorder.borrow_mut().clear();
+        trainer.fit(&mut net, &dataset).unwrap();
+
+        assert_eq!(recorder.borrow()[0], (6, 0.0125));
+    }
+
+    #[test]
+    fn fit_validation() {
+        let mut net = Network::new(vec![2, 3, 1]);
+        let dataset = create_dataset(20);
+        let validation = create_dataset(5);
+
+        let expected_loss = validation
+            .iter()
+            .map(|sample| calc_loss(&net, sample).0.borrow().d)
+            .sum::<f64>()
+            / 5.0;
+
+        // no updates, so validation loss is one of initial network
+        let mut trainer = Trainer::new(calc_loss, Sgd::new(0.0))
+            .batch_size(4)
+            .validation(validation);
+
+        let report = trainer.fit(&mut net, &dataset).unwrap();
+        let metrics = report.epochs[0].validation.as_ref().unwrap();
+
+        assert_eq!(metrics.samples, 5);
+        assert!((metrics.loss - expected_loss).abs() < 1e-12);
+        assert_eq!(report.epochs[0].monitored_loss(), metrics.loss);
+    }
+
+    #[test]
+    fn fit_early_stopping() {
+        let mut net = Network::new(vec![2, 3, 1]);
+        let dataset = create_dataset(10);
+
+        // loss doesn't change without updates
+        let mut trainer = Trainer::new(calc_loss, Sgd::new(0.0))
+            .epochs(10)
+            .batch_size(5)
+            .callback(EarlyStopping::new(2, 0.0));
+
+        let report = trainer.fit(&mut net, &dataset).unwrap();
+
+        assert_eq!(report.epochs.len(), 3);
+        assert!(report.stopped);
+    }
+
+    #[test]
+    fn fit_regularization() {
+        let dataset = create_dataset(20);
+
+        let mut results = Vec::new();
+
+        for regularization in [None, Some(Regularization::L2(0.1))] {
+            let mut net = Network::new(vec![2, 3, 1]);
+            for param in net.parameters() {
+                param.borrow_mut().d = 0.5;
+            }
+
+            let mut trainer = Trainer::new(calc_loss, Sgd::new(0.01))
+                .batch_size(20)
+                .regularization(regularization);
+
+            trainer.fit(&mut net, &dataset).unwrap();
+
+            let norm: f64 = get_params(&net).iter().map(|p| p * p).sum();
+            results.push(norm);
+        }
+
+        assert!(results[1] < results[0]);
+    }
+
+    #[test]
+    fn fit_deterministic() {
+        let dataset = create_dataset(25);
+
+        // dropout masks don't depend on threads count either
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(2, 4)),
+            Box::new(Activation::tanh()),
+            Box::new(Dropout::with_seed(0.3, 5)),
+            Box::new(Dense::new(4, 1)),
+        ];
+        let base = Network::from_layers(vec![2], layers).unwrap();
+
+        let mut results = Vec::new();
+
+        for threads in [1, 2, 3] {
+            let mut net = clone_network(&base);
+
+            let mut trainer = Trainer::new(calc_loss, Sgd::with_momentum(0.01, 0.9))
+                .epochs(3)
+                .batch_size(7)
+                .threads(threads);
+
+            let report = trainer.fit(&mut net, &dataset).unwrap();
+
+            results.push((report, get_params(&net)));
+        }
+
+        // bit-exact regardless of threads count
+        for res in &results[1..] {
+            assert_eq!(res.1, results[0].1);
+            for (epoch, expected) in res.0.epochs.iter().zip(&results[0].0.epochs) {
+                assert_eq!(epoch.loss.to_bits(), expected.loss.to_bits());
+            }
+        }
+    }
+}
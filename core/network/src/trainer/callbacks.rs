@@ -0,0 +1,260 @@
+use crate::{error::NetworkError, network::Network, optim::Optimizer};
+
+use super::{BatchMetrics, Callback, Control, EpochMetrics};
+
+// stops training when monitored loss (validation one if available) hasn't improved by at least
+// min_delta for patience epochs in a row
+pub struct EarlyStopping {
+    patience: usize,
+    min_delta: f64,
+    best_loss: f64,
+    bad_epochs: usize,
+}
+
+impl EarlyStopping {
+    pub fn new(patience: usize, min_delta: f64) -> Self {
+        EarlyStopping {
+            patience,
+            min_delta,
+            best_loss: f64::INFINITY,
+            bad_epochs: 0,
+        }
+    }
+
+    pub fn best_loss(&self) -> f64 {
+        self.best_loss
+    }
+}
+
+impl Callback for EarlyStopping {
+    fn on_epoch_end(
+        &mut self,
+        _net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        metrics: &EpochMetrics,
+    ) -> Result<Control, NetworkError> {
+        let loss = metrics.monitored_loss();
+
+        if loss < self.best_loss - self.min_delta {
+            self.best_loss = loss;
+            self.bad_epochs = 0;
+            return Ok(Control::Continue);
+        }
+
+        self.bad_epochs += 1;
+
+        if self.bad_epochs >= self.patience {
+            println!(
+                "early stopping: loss hasn't improved for {} epochs, best loss = {:.4}",
+                self.bad_epochs, self.best_loss
+            );
+            return Ok(Control::Stop);
+        }
+
+        Ok(Control::Continue)
+    }
+}
+
+// serializes network to models dir each nth batch (if set) and at the end of each epoch
+pub struct Checkpoint {
+    dir: String,
+    file_name_prefix: String,
+    each_nth_batch: Option<usize>,
+}
+
+impl Checkpoint {
+    pub fn new(dir: &str, file_name_prefix: &str, each_nth_batch: Option<usize>) -> Self {
+        Checkpoint {
+            dir: String::from(dir),
+            file_name_prefix: String::from(file_name_prefix),
+            each_nth_batch,
+        }
+    }
+}
+
+impl Callback for Checkpoint {
+    fn on_batch_end(
+        &mut self,
+        net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        metrics: &BatchMetrics,
+    ) -> Result<Control, NetworkError> {
+        if let Some(each_nth_batch) = self.each_nth_batch {
+            if metrics.batch > 0 && metrics.batch.checked_rem(each_nth_batch) == Some(0) {
+                net.serialize_to_file(&self.dir, &self.file_name_prefix)?;
+            }
+        }
+
+        Ok(Control::Continue)
+    }
+
+    fn on_epoch_end(
+        &mut self,
+        net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        _metrics: &EpochMetrics,
+    ) -> Result<Control, NetworkError> {
+        net.serialize_to_file(&self.dir, &self.file_name_prefix)?;
+        Ok(Control::Continue)
+    }
+}
+
+// prints metrics of each nth batch and of each epoch
+pub struct MetricsLogger {
+    each_nth_batch: usize,
+}
+
+impl MetricsLogger {
+    pub fn new(each_nth_batch: usize) -> Self {
+        assert!(each_nth_batch > 0, "logging interval should be positive");
+        MetricsLogger { each_nth_batch }
+    }
+}
+
+impl Default for MetricsLogger {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Callback for MetricsLogger {
+    fn on_batch_end(
+        &mut self,
+        _net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        metrics: &BatchMetrics,
+    ) -> Result<Control, NetworkError> {
+        if metrics.batch.checked_rem(self.each_nth_batch) == Some(0) {
+            println!(
+                "epoch = {epoch}, \
+                batch = {batch}, \
+                duration = {duration}ms, \
+                rate = {learning_rate:.4}, \
+                loss = {loss:.4}{errors}",
+                epoch = metrics.epoch,
+                batch = metrics.batch,
+                duration = metrics.duration_ms,
+                learning_rate = metrics.learning_rate,
+                loss = metrics.loss,
+                errors = format_errors(metrics.errors_percent())
+            );
+        }
+
+        Ok(Control::Continue)
+    }
+
+    fn on_epoch_end(
+        &mut self,
+        _net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        metrics: &EpochMetrics,
+    ) -> Result<Control, NetworkError> {
+        let validation = match &metrics.validation {
+            Some(validation) => format!(
+                ", validation loss = {:.4}{}",
+                validation.loss,
+                format_errors(
+                    validation
+                        .errors
+                        .map(|errors| errors as f64 / validation.samples.max(1) as f64 * 100.0)
+                )
+            ),
+            None => String::new(),
+        };
+
+        println!(
+            "epoch = {epoch} done, \
+            batches = {batches}, \
+            duration = {duration}ms, \
+            loss = {loss:.4}{errors}{validation}",
+            epoch = metrics.epoch,
+            batches = metrics.batches,
+            duration = metrics.duration_ms,
+            loss = metrics.loss,
+            errors = format_errors(metrics.errors_percent())
+        );
+
+        Ok(Control::Continue)
+    }
+}
+
+fn format_errors(errors_percent: Option<f64>) -> String {
+    errors_percent
+        .map(|errors_percent| format!(", errors = {errors_percent}%"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::optim::sgd::Sgd;
+
+    use super::*;
+
+    fn create_epoch_metrics(epoch: usize, loss: f64) -> EpochMetrics {
+        EpochMetrics {
+            epoch,
+            batches: 1,
+            samples: 1,
+            loss,
+            errors: None,
+            validation: None,
+            duration_ms: 0,
+        }
+    }
+
+    #[test]
+    fn early_stopping() {
+        let net = Network::new(vec![1, 1]);
+        let mut sgd = Sgd::new(0.1);
+        let mut early_stopping = EarlyStopping::new(2, 0.1);
+
+        let controls: Vec<Control> = [1.0, 0.5, 0.45, 0.3, 0.35, 0.3]
+            .iter()
+            .enumerate()
+            .map(|(epoch, loss)| {
+                early_stopping
+                    .on_epoch_end(&net, &mut sgd, &create_epoch_metrics(epoch, *loss))
+                    .unwrap()
+            })
+            .collect();
+
+        // 0.45 isn't enough of improvement, 0.3 resets patience, then two bad epochs in a row
+        assert_eq!(
+            controls,
+            vec![
+                Control::Continue,
+                Control::Continue,
+                Control::Continue,
+                Control::Continue,
+                Control::Continue,
+                Control::Stop
+            ]
+        );
+        assert_eq!(early_stopping.best_loss(), 0.3);
+    }
+
+    #[test]
+    fn checkpoint() {
+        let dir = env::temp_dir().join("network_checkpoint_test");
+        fs::create_dir_all(&dir).unwrap();
+        let dir = dir.to_str().unwrap();
+
+        let net = Network::new(vec![2, 3, 1]);
+        let mut sgd = Sgd::new(0.1);
+        let mut checkpoint = Checkpoint::new(dir, "test", None);
+
+        checkpoint
+            .on_epoch_end(&net, &mut sgd, &create_epoch_metrics(0, 1.0))
+            .unwrap();
+
+        let restored = Network::new_or_deserialize_from_file(vec![2, 3, 1], dir, "test");
+
+        for (param1, param2) in net.parameters().iter().zip(restored.parameters()) {
+            assert_eq!(param1.borrow().d, param2.borrow().d);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{error::NetworkError, network::Network, optim, scheduler, utils};
+
+use super::{Progress, Shuffle, Trainer};
+
+// training checkpoint has its own magic bytes, since it's not a model file
+const MAGIC: [u8; 4] = *b"NNTC";
+const FORMAT_VERSION: u32 = 1;
+
+impl<S> Trainer<S>
+where
+    S: Clone + Send + 'static,
+{
+    // saves everything needed to continue training exactly as if it wasn't interrupted: network,
+    // optimizer and scheduler state, trainable flags and random generators of layers and position
+    // in the dataset.
+    // callbacks keep their own state, so e.g. early stopping starts counting anew after resuming
+    pub fn save_checkpoint(
+        &self,
+        net: &Network,
+        mut writer: impl Write,
+    ) -> Result<(), NetworkError> {
+        writer.write_all(&MAGIC)?;
+        utils::write_u32(&mut writer, FORMAT_VERSION)?;
+
+        // network goes with its size, since model reader expects model data to end with it
+        let mut net_bytes = Vec::new();
+        net.serialize_to_writer(&mut net_bytes)?;
+
+        utils::write_u64(&mut writer, net_bytes.len() as u64)?;
+        writer.write_all(&net_bytes)?;
+
+        // trainable flags and random generators of layers, e.g. dropout
+        utils::write_u32(&mut writer, net.layers.len() as u32)?;
+
+        for (idx, layer) in net.layers.iter().enumerate() {
+            utils::write_u32(&mut writer, net.is_trainable(idx) as u32)?;
+
+            match layer.rng() {
+                Some(rng) => {
+                    utils::write_u32(&mut writer, 1)?;
+                    write_rng(&mut writer, &rng.borrow())?;
+                }
+                None => utils::write_u32(&mut writer, 0)?,
+            }
+        }
+
+        optim::serialize_optimizer(self.optimizer.as_ref(), &mut writer)?;
+
+        match &self.scheduler {
+            Some(scheduler) => {
+                utils::write_u32(&mut writer, 1)?;
+                scheduler::serialize_scheduler(scheduler.as_ref(), &mut writer)?;
+            }
+            None => utils::write_u32(&mut writer, 0)?,
+        }
+
+        // position in the dataset, batch size and shuffle buffer size are kept to check that
+        // position means the same. zero buffer size means samples aren't shuffled
+        utils::write_u64(&mut writer, self.step as u64)?;
+        utils::write_u64(&mut writer, self.batch_size as u64)?;
+
+        let shuffle = self.shuffle.unwrap_or(Shuffle {
+            buffer_size: 0,
+            seed: 0,
+        });
+        utils::write_u64(&mut writer, shuffle.buffer_size as u64)?;
+        utils::write_u64(&mut writer, shuffle.seed)?;
+        utils::write_u64(&mut writer, self.progress.epoch as u64)?;
+        utils::write_u64(&mut writer, self.progress.batch as u64)?;
+        utils::write_u64(&mut writer, self.progress.samples as u64)?;
+        utils::write_f64(&mut writer, self.progress.loss)?;
+
+        match self.progress.errors {
+            Some(errors) => {
+                utils::write_u32(&mut writer, 1)?;
+                utils::write_u64(&mut writer, errors as u64)?;
+            }
+            None => utils::write_u32(&mut writer, 0)?,
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    // writes checkpoint to temporary file first, so interruption while saving doesn't corrupt
+    // the previous checkpoint
+    pub fn save_checkpoint_to_file(&self, net: &Network, path: &str) -> Result<(), NetworkError> {
+        let tmp_path = format!("{path}.tmp");
+
+        let file = File::create(&tmp_path)?;
+        self.save_checkpoint(net, BufWriter::new(file))?;
+
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    // restores state saved by save_checkpoint(), replacing optimizer and scheduler of the
+    // trainer. returns the network, which fit() continues to train from the saved position
+    pub fn resume_from_checkpoint(
+        &mut self,
+        mut reader: impl Read,
+    ) -> Result<Network, NetworkError> {
+        let magic = utils::read_magic(&mut reader)?;
+
+        if magic != MAGIC {
+            return Err(NetworkError::BadMagic(magic));
+        }
+
+        let version = utils::read_u32(&mut reader)?;
+
+        if version != FORMAT_VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+
+        let net_len = utils::read_u64(&mut reader)?;
+
+        // buffer only grows as data is read, so corrupted size fails on missing data
+        let mut net_bytes = Vec::new();
+        (&mut reader).take(net_len).read_to_end(&mut net_bytes)?;
+
+        if net_bytes.len() as u64 != net_len {
+            return Err(NetworkError::UnexpectedEof);
+        }
+
+        let mut net = Network::deserialize_from_reader(net_bytes.as_slice())?;
+
+        let layers_count = utils::read_u32(&mut reader)? as usize;
+
+        if layers_count != net.layers.len() {
+            return Err(NetworkError::InvalidCheckpoint(format!(
+                "state of {layers_count} layers for network of {} layers",
+                net.layers.len()
+            )));
+        }
+
+        for idx in 0..layers_count {
+            match utils::read_u32(&mut reader)? {
+                0 => net.set_trainable(idx, false),
+                1 => {}
+                flag => {
+                    return Err(NetworkError::InvalidCheckpoint(format!(
+                        "invalid trainable flag: {flag}"
+                    )))
+                }
+            }
+
+            let layer = &net.layers[idx];
+            let has_rng = utils::read_u32(&mut reader)? == 1;
+
+            match (layer.rng(), has_rng) {
+                (Some(rng), true) => *rng.borrow_mut() = read_rng(&mut reader)?,
+                (None, false) => {}
+                _ => {
+                    return Err(NetworkError::InvalidCheckpoint(format!(
+                        "random generator mismatch of layer {}",
+                        layer.tag()
+                    )))
+                }
+            }
+        }
+
+        let optimizer = optim::deserialize_optimizer(&mut reader)?;
+
+        let scheduler = match utils::read_u32(&mut reader)? {
+            0 => None,
+            1 => Some(scheduler::deserialize_scheduler(&mut reader)?),
+            flag => {
+                return Err(NetworkError::InvalidCheckpoint(format!(
+                    "invalid scheduler flag: {flag}"
+                )))
+            }
+        };
+
+        let step = utils::read_u64(&mut reader)? as usize;
+        let batch_size = utils::read_u64(&mut reader)? as usize;
+
+        if batch_size != self.batch_size {
+            return Err(NetworkError::InvalidCheckpoint(format!(
+                "checkpoint batch size {batch_size} differs from trainer one {}",
+                self.batch_size
+            )));
+        }
+
+        let buffer_size = utils::read_u64(&mut reader)? as usize;
+        let seed = utils::read_u64(&mut reader)?;
+        let trainer_buffer_size = self.shuffle.map_or(0, |shuffle| shuffle.buffer_size);
+
+        if buffer_size != trainer_buffer_size {
+            return Err(NetworkError::InvalidCheckpoint(format!(
+                "checkpoint shuffle buffer size {buffer_size} differs from trainer one \
+                 {trainer_buffer_size}"
+            )));
+        }
+
+        let mut progress = Progress::new(utils::read_u64(&mut reader)? as usize);
+        progress.batch = utils::read_u64(&mut reader)? as usize;
+        progress.samples = utils::read_u64(&mut reader)? as usize;
+        progress.loss = utils::read_f64(&mut reader)?;
+        progress.errors = match utils::read_u32(&mut reader)? {
+            0 => None,
+            _ => Some(utils::read_u64(&mut reader)? as usize),
+        };
+
+        utils::ensure_eof(&mut reader)?;
+
+        self.optimizer = optimizer;
+        self.scheduler = scheduler;
+        self.step = step;
+        self.progress = progress;
+
+        // samples order continues the one of interrupted training
+        if let Some(shuffle) = &mut self.shuffle {
+            shuffle.seed = seed;
+        }
+
+        Ok(net)
+    }
+
+    pub fn resume_from_checkpoint_file(&mut self, path: &str) -> Result<Network, NetworkError> {
+        let file = File::open(path)?;
+        self.resume_from_checkpoint(BufReader::new(file))
+    }
+}
+
+fn write_rng(writer: &mut dyn Write, rng: &ChaCha8Rng) -> Result<(), NetworkError> {
+    writer.write_all(&rng.get_seed())?;
+    utils::write_u64(writer, rng.get_stream())?;
+    writer.write_all(&rng.get_word_pos().to_ne_bytes())?;
+    Ok(())
+}
+
+fn read_rng(reader: &mut dyn Read) -> Result<ChaCha8Rng, NetworkError> {
+    let mut seed = [0; 32];
+    reader.read_exact(&mut seed)?;
+
+    let stream = utils::read_u64(reader)?;
+
+    let mut word_pos = [0; 16];
+    reader.read_exact(&mut word_pos)?;
+
+    let mut rng = ChaCha8Rng::from_seed(seed);
+    rng.set_stream(stream);
+    rng.set_word_pos(u128::from_ne_bytes(word_pos));
+
+    Ok(rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::{
+        layer::{activation::Activation, dense::Dense, dropout::Dropout, Layer},
+        optim::{adam::Adam, sgd::Sgd},
+        rng,
+        scheduler::{linear_decay::LinearDecay, reduce_on_plateau::ReduceOnPlateau},
+        test_utils::{calc_loss, clone_network, create_dataset, get_params},
+        trainer::{BatchMetrics, Callback, Control, EpochMetrics},
+    };
+
+    use super::*;
+
+    fn create_network() -> Network {
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(2, 6)),
+            Box::new(Activation::tanh()),
+            Box::new(Dropout::with_seed(0.3, 7)),
+            Box::new(Dense::new(6, 1)),
+            Box::new(Activation::tanh()),
+        ];
+
+        Network::from_layers(vec![2], layers).unwrap()
+    }
+
+    fn create_trainer(optimizer: impl optim::Optimizer + 'static) -> Trainer<(Vec<f64>, f64)> {
+        Trainer::new(calc_loss, optimizer)
+            .epochs(4)
+            .batch_size(6)
+            .scheduler(LinearDecay::new(0.05, 0.01, 40))
+            .shuffle(16)
+    }
+
+    // stops training after given number of batches
+    struct Interrupt {
+        batches: usize,
+    }
+
+    impl Callback for Interrupt {
+        fn on_batch_end(
+            &mut self,
+            _net: &Network,
+            _optimizer: &mut dyn optim::Optimizer,
+            _metrics: &BatchMetrics,
+        ) -> Result<Control, NetworkError> {
+            self.batches -= 1;
+
+            if self.batches == 0 {
+                Ok(Control::Stop)
+            } else {
+                Ok(Control::Continue)
+            }
+        }
+    }
+
+    fn assert_same_epochs(actual: &[EpochMetrics], expected: &[EpochMetrics]) {
+        assert_eq!(actual.len(), expected.len());
+
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert_eq!(actual.epoch, expected.epoch);
+            assert_eq!(actual.samples, expected.samples);
+            assert_eq!(actual.errors, expected.errors);
+            assert_eq!(actual.loss.to_bits(), expected.loss.to_bits());
+        }
+    }
+
+    // interrupts training in the middle of the second epoch, with optimizer state, lr schedule
+    // and dropout masks in progress, and resumes it with fresh trainer
+    fn check_resume<O: optim::Optimizer + 'static>(create_optimizer: impl Fn() -> O) {
+        let dataset = create_dataset(40);
+        let initial = create_network();
+
+        // both runs shuffle samples in the same order
+        rng::seed(11);
+        let mut net = clone_network(&initial);
+        let mut trainer = create_trainer(create_optimizer());
+        let full_report = trainer.fit(&mut net, &dataset).unwrap();
+        let expected = get_params(&net);
+
+        rng::seed(11);
+        let mut net = clone_network(&initial);
+        let mut trainer = create_trainer(create_optimizer()).callback(Interrupt { batches: 10 });
+        let report = trainer.fit(&mut net, &dataset).unwrap();
+
+        assert!(report.stopped);
+        assert_eq!(trainer.epoch(), 1);
+
+        let mut bytes = Vec::new();
+        trainer.save_checkpoint(&net, &mut bytes).unwrap();
+
+        // optimizer and scheduler of the trainer are replaced by saved ones
+        let mut trainer =
+            create_trainer(Sgd::new(1.0)).scheduler(ReduceOnPlateau::new(1.0, 0.5, 1, 0.0));
+        let mut net = trainer.resume_from_checkpoint(bytes.as_slice()).unwrap();
+
+        assert_eq!(trainer.global_step(), 10);
+        assert_eq!(trainer.optimizer().tag(), create_optimizer().tag());
+
+        let report = trainer.fit(&mut net, &dataset).unwrap();
+
+        assert_eq!(get_params(&net), expected);
+        assert_same_epochs(&report.epochs, &full_report.epochs[1..]);
+    }
+
+    #[test]
+    fn resume() {
+        check_resume(|| Adam::new(0.05));
+        check_resume(|| Sgd::with_momentum(0.05, 0.9));
+    }
+
+    #[test]
+    fn resume_from_file() {
+        let dataset = create_dataset(30);
+        let path = env::temp_dir().join("network_trainer_checkpoint_test.nc");
+        let path = path.to_str().unwrap();
+
+        let mut net = create_network();
+        let mut trainer = create_trainer(Adam::new(0.05))
+            .checkpoint(path, Some(2))
+            .callback(Interrupt { batches: 3 });
+        trainer.fit(&mut net, &dataset).unwrap();
+
+        // checkpoint of the second batch, which is the last saved one
+        let mut trainer = create_trainer(Adam::new(0.05));
+        trainer.resume_from_checkpoint_file(path).unwrap();
+
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(trainer.global_step(), 2);
+        assert_eq!(trainer.epoch(), 0);
+    }
+
+    #[test]
+    fn resume_errors() {
+        let net = create_network();
+        let trainer = create_trainer(Adam::new(0.05));
+
+        let mut bytes = Vec::new();
+        trainer.save_checkpoint(&net, &mut bytes).unwrap();
+
+        let mut trainer = create_trainer(Adam::new(0.05)).batch_size(3);
+        assert!(matches!(
+            trainer.resume_from_checkpoint(bytes.as_slice()),
+            Err(NetworkError::InvalidCheckpoint(_))
+        ));
+
+        let mut trainer = create_trainer(Adam::new(0.05)).shuffle(8);
+        assert!(matches!(
+            trainer.resume_from_checkpoint(bytes.as_slice()),
+            Err(NetworkError::InvalidCheckpoint(_))
+        ));
+
+        let mut trainer = create_trainer(Adam::new(0.05));
+        assert!(matches!(
+            trainer.resume_from_checkpoint(&bytes[..bytes.len() - 1]),
+            Err(NetworkError::UnexpectedEof)
+        ));
+
+        let mut net_bytes = Vec::new();
+        net.serialize_to_writer(&mut net_bytes).unwrap();
+        assert!(matches!(
+            trainer.resume_from_checkpoint(net_bytes.as_slice()),
+            Err(NetworkError::BadMagic(_))
+        ));
+    }
+}
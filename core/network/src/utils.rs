@@ -3,34 +3,185 @@ use std::{
     path::Path,
 };
 
+use network_format::zero_runs;
 use rand_distr::{Distribution, Normal};
 
-// generates random number with normal distribution
+use crate::{error::NetworkError, rng};
+
+// generates random number with normal distribution, see rng::seed()
 pub fn gen_rand_normal(deviation: f64) -> f64 {
     let normal = Normal::new(0.0, deviation).unwrap();
-    normal.sample(&mut rand::thread_rng())
+    rng::with_rng(|rng| normal.sample(rng))
 }
 
-pub fn write_u32<T: Write>(writer: &mut T, n: u32) {
-    let wrote_bytes = writer.write(&n.to_ne_bytes()).expect("failed to write");
-    assert_eq!(wrote_bytes, 4);
+pub fn write_u32<T: Write + ?Sized>(writer: &mut T, n: u32) -> Result<(), NetworkError> {
+    writer.write_all(&n.to_ne_bytes())?;
+    Ok(())
 }
 
-pub fn read_u32<T: Read>(reader: &mut T) -> u32 {
+pub fn read_u32<T: Read + ?Sized>(reader: &mut T) -> Result<u32, NetworkError> {
     let mut buf: [u8; 4] = [0; 4];
-    reader.read_exact(&mut buf).expect("failed to read");
-    u32::from_ne_bytes(buf)
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_ne_bytes(buf))
 }
 
-pub fn write_f64<T: Write>(writer: &mut T, n: f64) {
-    let wrote_bytes = writer.write(&n.to_ne_bytes()).expect("failed to write");
-    assert_eq!(wrote_bytes, 8);
+pub fn write_u64<T: Write + ?Sized>(writer: &mut T, n: u64) -> Result<(), NetworkError> {
+    writer.write_all(&n.to_ne_bytes())?;
+    Ok(())
 }
 
-pub fn read_f64<T: Read>(reader: &mut T) -> f64 {
+pub fn read_u64<T: Read + ?Sized>(reader: &mut T) -> Result<u64, NetworkError> {
     let mut buf: [u8; 8] = [0; 8];
-    reader.read_exact(&mut buf).expect("failed to read");
-    f64::from_ne_bytes(buf)
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_ne_bytes(buf))
+}
+
+pub fn write_f64<T: Write + ?Sized>(writer: &mut T, n: f64) -> Result<(), NetworkError> {
+    writer.write_all(&n.to_ne_bytes())?;
+    Ok(())
+}
+
+pub fn read_f64<T: Read + ?Sized>(reader: &mut T) -> Result<f64, NetworkError> {
+    let mut buf: [u8; 8] = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(f64::from_ne_bytes(buf))
+}
+
+// params are read before network or layer is initialized, and vector only grows as values are
+// read, so corrupted sizes fail on missing data instead of allocating huge layer first
+pub fn read_params<T: Read + ?Sized>(
+    reader: &mut T,
+    count: usize,
+) -> Result<Vec<f64>, NetworkError> {
+    let mut params = Vec::new();
+    for _ in 0..count {
+        params.push(read_f64(reader)?);
+    }
+
+    Ok(params)
+}
+
+pub fn write_f32<T: Write + ?Sized>(writer: &mut T, n: f32) -> Result<(), NetworkError> {
+    writer.write_all(&n.to_ne_bytes())?;
+    Ok(())
+}
+
+pub fn read_f32<T: Read + ?Sized>(reader: &mut T) -> Result<f32, NetworkError> {
+    let mut buf: [u8; 4] = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(f32::from_ne_bytes(buf))
+}
+
+pub fn read_magic<T: Read + ?Sized>(reader: &mut T) -> Result<[u8; 4], NetworkError> {
+    let mut buf: [u8; 4] = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+pub fn write_string<T: Write + ?Sized>(writer: &mut T, s: &str) -> Result<(), NetworkError> {
+    write_u32(writer, s.len() as u32)?;
+    writer.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+// reads string written by write_string(). strings in model file are short identifiers, so
+// anything longer than max length is treated as corrupted data
+pub fn read_string<T: Read + ?Sized>(
+    reader: &mut T,
+    max_len: usize,
+) -> Result<Option<String>, NetworkError> {
+    let len = read_u32(reader)? as usize;
+
+    if len > max_len {
+        return Ok(None);
+    }
+
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+
+    Ok(String::from_utf8(buf).ok())
+}
+
+pub fn ensure_eof<T: Read + ?Sized>(reader: &mut T) -> Result<(), NetworkError> {
+    let mut buf: [u8; 1] = [0; 1];
+
+    if reader.read(&mut buf)? == 0 {
+        Ok(())
+    } else {
+        Err(NetworkError::TrailingBytes)
+    }
+}
+
+// zero runs shorter than this are kept in literals, since each run costs two length prefixes
+const MIN_ZERO_RUN: usize = 8;
+
+// writes bytes as chunks of literal bytes followed by number of zero bytes, which shrinks data
+// with lots of zeros (e.g. pruned parameters). chunks end with empty one
+pub fn write_zero_runs<T: Write + ?Sized>(
+    writer: &mut T,
+    bytes: &[u8],
+) -> Result<(), NetworkError> {
+    let mut literal_start = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        let zeros = bytes[idx..].iter().take_while(|b| **b == 0).count();
+
+        if zeros >= MIN_ZERO_RUN || (zeros > 0 && idx + zeros == bytes.len()) {
+            write_zero_run(writer, &bytes[literal_start..idx], zeros)?;
+            literal_start = idx + zeros;
+        }
+
+        idx += zeros.max(1);
+    }
+
+    if literal_start < bytes.len() {
+        write_zero_run(writer, &bytes[literal_start..], 0)?;
+    }
+
+    write_zero_run(writer, &[], 0)
+}
+
+fn write_zero_run<T: Write + ?Sized>(
+    writer: &mut T,
+    literal: &[u8],
+    zeros: usize,
+) -> Result<(), NetworkError> {
+    write_u32(writer, literal.len() as u32)?;
+    writer.write_all(literal)?;
+    write_u32(writer, zeros as u32)
+}
+
+// reads bytes written by write_zero_runs()
+pub fn read_zero_runs<T: Read + ?Sized>(reader: &mut T) -> Result<Vec<u8>, NetworkError> {
+    zero_runs::decode(&mut ZeroRunsSource(reader))
+}
+
+struct ZeroRunsSource<'a, T: Read + ?Sized>(&'a mut T);
+
+impl<T: Read + ?Sized> zero_runs::Source for ZeroRunsSource<'_, T> {
+    type Error = NetworkError;
+
+    fn read_u32(&mut self) -> Result<u32, NetworkError> {
+        read_u32(self.0)
+    }
+
+    fn read_literal(&mut self, len: usize, buf: &mut Vec<u8>) -> Result<(), NetworkError> {
+        let read = self.0.take(len as u64).read_to_end(buf)?;
+
+        if read < len {
+            Err(NetworkError::UnexpectedEof)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn too_long_error() -> NetworkError {
+        NetworkError::InvalidLayer(format!(
+            "zero runs exceed {} bytes",
+            zero_runs::MAX_DECODED_LEN
+        ))
+    }
 }
 
 fn get_model_file_name(prefix: &str, layers_sizes: &[usize]) -> String {
@@ -67,4 +218,58 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn zero_runs() {
+        let mut long_run = vec![1, 2];
+        long_run.extend([0; 100]);
+        long_run.extend([3, 0, 0, 4]);
+
+        let cases: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![0],
+            vec![0; 20],
+            vec![1, 2, 3],
+            vec![1, 0, 0, 2, 0],
+            long_run.clone(),
+        ];
+
+        for bytes in cases {
+            let mut encoded = Vec::new();
+            write_zero_runs(&mut encoded, &bytes).unwrap();
+
+            let mut reader = encoded.as_slice();
+            assert_eq!(read_zero_runs(&mut reader).unwrap(), bytes);
+            assert!(reader.is_empty());
+        }
+
+        // long run of zeros is stored as its length
+        let mut encoded = Vec::new();
+        write_zero_runs(&mut encoded, &long_run).unwrap();
+        assert_eq!(encoded.len(), 4 + 2 + 4 + 4 + 4 + 4 + 4 + 4);
+
+        // missing terminating chunk
+        let encoded = &encoded[..encoded.len() - 8];
+        assert!(matches!(
+            read_zero_runs(&mut &encoded[..]),
+            Err(NetworkError::UnexpectedEof)
+        ));
+
+        // oversized runs are rejected before allocation
+        for (literal_len, zeros) in [
+            (0, u32::MAX),
+            (u32::MAX, 0),
+            (2, zero_runs::MAX_DECODED_LEN as u32),
+        ] {
+            let mut encoded = Vec::new();
+            write_u32(&mut encoded, literal_len).unwrap();
+            encoded.extend([1, 2]);
+            write_u32(&mut encoded, zeros).unwrap();
+
+            assert!(matches!(
+                read_zero_runs(&mut encoded.as_slice()),
+                Err(NetworkError::InvalidLayer(_))
+            ));
+        }
+    }
 }
@@ -0,0 +1,73 @@
+// layout of model files, which are written by "network" crate and read by both "network" and
+// "network_infer" crates. kept without std, so inference crate can use it on embedded targets
+#![no_std]
+
+extern crate alloc;
+
+pub mod zero_runs;
+
+// model file starts with magic bytes and format version, so foreign or corrupted data can be
+// rejected before reading network structure
+pub const MAGIC: [u8; 4] = *b"NNMF";
+pub const FORMAT_VERSION: u32 = 2;
+
+// version 1 only stored sizes of dense layers, each followed by tanh activation
+pub const DENSE_FORMAT_VERSION: u32 = 1;
+
+// pruned networks are stored as version 2 data with zero runs encoded as their lengths (see
+// zero_runs module), followed by bitmap of pruned parameters
+pub const SPARSE_FORMAT_VERSION: u32 = 3;
+
+// legacy files (without header) start with layers count, which is never big in practice
+pub const MAX_LEGACY_LAYERS_COUNT: u32 = 1024;
+
+// layer tags are short identifiers, so anything longer can only come from corrupted data
+pub const MAX_TAG_LEN: usize = 64;
+
+// shapes have few dimensions in practice, so more can only come from corrupted data
+pub const MAX_DIMS: usize = 16;
+
+// each layer in model file starts with its tag
+pub mod tags {
+    pub const DENSE: &str = "dense";
+    pub const ACTIVATION: &str = "activation";
+    pub const DROPOUT: &str = "dropout";
+    pub const FLATTEN: &str = "flatten";
+    pub const RESHAPE: &str = "reshape";
+    pub const CONV2D: &str = "conv2d";
+    pub const MAX_POOL2D: &str = "max_pool2d";
+    pub const AVG_POOL2D: &str = "avg_pool2d";
+    pub const BATCH_NORM1D: &str = "batch_norm1d";
+    pub const RNN: &str = "rnn";
+    pub const GRU: &str = "gru";
+    pub const LSTM: &str = "lstm";
+}
+
+// number of values of the shape, or None if shape can't describe layer outputs: it has no
+// dimensions or too many of them, some of them are zero, or the number overflows
+pub fn get_shape_size(shape: &[usize]) -> Option<usize> {
+    if shape.is_empty() || shape.len() > MAX_DIMS {
+        return None;
+    }
+
+    shape
+        .iter()
+        .try_fold(1usize, |size, dim| size.checked_mul(*dim))
+        .filter(|size| *size > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shape_size() {
+        assert_eq!(get_shape_size(&[3]), Some(3));
+        assert_eq!(get_shape_size(&[2, 3, 4]), Some(24));
+
+        assert_eq!(get_shape_size(&[]), None);
+        assert_eq!(get_shape_size(&[2, 0]), None);
+        assert_eq!(get_shape_size(&[usize::MAX, 2]), None);
+        assert_eq!(get_shape_size(&[1; MAX_DIMS + 1]), None);
+    }
+}
@@ -0,0 +1,123 @@
+// data with lots of zeros (e.g. pruned parameters) is stored as chunks of literal bytes followed
+// by number of zero bytes, chunks end with empty one. each chunk is u32 literal length, literal
+// bytes and u32 zeros count
+
+use alloc::vec::Vec;
+
+// decoded data is never that large for valid model, so corrupted run lengths fail instead of
+// allocating gigabytes of zeros
+pub const MAX_DECODED_LEN: usize = 1 << 30;
+
+// model data reader of each crate, with its own error type
+pub trait Source {
+    type Error;
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error>;
+
+    // appends given number of bytes to the buffer. buffer should grow as bytes are read, so
+    // corrupted length fails on missing data instead of allocating huge buffer first
+    fn read_literal(&mut self, len: usize, buf: &mut Vec<u8>) -> Result<(), Self::Error>;
+
+    // error for data which decodes to more than MAX_DECODED_LEN bytes
+    fn too_long_error() -> Self::Error;
+}
+
+pub fn decode<S: Source>(source: &mut S) -> Result<Vec<u8>, S::Error> {
+    let mut bytes = Vec::new();
+
+    loop {
+        let literal_len = source.read_u32()? as usize;
+        check_len(bytes.len(), literal_len).ok_or_else(S::too_long_error)?;
+
+        source.read_literal(literal_len, &mut bytes)?;
+
+        let zeros = source.read_u32()? as usize;
+
+        if literal_len == 0 && zeros == 0 {
+            return Ok(bytes);
+        }
+
+        let len = check_len(bytes.len(), zeros).ok_or_else(S::too_long_error)?;
+        bytes.resize(len, 0);
+    }
+}
+
+fn check_len(len: usize, chunk_len: usize) -> Option<usize> {
+    len.checked_add(chunk_len)
+        .filter(|len| *len <= MAX_DECODED_LEN)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        Eof,
+        TooLong,
+    }
+
+    struct SliceSource<'a>(&'a [u8]);
+
+    impl Source for SliceSource<'_> {
+        type Error = Error;
+
+        fn read_u32(&mut self) -> Result<u32, Error> {
+            let mut buf = Vec::new();
+            self.read_literal(4, &mut buf)?;
+            Ok(u32::from_ne_bytes([buf[0], buf[1], buf[2], buf[3]]))
+        }
+
+        fn read_literal(&mut self, len: usize, buf: &mut Vec<u8>) -> Result<(), Error> {
+            if self.0.len() < len {
+                return Err(Error::Eof);
+            }
+
+            let (head, rest) = self.0.split_at(len);
+            buf.extend_from_slice(head);
+            self.0 = rest;
+            Ok(())
+        }
+
+        fn too_long_error() -> Error {
+            Error::TooLong
+        }
+    }
+
+    fn encode(chunks: &[(u32, &[u8], u32)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        for (literal_len, literal, zeros) in chunks {
+            bytes.extend(literal_len.to_ne_bytes());
+            bytes.extend(*literal);
+            bytes.extend(zeros.to_ne_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn decode_runs() {
+        let bytes = encode(&[(2, &[1, 2], 3), (1, &[4], 0), (0, &[], 0)]);
+        assert_eq!(decode(&mut SliceSource(&bytes)), Ok(vec![1, 2, 0, 0, 0, 4]));
+
+        // missing terminating chunk
+        let bytes = encode(&[(2, &[1, 2], 3)]);
+        assert_eq!(decode(&mut SliceSource(&bytes)), Err(Error::Eof));
+    }
+
+    #[test]
+    fn decode_oversized_runs() {
+        // rejected before allocation
+        for chunks in [
+            [(0, &[][..], u32::MAX)],
+            [(u32::MAX, &[1, 2][..], 0)],
+            [(2, &[1, 2][..], MAX_DECODED_LEN as u32)],
+        ] {
+            let bytes = encode(&chunks);
+            assert_eq!(decode(&mut SliceSource(&bytes)), Err(Error::TooLong));
+        }
+    }
+}
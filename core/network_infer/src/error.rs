@@ -0,0 +1,33 @@
+use alloc::string::String;
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InferError {
+    // model data doesn't start with known file header
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u32),
+    // layers sizes read from model data can't describe valid network
+    InvalidShape,
+    UnexpectedEof,
+    TrailingBytes,
+    // layer config read from model data is corrupted
+    InvalidLayer(String),
+    // layer type can't be evaluated by dense model
+    UnsupportedLayer(String),
+}
+
+impl fmt::Display for InferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InferError::BadMagic(magic) => write!(f, "bad magic bytes: {magic:?}"),
+            InferError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version: {version}")
+            }
+            InferError::InvalidShape => write!(f, "invalid layers sizes"),
+            InferError::UnexpectedEof => write!(f, "unexpected end of model data"),
+            InferError::TrailingBytes => write!(f, "trailing bytes after model data"),
+            InferError::InvalidLayer(reason) => write!(f, "invalid layer: {reason}"),
+            InferError::UnsupportedLayer(tag) => write!(f, "unsupported layer: {tag}"),
+        }
+    }
+}
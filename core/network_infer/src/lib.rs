@@ -0,0 +1,10 @@
+// inference of dense models saved by "network" crate, without std and random generators, so it
+// can be used on embedded targets and in small wasm modules
+#![no_std]
+
+extern crate alloc;
+
+pub mod error;
+pub mod model;
+
+mod reader;
@@ -0,0 +1,474 @@
+use alloc::{format, string::String, vec::Vec};
+
+use network_format::{
+    get_shape_size, tags, DENSE_FORMAT_VERSION, FORMAT_VERSION, MAGIC, MAX_DIMS,
+    MAX_LEGACY_LAYERS_COUNT, MAX_TAG_LEN, SPARSE_FORMAT_VERSION,
+};
+
+use crate::{error::InferError, reader::Reader};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    Tanh,
+    Relu,
+    Sigmoid,
+}
+
+impl Activation {
+    fn from_u32(kind: u32) -> Option<Self> {
+        match kind {
+            0 => Some(Activation::Tanh),
+            1 => Some(Activation::Relu),
+            2 => Some(Activation::Sigmoid),
+            _ => None,
+        }
+    }
+
+    fn apply(self, d: f32) -> f32 {
+        match self {
+            Activation::Tanh => libm::tanhf(d),
+            Activation::Relu => d.max(0.0),
+            Activation::Sigmoid => 1.0 / (1.0 + libm::expf(-d)),
+        }
+    }
+}
+
+struct DenseLayer {
+    inputs: usize,
+    outputs: usize,
+    // row-major matrix [outputs x inputs], ie. each row holds weights of one neuron
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+    // activation layer following dense layer is applied right in its forward pass
+    activation: Option<Activation>,
+}
+
+impl DenseLayer {
+    // params of each neuron are its weights followed by bias
+    fn read(reader: &mut Reader, inputs: usize, outputs: usize) -> Result<Self, InferError> {
+        let params_count = inputs
+            .checked_add(1)
+            .and_then(|count| count.checked_mul(outputs))
+            .filter(|count| *count > 0 && inputs > 0)
+            .ok_or(InferError::InvalidShape)?;
+
+        // check data size before allocating, so corrupted sizes don't allocate huge layer
+        if !matches!(params_count.checked_mul(8), Some(size) if size <= reader.remaining()) {
+            return Err(InferError::UnexpectedEof);
+        }
+
+        let mut weights = Vec::with_capacity(inputs * outputs);
+        let mut biases = Vec::with_capacity(outputs);
+
+        for _ in 0..outputs {
+            for _ in 0..inputs {
+                weights.push(reader.read_f64()? as f32);
+            }
+
+            biases.push(reader.read_f64()? as f32);
+        }
+
+        Ok(DenseLayer {
+            inputs,
+            outputs,
+            weights,
+            biases,
+            activation: None,
+        })
+    }
+
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.weights
+            .chunks_exact(self.inputs)
+            .zip(&self.biases)
+            .map(|(row, bias)| {
+                let sum = row.iter().zip(input).fold(*bias, |acc, (w, x)| acc + w * x);
+
+                match self.activation {
+                    Some(activation) => activation.apply(sum),
+                    None => sum,
+                }
+            })
+            .collect()
+    }
+}
+
+// dense network read from model file. only dense layers (optionally followed by activation) are
+// supported, layers which only change shape of inputs and dropout are skipped
+pub struct Model {
+    layers: Vec<DenseLayer>,
+}
+
+impl Model {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InferError> {
+        let mut reader = Reader::new(bytes);
+        let magic = reader.read_bytes()?;
+
+        let model = if magic == MAGIC {
+            let version = reader.read_u32()?;
+
+            match version {
+                FORMAT_VERSION => Self::read_layers(&mut reader)?,
+                SPARSE_FORMAT_VERSION => Self::read_sparse(&mut reader)?,
+                DENSE_FORMAT_VERSION => {
+                    let layers_count = reader.read_u32()?;
+                    Self::read_dense_layers(&mut reader, layers_count)?
+                }
+                _ => return Err(InferError::UnsupportedVersion(version)),
+            }
+        } else {
+            // files written before header was introduced start with layers count right away
+            let layers_count = u32::from_ne_bytes(magic);
+
+            if layers_count == 0 || layers_count > MAX_LEGACY_LAYERS_COUNT {
+                return Err(InferError::BadMagic(magic));
+            }
+
+            Self::read_dense_layers(&mut reader, layers_count)?
+        };
+
+        if reader.remaining() > 0 {
+            return Err(InferError::TrailingBytes);
+        }
+
+        Ok(model)
+    }
+
+    fn read_layers(reader: &mut Reader) -> Result<Self, InferError> {
+        // only size of the inputs matters, since dense layers take flat vectors
+        let mut size = Self::read_shape_size(reader)?;
+
+        let layers_count = reader.read_u32()?;
+        let mut layers: Vec<DenseLayer> = Vec::new();
+
+        for _ in 0..layers_count {
+            let tag = reader.read_str(MAX_TAG_LEN)?;
+
+            match tag {
+                tags::DENSE => {
+                    let inputs = reader.read_u32()? as usize;
+                    let outputs = reader.read_u32()? as usize;
+
+                    if inputs != size {
+                        return Err(InferError::InvalidShape);
+                    }
+
+                    layers.push(DenseLayer::read(reader, inputs, outputs)?);
+                    size = outputs;
+                }
+                tags::ACTIVATION => {
+                    let kind = reader.read_u32()?;
+                    let activation = Activation::from_u32(kind).ok_or_else(|| {
+                        InferError::InvalidLayer(format!("unknown activation: {kind}"))
+                    })?;
+
+                    match layers.last_mut() {
+                        Some(last) if last.activation.is_none() => {
+                            last.activation = Some(activation)
+                        }
+                        _ => return Err(InferError::UnsupportedLayer(String::from(tag))),
+                    }
+                }
+                tags::DROPOUT => {
+                    reader.read_f64()?;
+                }
+                tags::FLATTEN => {}
+                tags::RESHAPE => {
+                    if Self::read_shape_size(reader)? != size {
+                        return Err(InferError::InvalidShape);
+                    }
+                }
+                _ => return Err(InferError::UnsupportedLayer(String::from(tag))),
+            }
+        }
+
+        if layers.is_empty() {
+            return Err(InferError::UnsupportedLayer(String::from(
+                "network without dense layers",
+            )));
+        }
+
+        Ok(Model { layers })
+    }
+
+    fn read_sparse(reader: &mut Reader) -> Result<Self, InferError> {
+        let bytes = reader.read_zero_runs()?;
+
+        let mut layers_reader = Reader::new(&bytes);
+        let model = Self::read_layers(&mut layers_reader)?;
+
+        if layers_reader.remaining() > 0 {
+            return Err(InferError::TrailingBytes);
+        }
+
+        // pruned parameters are zeros already, so the mask isn't needed for inference
+        let parameters_count = model.parameters_count();
+        reader.skip(parameters_count / 8 + usize::from(parameters_count % 8 != 0))?;
+
+        Ok(model)
+    }
+
+    // reads shape and returns number of values of that shape
+    fn read_shape_size(reader: &mut Reader) -> Result<usize, InferError> {
+        let dims = reader.read_u32()? as usize;
+
+        if dims == 0 || dims > MAX_DIMS {
+            return Err(InferError::InvalidShape);
+        }
+
+        let mut shape = Vec::new();
+        for _ in 0..dims {
+            shape.push(reader.read_u32()? as usize);
+        }
+
+        // the same check as in Reshape layer of "network" crate, so both read the same files
+        let size = get_shape_size(&shape).ok_or(InferError::InvalidShape)?;
+
+        Ok(size)
+    }
+
+    fn read_dense_layers(reader: &mut Reader, layers_count: u32) -> Result<Self, InferError> {
+        let mut layers_sizes = Vec::new();
+
+        // inputs size, then outputs size of each layer
+        for _ in 0..=layers_count {
+            layers_sizes.push(reader.read_u32()? as usize);
+        }
+
+        let mut layers = Vec::new();
+
+        for sizes in layers_sizes.windows(2) {
+            let mut layer = DenseLayer::read(reader, sizes[0], sizes[1])?;
+            layer.activation = Some(Activation::Tanh);
+            layers.push(layer);
+        }
+
+        Ok(Model { layers })
+    }
+
+    pub fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        assert_eq!(inputs.len(), self.inputs_count(), "invalid inputs size");
+
+        let mut res = self.layers[0].forward(inputs);
+
+        for layer in &self.layers[1..] {
+            res = layer.forward(&res);
+        }
+
+        res
+    }
+
+    pub fn inputs_count(&self) -> usize {
+        self.layers[0].inputs
+    }
+
+    pub fn outputs_count(&self) -> usize {
+        self.layers.last().unwrap().outputs
+    }
+
+    pub fn parameters_count(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| layer.weights.len() + layer.biases.len())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec, vec::Vec};
+
+    use network::{
+        layer::{
+            activation::Activation as NetActivation, dense::Dense, dropout::Dropout,
+            flatten::Flatten, reshape::Reshape, Layer,
+        },
+        network::Network,
+        prune::PruneMethod,
+    };
+
+    use super::*;
+
+    // shipped models of digits experiment, all of them in legacy format
+    const MODELS: [&[u8]; 4] = [
+        include_bytes!(
+            "../../../experiments/digits/nn/train_runner/models/digits-784-30-10/digits-784-30-10-epoch-4.nm"
+        ),
+        include_bytes!(
+            "../../../experiments/digits/nn/train_runner/models/digits-784-100-10/digits-784-100-10-epoch-5.nm"
+        ),
+        include_bytes!(
+            "../../../experiments/digits/nn/train_runner/models/digits-784-100-30-10/digits-784-100-30-10-epoch-3.nm"
+        ),
+        include_bytes!(
+            "../../../experiments/digits/nn/train_runner/models/digits-784-200-80-10/digits-784-200-80-10-epoch-6-error-0.04.nm"
+        ),
+    ];
+
+    // hand-drawn digits in mnist format, pixels are converted to [-1, 1] range as in training
+    const IMAGES: &[u8] =
+        include_bytes!("../../../experiments/digits/data/canvas_digits/t10k-images-idx3-ubyte");
+    const IMAGES_HEADER_SIZE: usize = 16;
+    const IMAGE_SIZE: usize = 28 * 28;
+
+    fn images() -> impl Iterator<Item = Vec<f64>> {
+        IMAGES[IMAGES_HEADER_SIZE..]
+            .chunks_exact(IMAGE_SIZE)
+            .map(|image| image.iter().map(|v| *v as f64 / 127.5 - 1.0).collect())
+    }
+
+    fn assert_same_outputs(net: &Network, model: &Model, inputs: &[f64]) {
+        let expected: Vec<f64> = net.forward(inputs).iter().map(|v| v.borrow().d).collect();
+
+        let inputs: Vec<f32> = inputs.iter().map(|d| *d as f32).collect();
+        let actual = model.forward(&inputs);
+
+        assert_eq!(actual.len(), expected.len());
+
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert!(
+                (expected - actual as f64).abs() < 1e-4,
+                "expected {expected}, actual {actual}"
+            );
+        }
+    }
+
+    fn serialize(net: &Network) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        net.serialize_to_writer(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn shipped_models() {
+        for bytes in MODELS {
+            let net = Network::deserialize_from_reader(bytes).unwrap();
+            let model = Model::from_bytes(bytes).unwrap();
+
+            assert_eq!(model.inputs_count(), IMAGE_SIZE);
+            assert_eq!(model.outputs_count(), 10);
+            assert_eq!(model.parameters_count(), net.parameters().len());
+
+            for image in images() {
+                assert_same_outputs(&net, &model, &image);
+            }
+        }
+    }
+
+    #[test]
+    fn layers_format() {
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Flatten::new()),
+            Box::new(Dense::new(6, 5)),
+            Box::new(NetActivation::relu()),
+            Box::new(Dropout::new(0.2)),
+            Box::new(Dense::new(5, 3)),
+            Box::new(NetActivation::sigmoid()),
+        ];
+        let net = Network::from_layers(vec![2, 3], layers).unwrap();
+
+        let model = Model::from_bytes(&serialize(&net)).unwrap();
+
+        assert_eq!(model.inputs_count(), 6);
+        assert_same_outputs(&net, &model, &[0.5, -1.0, 2.0, 0.0, -0.3, 1.5]);
+    }
+
+    #[test]
+    fn sparse_format() {
+        let mut net = Network::new(vec![20, 10, 5]);
+        net.prune(PruneMethod::Unstructured, 0.8);
+
+        let bytes = serialize(&net);
+        let model = Model::from_bytes(&bytes).unwrap();
+
+        let inputs: Vec<f64> = (0..20).map(|i| (i as f64 * 0.4).sin()).collect();
+        assert_same_outputs(&net, &model, &inputs);
+
+        assert_eq!(
+            Model::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(InferError::UnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn dense_format() {
+        let net = Network::new(vec![3, 4, 2]);
+
+        // version 1: header, then layers sizes and params of dense layers
+        let mut bytes = MAGIC.to_vec();
+        for n in [DENSE_FORMAT_VERSION, 2, 3, 4, 2] {
+            bytes.extend(n.to_ne_bytes());
+        }
+        for param in net.parameters() {
+            bytes.extend(param.borrow().d.to_ne_bytes());
+        }
+
+        let model = Model::from_bytes(&bytes).unwrap();
+        assert_same_outputs(&net, &model, &[1.0, -2.0, 0.5]);
+    }
+
+    #[test]
+    fn reshape_format() {
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Reshape::new(vec![4, 1])),
+            Box::new(Flatten::new()),
+            Box::new(Dense::new(4, 2)),
+        ];
+        let net = Network::from_layers(vec![4], layers).unwrap();
+
+        let bytes = serialize(&net);
+        let model = Model::from_bytes(&bytes).unwrap();
+        assert_same_outputs(&net, &model, &[1.0, -2.0, 0.5, 0.0]);
+
+        // reshape to [4, 0] is rejected by both crates
+        let tag_pos = bytes
+            .windows(tags::RESHAPE.len())
+            .position(|w| w == tags::RESHAPE.as_bytes())
+            .unwrap();
+        let dim_pos = tag_pos + tags::RESHAPE.len() + 8;
+
+        let mut zero_dim = bytes.clone();
+        zero_dim[dim_pos..dim_pos + 4].copy_from_slice(&0u32.to_ne_bytes());
+
+        assert_eq!(
+            Model::from_bytes(&zero_dim).err(),
+            Some(InferError::InvalidShape)
+        );
+        assert!(Network::deserialize_from_reader(zero_dim.as_slice()).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = serialize(&Network::new(vec![3, 4, 2]));
+
+        assert_eq!(
+            Model::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(InferError::UnexpectedEof)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            Model::from_bytes(&trailing).err(),
+            Some(InferError::TrailingBytes)
+        );
+
+        let mut version = bytes.clone();
+        version[4..8].copy_from_slice(&7u32.to_ne_bytes());
+        assert_eq!(
+            Model::from_bytes(&version).err(),
+            Some(InferError::UnsupportedVersion(7))
+        );
+
+        assert_eq!(
+            Model::from_bytes(&[0; 8]).err(),
+            Some(InferError::BadMagic([0; 4]))
+        );
+
+        let net = Network::from_layers(vec![3], vec![Box::new(NetActivation::tanh())]).unwrap();
+        assert_eq!(
+            Model::from_bytes(&serialize(&net)).err(),
+            Some(InferError::UnsupportedLayer(String::from("activation")))
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use alloc::{format, string::String, vec::Vec};
+
+use network_format::zero_runs;
+
+use crate::error::InferError;
+
+// reads values of model data in the same byte order they were written by "network" crate
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    pub(crate) fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N], InferError> {
+        if self.bytes.len() < N {
+            return Err(InferError::UnexpectedEof);
+        }
+
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+
+        let mut buf = [0; N];
+        buf.copy_from_slice(head);
+        Ok(buf)
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, InferError> {
+        Ok(u32::from_ne_bytes(self.read_bytes()?))
+    }
+
+    pub(crate) fn read_f64(&mut self) -> Result<f64, InferError> {
+        Ok(f64::from_ne_bytes(self.read_bytes()?))
+    }
+
+    // string written with length prefix. anything longer than max length is treated as
+    // corrupted data
+    pub(crate) fn read_str(&mut self, max_len: usize) -> Result<&'a str, InferError> {
+        let len = self.read_u32()? as usize;
+
+        if len > max_len {
+            return Err(InferError::InvalidLayer(format!(
+                "too long layer tag: {len}"
+            )));
+        }
+
+        if self.bytes.len() < len {
+            return Err(InferError::UnexpectedEof);
+        }
+
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        core::str::from_utf8(head)
+            .map_err(|_| InferError::InvalidLayer(String::from("invalid layer tag")))
+    }
+
+    pub(crate) fn skip(&mut self, len: usize) -> Result<(), InferError> {
+        if self.bytes.len() < len {
+            return Err(InferError::UnexpectedEof);
+        }
+
+        self.bytes = &self.bytes[len..];
+        Ok(())
+    }
+
+    // chunks of literal bytes followed by number of zero bytes, ending with empty chunk, see
+    // utils::write_zero_runs() of "network" crate
+    pub(crate) fn read_zero_runs(&mut self) -> Result<Vec<u8>, InferError> {
+        zero_runs::decode(self)
+    }
+
+    // lets callers check that data is long enough before allocating memory for it
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl zero_runs::Source for Reader<'_> {
+    type Error = InferError;
+
+    fn read_u32(&mut self) -> Result<u32, InferError> {
+        Reader::read_u32(self)
+    }
+
+    fn read_literal(&mut self, len: usize, buf: &mut Vec<u8>) -> Result<(), InferError> {
+        if self.bytes.len() < len {
+            return Err(InferError::UnexpectedEof);
+        }
+
+        let (literal, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        buf.extend_from_slice(literal);
+        Ok(())
+    }
+
+    fn too_long_error() -> InferError {
+        InferError::InvalidLayer(format!(
+            "zero runs exceed {} bytes",
+            zero_runs::MAX_DECODED_LEN
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read() {
+        let mut bytes = alloc::vec::Vec::new();
+        bytes.extend(7u32.to_ne_bytes());
+        bytes.extend(0.5f64.to_ne_bytes());
+        bytes.extend(3u32.to_ne_bytes());
+        bytes.extend(b"abc");
+
+        let mut reader = Reader::new(&bytes);
+
+        assert_eq!(reader.read_u32(), Ok(7));
+        assert_eq!(reader.read_f64(), Ok(0.5));
+        assert_eq!(reader.read_str(8), Ok("abc"));
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(reader.read_u32(), Err(InferError::UnexpectedEof));
+    }
+
+    #[test]
+    fn read_zero_runs() {
+        // literal of two bytes followed by three zeros, and terminating chunk
+        let mut bytes = alloc::vec::Vec::new();
+        bytes.extend(2u32.to_ne_bytes());
+        bytes.extend([1, 2]);
+        bytes.extend(3u32.to_ne_bytes());
+        bytes.extend([0; 8]);
+
+        assert_eq!(
+            Reader::new(&bytes).read_zero_runs(),
+            Ok(alloc::vec![1, 2, 0, 0, 0])
+        );
+
+        // oversized runs are rejected before allocation
+        for (literal_len, zeros) in [
+            (0, u32::MAX),
+            (u32::MAX, 0),
+            (2, zero_runs::MAX_DECODED_LEN as u32),
+        ] {
+            let mut bytes = alloc::vec::Vec::new();
+            bytes.extend(literal_len.to_ne_bytes());
+            bytes.extend([1, 2]);
+            bytes.extend(zeros.to_ne_bytes());
+
+            assert!(matches!(
+                Reader::new(&bytes).read_zero_runs(),
+                Err(InferError::InvalidLayer(_))
+            ));
+        }
+    }
+}
@@ -5,7 +5,7 @@ use image::{imageops, Rgb, RgbImage};
 use imageproc::geometric_transformations::{rotate_about_center, Interpolation};
 use moving_least_squares as mls;
 use moving_least_squares_image as mls_image;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::{
     data::{MnistDataSet, MnistDataSetKind},
@@ -22,6 +22,11 @@ pub struct AugmentedMnistDataSet {
     aug_ratio: usize,
 
     deform_cfg: DeformationConfig,
+
+    // deformations of each sample are generated from this seed, epoch and sample index, so they
+    // don't depend on order samples are taken in, but differ between epochs
+    seed: u64,
+    epoch: u64,
 }
 
 pub struct DeformationConfig {
@@ -59,8 +64,30 @@ impl AugmentedMnistDataSet {
             dataset: MnistDataSet::new(path, kind),
             aug_ratio,
             deform_cfg,
+            seed: rand::random(),
+            epoch: 0,
         }
     }
+
+    // makes deformations reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // deformed samples are generated anew for each epoch, while staying reproducible with the seed
+    pub fn set_epoch(&mut self, epoch: u64) {
+        self.epoch = epoch;
+    }
+
+    fn sample_rng(&self, index: usize) -> StdRng {
+        let mut seed = [0; 32];
+        seed[..8].copy_from_slice(&self.seed.to_le_bytes());
+        seed[8..16].copy_from_slice(&self.epoch.to_le_bytes());
+        seed[16..24].copy_from_slice(&(index as u64).to_le_bytes());
+
+        StdRng::from_seed(seed)
+    }
 }
 
 const IMAGE_SIZE: u32 = 28;
@@ -78,7 +105,8 @@ impl ExactSizeDataset for AugmentedMnistDataSet {
             let original_image_rgb: RgbImage =
                 utils::mnist_to_rgb_image(&original_image, IMAGE_SIZE, IMAGE_SIZE);
 
-            let new_image_rgb = deform_image(&original_image_rgb, &self.deform_cfg);
+            let mut rng = self.sample_rng(index);
+            let new_image_rgb = deform_image(&original_image_rgb, &self.deform_cfg, &mut rng);
             let new_image = utils::rgb_to_mnist_image(new_image_rgb, IMAGE_SIZE, IMAGE_SIZE);
 
             (new_image, original_label)
@@ -90,9 +118,11 @@ impl ExactSizeDataset for AugmentedMnistDataSet {
     }
 }
 
-fn deform_image(original_image_rgb: &RgbImage, cfg: &DeformationConfig) -> RgbImage {
-    let mut rng = rand::thread_rng();
-
+fn deform_image(
+    original_image_rgb: &RgbImage,
+    cfg: &DeformationConfig,
+    rng: &mut impl Rng,
+) -> RgbImage {
     let new_image_rgb = original_image_rgb;
 
     // MLS deformation
@@ -100,6 +130,7 @@ fn deform_image(original_image_rgb: &RgbImage, cfg: &DeformationConfig) -> RgbIm
         new_image_rgb,
         cfg.max_mls_grid_density,
         cfg.max_mls_shift,
+        rng,
     );
 
     // rotate
@@ -166,9 +197,8 @@ fn apply_moving_least_squares_deformation(
     rgb_image: &RgbImage,
     max_grid_density: usize,
     max_shift: f32,
+    rng: &mut impl Rng,
 ) -> RgbImage {
-    let mut rng = rand::thread_rng();
-
     let grid_density = rng.gen_range(1..=max_grid_density);
 
     // init control points for deformation grid
@@ -18,9 +18,11 @@ mod utils;
 
 pub const DATASET_PATH: &str = "../../data/mnist";
 pub const MODEL_PATH: &str = "./models/mnist.npz";
+// seeds model initialization, dataset shuffling and augmentation
+pub const SEED: u64 = 0;
 
 fn main() {
-    let device = AutoDevice::default();
+    let device = AutoDevice::seed_from_u64(SEED);
     let mut model = device.build_module::<Model, f32>();
 
     if metadata(MODEL_PATH).is_ok() {
@@ -7,11 +7,13 @@ use dfdx::{
 };
 use indicatif::ProgressIterator;
 
-use crate::{deform::DATASET_DEFORM_CFG, model_type::ModelBuild, utils, DATASET_PATH};
+use crate::{deform::DATASET_DEFORM_CFG, model_type::ModelBuild, utils, DATASET_PATH, SEED};
 
 pub fn test(device: &AutoDevice, model: &ModelBuild, is_log: bool) -> f32 {
+    // same deformations on each run, so test errors of different models are comparable
     let dataset =
-        AugmentedMnistDataSet::new(DATASET_PATH, MnistDataSetKind::Test, 2, DATASET_DEFORM_CFG);
+        AugmentedMnistDataSet::new(DATASET_PATH, MnistDataSetKind::Test, 2, DATASET_DEFORM_CFG)
+            .with_seed(SEED);
 
     if is_log {
         println!(
@@ -12,7 +12,7 @@ use dfdx::{data::*, tensor::AutoDevice};
 use crate::deform::DATASET_DEFORM_CFG;
 use crate::model_type::ModelBuild;
 use crate::test::{self};
-use crate::{DATASET_PATH, MODEL_PATH};
+use crate::{DATASET_PATH, MODEL_PATH, SEED};
 
 const EPOCHS: i32 = 100;
 const BATCH_SIZE: usize = 32;
@@ -22,8 +22,9 @@ pub fn train(device: &AutoDevice, model: &mut ModelBuild) {
     // ftz substantially improves performance
     dfdx::flush_denormals_to_zero();
 
-    let dataset =
-        AugmentedMnistDataSet::new(DATASET_PATH, MnistDataSetKind::Train, 2, DATASET_DEFORM_CFG);
+    let mut dataset =
+        AugmentedMnistDataSet::new(DATASET_PATH, MnistDataSetKind::Train, 2, DATASET_DEFORM_CFG)
+            .with_seed(SEED);
 
     println!(
         "start training. time: {}, model params: {}, dataset size: {}",
@@ -32,7 +33,7 @@ pub fn train(device: &AutoDevice, model: &mut ModelBuild) {
         dataset.len()
     );
 
-    let mut rng = StdRng::seed_from_u64(0);
+    let mut rng = StdRng::seed_from_u64(SEED);
     let mut grads = model.alloc_grads();
 
     let mut opt = Adam::new(model, AdamConfig::default());
@@ -52,6 +53,9 @@ pub fn train(device: &AutoDevice, model: &mut ModelBuild) {
     for epoch_idx in 0..EPOCHS {
         let epoch_start = Instant::now();
 
+        // each epoch sees new deformations of source samples
+        dataset.set_epoch(epoch_idx as u64);
+
         let mut total_epoch_loss = 0.0;
         let mut batches_count = 0;
 
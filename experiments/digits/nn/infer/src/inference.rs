@@ -1,7 +1,16 @@
 use crate::model::NETWORK;
 
 // image represented as series of pixels, where each each pixel is a number in range [-1, 1]
-pub fn infer(image: &[f64]) -> Vec<f64> {
-    let output = NETWORK.with(|n| n.forward(image));
-    output.iter().map(|v| v.borrow().d).collect()
+pub fn infer(image: &[f64]) -> Result<Vec<f64>, String> {
+    NETWORK.with(|net| {
+        let net = net.borrow();
+        let net = net
+            .as_ref()
+            .map_err(|err| format!("failed to load model: {err}"))?;
+
+        let image: Vec<f32> = image.iter().map(|n| *n as f32).collect();
+        let output = net.forward(&image);
+
+        Ok(output.iter().map(|n| *n as f64).collect())
+    })
 }
@@ -1,2 +1,4 @@
 pub mod inference;
 pub mod model;
+#[cfg(feature = "online")]
+pub mod online;
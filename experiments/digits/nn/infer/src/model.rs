@@ -1,22 +1,34 @@
-use network::network::Network;
+use std::cell::RefCell;
+
+use network_infer::{error::InferError, model::Model};
 use once_cell::sync::Lazy;
 
 // large models fail in dev wasm build with stack overflow error while dropping lots
 // of termporary BVals which are produced by network forward pass, so use small
 // model for dev build and switch to large model in release mode only
 #[cfg(not(debug_assertions))]
-static MODEL: &[u8] =
+pub(crate) static MODEL: &[u8] =
     include_bytes!("../../train_runner/models/digits-784-1000-400-150-10/digits-784-1000-400-150-10-epoch-6-error-0.025.nm");
 
 #[cfg(debug_assertions)]
-static MODEL: &[u8] =
+pub(crate) static MODEL: &[u8] =
     include_bytes!("../../train_runner/models/digits-784-30-10/digits-784-30-10-epoch-4.nm");
 
-thread_local!(pub static NETWORK: Lazy<Network> = Lazy::new(
-    || Network::deserialize_from_reader(MODEL))
+// corrupted model should not abort whole module, so keep deserialization error around and
+// report it to callers instead.
+// model is read by no_std inference crate, so wasm module doesn't pull random generators and
+// autograd of the training code.
+// model is replaced by the one updated with online learning, see online::learn()
+thread_local!(pub static NETWORK: Lazy<RefCell<Result<Model, InferError>>> = Lazy::new(
+    || RefCell::new(Model::from_bytes(MODEL)))
 );
 
-pub fn init_model() -> usize {
+pub fn init_model() -> Result<usize, String> {
     // force model init earlier, so it doesn't slow down real first use
-    NETWORK.with(|net| net.parameters().len())
+    NETWORK.with(|net| {
+        net.borrow()
+            .as_ref()
+            .map(|net| net.parameters_count())
+            .map_err(|err| format!("failed to load model: {err}"))
+    })
 }
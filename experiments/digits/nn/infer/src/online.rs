@@ -0,0 +1,185 @@
+use std::cell::RefCell;
+
+use network::{network::Network, online::ReplayBuffer};
+use network_infer::model::Model;
+use once_cell::sync::Lazy;
+
+use crate::model::{MODEL, NETWORK};
+
+// digits drawn on canvas, in the same format as mnist dataset. they are replayed along with
+// corrections, so model doesn't forget digits it recognized before
+static REFERENCE_IMAGES: &[u8] =
+    include_bytes!("../../../data/canvas_digits/train-images-idx3-ubyte");
+static REFERENCE_LABELS: &[u8] =
+    include_bytes!("../../../data/canvas_digits/train-labels-idx1-ubyte");
+
+// corrections are small, so model mostly keeps what it learned from the whole train set
+const LEARNING_RATE: f64 = 0.01;
+const STEPS: usize = 5;
+// reference digits and previous corrections replayed along with the new one, so they aren't
+// forgotten. buffer has room for as many corrections as there are reference digits
+const REPLAY_CAPACITY: usize = 200;
+const REPLAY_SIZE: usize = 8;
+const CLASSES_COUNT: usize = 10;
+const IMAGE_SIZE: usize = 28 * 28;
+// magic number, images count, height and width
+const IMAGES_HEADER_SIZE: usize = 16;
+// magic number and labels count
+const LABELS_HEADER_SIZE: usize = 8;
+
+struct Learner {
+    net: Network,
+    buffer: ReplayBuffer,
+}
+
+// trainable copy of the embedded model, which is only created once first correction is made
+thread_local!(static LEARNER: Lazy<RefCell<Result<Learner, String>>> = Lazy::new(
+    || RefCell::new(create_learner()))
+);
+
+fn create_learner() -> Result<Learner, String> {
+    let net = Network::deserialize_from_reader(MODEL)
+        .map_err(|err| format!("failed to load model: {err}"))?;
+
+    let mut buffer = ReplayBuffer::new(REPLAY_CAPACITY, REPLAY_SIZE);
+
+    for (image, label) in read_samples(REFERENCE_IMAGES, REFERENCE_LABELS)? {
+        buffer.push(image, one_hotted(label));
+    }
+
+    Ok(Learner { net, buffer })
+}
+
+// images and labels in idx format of mnist dataset, pixels are scaled to range [-1, 1] as for
+// infer()
+fn read_samples(images: &[u8], labels: &[u8]) -> Result<Vec<(Vec<f64>, u8)>, String> {
+    let images = images.get(IMAGES_HEADER_SIZE..).unwrap_or_default();
+    let labels = labels.get(LABELS_HEADER_SIZE..).unwrap_or_default();
+
+    if images.len() != labels.len() * IMAGE_SIZE
+        || labels.iter().any(|l| *l as usize >= CLASSES_COUNT)
+    {
+        return Err(String::from("invalid reference digits"));
+    }
+
+    Ok(images
+        .chunks_exact(IMAGE_SIZE)
+        .zip(labels)
+        .map(|(image, label)| {
+            let image = image.iter().map(|v| *v as f64 / 127.5 - 1.0).collect();
+            (image, *label)
+        })
+        .collect())
+}
+
+fn one_hotted(label: u8) -> Vec<f64> {
+    let mut target = vec![0.0; CLASSES_COUNT];
+    target[label as usize] = 1.0;
+    target
+}
+
+// corrects the model with image of digit it failed to recognize, subsequent infer() calls use
+// updated model. image is in the same format as for infer().
+// returns loss of the image before correction
+pub fn learn(image: &[f64], label: u8) -> Result<f64, String> {
+    if label as usize >= CLASSES_COUNT {
+        return Err(format!("invalid label: {label}"));
+    }
+
+    let target = one_hotted(label);
+
+    LEARNER.with(|learner| {
+        let mut learner = learner.borrow_mut();
+        let learner = learner.as_mut().map_err(|err| err.clone())?;
+
+        let loss = learner
+            .net
+            .learn_one_replayed(image, &target, LEARNING_RATE, STEPS, &mut learner.buffer)
+            .map_err(|err| format!("failed to learn: {err}"))?;
+
+        // inference model is rebuilt from exported weights
+        let bytes = learner
+            .net
+            .to_bytes()
+            .map_err(|err| format!("failed to export model: {err}"))?;
+
+        NETWORK.with(|net| *net.borrow_mut() = Model::from_bytes(&bytes));
+
+        Ok(loss)
+    })
+}
+
+// model file with all corrections made so far, e.g. to keep them between sessions
+pub fn export_model() -> Result<Vec<u8>, String> {
+    LEARNER.with(|learner| {
+        learner
+            .borrow()
+            .as_ref()
+            .map_err(|err| err.clone())?
+            .net
+            .to_bytes()
+            .map_err(|err| format!("failed to export model: {err}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inference::infer;
+
+    use super::*;
+
+    fn predict(output: &[f64]) -> u8 {
+        (0..output.len())
+            .max_by(|a, b| output[*a].total_cmp(&output[*b]))
+            .unwrap() as u8
+    }
+
+    // fraction of canvas digits which weren't used as reference ones
+    fn held_out_accuracy() -> f64 {
+        let samples = read_samples(
+            include_bytes!("../../../data/canvas_digits/t10k-images-idx3-ubyte"),
+            include_bytes!("../../../data/canvas_digits/t10k-labels-idx1-ubyte"),
+        )
+        .unwrap();
+
+        let correct_count = samples
+            .iter()
+            .filter(|(image, label)| predict(&infer(image).unwrap()) == *label)
+            .count();
+
+        correct_count as f64 / samples.len() as f64
+    }
+
+    #[test]
+    fn learn_misclassified() {
+        let accuracy = held_out_accuracy();
+
+        let image = vec![-1.0; 784];
+        let label = (predict(&infer(&image).unwrap()) + 1) % 10;
+
+        let mut losses = Vec::new();
+        while predict(&infer(&image).unwrap()) != label {
+            losses.push(learn(&image, label).unwrap());
+            assert!(losses.len() < 100, "model isn't corrected");
+        }
+
+        assert!(losses.windows(2).all(|pair| pair[1] < pair[0]));
+
+        // reference digits are replayed, so correction doesn't break recognition of other ones
+        LEARNER.with(|learner| {
+            let learner = learner.borrow();
+            assert!(learner.as_ref().unwrap().buffer.len() > 100);
+        });
+        assert!(held_out_accuracy() >= accuracy);
+
+        // exported model recognizes corrected image as well
+        let model = Model::from_bytes(&export_model().unwrap()).unwrap();
+        let image: Vec<f32> = image.iter().map(|v| *v as f32).collect();
+        let output: Vec<f64> = model.forward(&image).iter().map(|v| *v as f64).collect();
+
+        assert_eq!(predict(&output), label);
+
+        assert!(learn(&[0.0; 10], label).is_err());
+        assert!(learn(&[-1.0; 784], 10).is_err());
+    }
+}
@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use network::network::Network;
-use nn_train::train::train;
+use nn_train::train::{train, TrainOptions};
 
 const TRAIN_IMAGES_FILE_PATH: &str = "../../data/mnist/train-images-idx3-ubyte";
 const TRAIN_LABELS_FILE_PATH: &str = "../../data/mnist/train-labels-idx1-ubyte";
@@ -14,17 +14,24 @@ pub fn train_benchmark(c: &mut Criterion) {
         b.iter(|| {
             train(
                 &mut net,
-                TRAIN_IMAGES_FILE_PATH,
-                TRAIN_LABELS_FILE_PATH,
-                "/tmp",
-                "digits",
-                "/tmp",
-                1,
-                10,
-                10,
-                (0.01, 0.01),
-                Some(10),
-                Some(10),
+                &TrainOptions {
+                    images_file_path: TRAIN_IMAGES_FILE_PATH,
+                    labels_file_path: TRAIN_LABELS_FILE_PATH,
+                    models_dir: "/tmp",
+                    model_file_name_prefix: "digits",
+                    plots_dir: "/tmp",
+                    epochs: 1,
+                    batches: 10,
+                    batch_size: 10,
+                    learning_rate: (0.01, 0.01),
+                    regularization: None,
+                    threads: 1,
+                    shuffle_buffer_size: None,
+                    pruning: None,
+                    plot_losses_each_nth_batch: Some(10),
+                    serialize_model_each_nth_batch: Some(10),
+                    checkpoint_file_path: None,
+                },
             )
         })
     });
@@ -0,0 +1,53 @@
+use std::{fs::File, io::BufReader};
+
+use network::{
+    diff::{NetworkDiff, OutputAgreement},
+    network::Network,
+};
+
+use crate::mnist::{images_it::ImagesIt, labels_it::LabelsIt};
+
+pub struct Comparison {
+    pub diff: NetworkDiff,
+    // None if networks take different inputs or produce different outputs
+    pub agreement: Option<OutputAgreement>,
+}
+
+// loads two models (e.g. checkpoints of different epochs) and compares their parameters and
+// predictions on the test set
+pub fn compare(
+    base_file_path: &str,
+    other_file_path: &str,
+    images_file_path: &str,
+    labels_file_path: &str,
+) -> Comparison {
+    let base = load(base_file_path);
+    let other = load(other_file_path);
+
+    let diff = base.diff(&other);
+    println!("{diff}");
+
+    let samples = ImagesIt::new(images_file_path)
+        .zip(LabelsIt::new(labels_file_path))
+        .map(|(image, label)| (image, label as usize));
+
+    let agreement = match base.agreement(&other, samples) {
+        Ok(agreement) => {
+            println!("{agreement}");
+            Some(agreement)
+        }
+        Err(err) => {
+            println!("failed to compare outputs: {err}");
+            None
+        }
+    };
+
+    Comparison { diff, agreement }
+}
+
+fn load(path: &str) -> Network {
+    println!("deserializing network from file: {path}");
+
+    let file = File::open(path).expect("failed to open model file");
+    Network::deserialize_from_reader(BufReader::new(file)).expect("failed to deserialize network")
+}
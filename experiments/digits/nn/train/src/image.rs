@@ -1,4 +1,4 @@
-use image::{ImageBuffer, Rgb, RgbImage};
+use image::{imageops, ImageBuffer, Rgb, RgbImage};
 
 pub fn create_image(image: &[f64], width: u32, height: u32) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
     let mut rgb_image = RgbImage::new(width, height);
@@ -14,3 +14,22 @@ pub fn create_image(image: &[f64], width: u32, height: u32) -> ImageBuffer<Rgb<u
 
     rgb_image
 }
+
+// places images row by row into grid with given number of columns
+pub fn create_grid(images: &[Vec<f64>], columns: u32, width: u32, height: u32) -> RgbImage {
+    let rows = images.chunks(columns as usize).count() as u32;
+    let mut grid = RgbImage::from_pixel(width * columns, height * rows, Rgb([255, 255, 255]));
+
+    for (idx, image) in images.iter().enumerate() {
+        let x = idx as u32 % columns * width;
+        let y = idx as u32 / columns * height;
+        imageops::replace(
+            &mut grid,
+            &create_image(image, width, height),
+            x as i64,
+            y as i64,
+        );
+    }
+
+    grid
+}
@@ -1,5 +1,7 @@
+pub mod compare;
 pub mod test;
 pub mod train;
+pub mod vae;
 
 mod image;
 mod mnist;
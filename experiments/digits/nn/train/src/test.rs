@@ -12,7 +12,7 @@ pub fn test(
     labels_file_path: &str,
     failed_images_dir: &str,
     save_failed_images: bool,
-) {
+) -> f64 {
     let images_it = ImagesIt::new(images_file_path);
     let labels_it = LabelsIt::new(labels_file_path);
 
@@ -40,8 +40,12 @@ pub fn test(
         }
     }
 
+    let errors_rate = errors as f64 / count as f64;
+
     println!(
         "count: {count}, errors: {errors}, errors_percent: {}%",
-        (errors as f64 / count as f64) * 100.0
+        errors_rate * 100.0
     );
+
+    errors_rate
 }
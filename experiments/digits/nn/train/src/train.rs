@@ -1,133 +1,149 @@
-use std::time::SystemTime;
+use std::{fs, path::Path};
 
 use autograd::val::BVal;
-use network::network::Network;
+use network::{
+    network::Network,
+    optim::sgd::Sgd,
+    prune::PruningSchedule,
+    regularization::Regularization,
+    scheduler::linear_decay::LinearDecay,
+    trainer::{
+        callbacks::{Checkpoint, MetricsLogger},
+        Dataset, TrainReport, Trainer,
+    },
+};
 
 use crate::{
     mnist::{images_it::ImagesIt, labels_it::LabelsIt},
-    train::plot::plot_losses,
+    train::callbacks::LossesPlot,
     utils::predict,
 };
 
+mod callbacks;
 mod plot;
 mod utils;
 
-#[allow(clippy::too_many_arguments)]
-pub fn train(
-    net: &mut Network,
-    images_file_path: &str,
-    labels_file_path: &str,
-    models_dir: &str,
-    model_file_name_prefix: &str,
-    plots_dir: &str,
-    epochs: u32,
-    batches: u32,
-    batch_size: u32,
-    learning_rate: (f64, f64),
-    plot_losses_each_nth_batch: Option<u32>,
-    serialize_model_each_nth_batch: Option<u32>,
-) {
-    let images_it = ImagesIt::new(images_file_path);
-    let labels_it = LabelsIt::new(labels_file_path);
+pub struct TrainOptions<'a> {
+    pub images_file_path: &'a str,
+    pub labels_file_path: &'a str,
+    pub models_dir: &'a str,
+    pub model_file_name_prefix: &'a str,
+    pub plots_dir: &'a str,
+    pub epochs: u32,
+    pub batches: u32,
+    pub batch_size: u32,
+    // learning rate decays linearly from first value to second one over all epochs
+    pub learning_rate: (f64, f64),
+    pub regularization: Option<Regularization>,
+    pub threads: usize,
+    // samples of each epoch are shuffled within buffer of given size, in order defined by
+    // network::rng::seed()
+    pub shuffle_buffer_size: Option<usize>,
+    // network is pruned during training according to the schedule of global steps
+    pub pruning: Option<PruningSchedule>,
+    pub plot_losses_each_nth_batch: Option<u32>,
+    pub serialize_model_each_nth_batch: Option<u32>,
+    // training checkpoint, which is saved along with the model and resumed from if it exists
+    pub checkpoint_file_path: Option<&'a str>,
+}
+
+// first images of mnist files, which are read from the start on each epoch
+struct MnistDataset {
+    images_file_path: String,
+    labels_file_path: String,
+    images_count: usize,
+}
+
+impl Dataset for MnistDataset {
+    type Sample = (Vec<f64>, u8);
+
+    fn samples(&self) -> Box<dyn Iterator<Item = Self::Sample> + '_> {
+        let images_it = ImagesIt::new(&self.images_file_path);
+        let labels_it = LabelsIt::new(&self.labels_file_path);
+
+        Box::new(images_it.zip(labels_it).take(self.images_count))
+    }
+}
+
+pub fn train(net: &mut Network, options: &TrainOptions) -> TrainReport {
+    let images_count = options.batches * options.batch_size;
 
     assert!(
-        batches * batch_size <= images_it.images_count(),
+        images_count <= ImagesIt::new(options.images_file_path).images_count(),
         "not enough images in input stream"
     );
 
-    let mut images_and_labels_it = images_it.zip(labels_it);
-
-    let mut losses = Vec::<f64>::new();
-    let mut errors_percents = Vec::<f64>::new();
-
-    for epoch_idx in 0..epochs {
-        for batch_idx in 0..batches {
-            let batch_start = SystemTime::now();
-
-            // take next batch of images
-            let mut batch = Vec::<(Vec<f64>, u8)>::new();
-
-            for _ in 0..batch_size {
-                batch.push(
-                    images_and_labels_it
-                        .next()
-                        .expect("failed to read next image"),
-                );
-            }
-
-            // forward
-            let mut batch_loss = BVal::new(0.0);
-            let mut batch_errors = 0;
-
-            for (image, label) in &batch {
-                let output = net.forward(image);
-                let expected = utils::one_hotted(*label);
-
-                let loss = utils::calc_prediction_loss(&output, &expected);
-                batch_loss = &batch_loss + &loss;
-
-                if *label != predict(&output) {
-                    batch_errors += 1;
-                }
-            }
-
-            let batch_errors_percent = batch_errors as f64 / batch.len() as f64;
-
-            losses.push(batch_loss.borrow().d);
-            errors_percents.push(batch_errors_percent);
-
-            // backward
-            net.reset_grad();
-            batch_loss.borrow_mut().grad = 1.0;
-            batch_loss.backward();
-
-            // update
-            let learning_rate = learning_rate.0
-                - (learning_rate.0 - learning_rate.1) * batch_idx as f64 / batches as f64;
-
-            for param in net.parameters() {
-                let grad = param.borrow().grad;
-                param.borrow_mut().d -= learning_rate * grad;
-            }
-
-            // log / plot
-            if plot_losses_each_nth_batch.is_some()
-                && batch_idx > 0
-                && batch_idx % plot_losses_each_nth_batch.unwrap() == 0
-            {
-                plot_losses(&losses, &errors_percents, plots_dir);
-            }
-
-            if serialize_model_each_nth_batch.is_some()
-                && batch_idx > 0
-                && batch_idx % serialize_model_each_nth_batch.unwrap() == 0
-            {
-                net.serialize_to_file(models_dir, model_file_name_prefix);
-            }
-
-            let batch_duration = SystemTime::now()
-                .duration_since(batch_start)
-                .unwrap_or_default();
-
-            println!(
-                "epoch = {epoch_idx}, \
-                batch = {batch_idx}, \
-                duration = {duration}ms, \
-                rate = {learning_rate:.4}, \
-                loss = {loss:.4}, \
-                errors = {errors_percent}%",
-                duration = batch_duration.as_millis(),
-                loss = batch_loss.borrow().d,
-                errors_percent = batch_errors_percent * 100.0
-            );
-        }
+    let dataset = MnistDataset {
+        images_file_path: String::from(options.images_file_path),
+        labels_file_path: String::from(options.labels_file_path),
+        images_count: images_count as usize,
+    };
+
+    let mut trainer = Trainer::new(calc_sample_loss, Sgd::new(options.learning_rate.0))
+        .epochs(options.epochs as usize)
+        .batch_size(options.batch_size as usize)
+        .threads(options.threads)
+        .regularization(options.regularization)
+        .scheduler(LinearDecay::new(
+            options.learning_rate.0,
+            options.learning_rate.1,
+            (options.epochs * options.batches) as usize,
+        ))
+        .callback(MetricsLogger::default());
+
+    if let Some(buffer_size) = options.shuffle_buffer_size {
+        trainer = trainer.shuffle(buffer_size);
+    }
 
-        if plot_losses_each_nth_batch.is_some() {
-            plot_losses(&losses, &errors_percents, plots_dir);
+    if let Some(schedule) = options.pruning {
+        trainer = trainer.pruning(schedule);
+    }
+
+    if let Some(each_nth_batch) = options.plot_losses_each_nth_batch {
+        trainer = trainer.callback(LossesPlot::new(options.plots_dir, each_nth_batch as usize));
+    }
+
+    if let Some(each_nth_batch) = options.serialize_model_each_nth_batch {
+        trainer = trainer.callback(Checkpoint::new(
+            options.models_dir,
+            options.model_file_name_prefix,
+            Some(each_nth_batch as usize),
+        ));
+    }
+
+    if let Some(file_path) = options.checkpoint_file_path {
+        trainer = trainer.checkpoint(
+            file_path,
+            options
+                .serialize_model_each_nth_batch
+                .map(|each_nth_batch| each_nth_batch as usize),
+        );
+
+        if Path::new(file_path).exists() {
+            println!("resuming training from checkpoint: {file_path}");
+            *net = trainer
+                .resume_from_checkpoint_file(file_path)
+                .expect("failed to resume training from checkpoint");
         }
+    }
 
-        if serialize_model_each_nth_batch.is_some() {
-            net.serialize_to_file(models_dir, model_file_name_prefix);
+    let report = trainer.fit(net, &dataset).expect("failed to train network");
+
+    // finished training shouldn't be resumed by the next run
+    if let Some(file_path) = options.checkpoint_file_path {
+        if !report.stopped {
+            fs::remove_file(file_path).expect("failed to remove checkpoint");
         }
     }
+
+    report
+}
+
+fn calc_sample_loss(net: &Network, (image, label): &(Vec<f64>, u8)) -> (BVal, Option<bool>) {
+    let output = net.forward(image);
+    let expected = utils::one_hotted(*label);
+
+    let loss = utils::calc_prediction_loss(&output, &expected);
+
+    (loss, Some(predict(&output) == *label))
 }
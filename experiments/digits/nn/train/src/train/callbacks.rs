@@ -0,0 +1,57 @@
+use network::{
+    error::NetworkError,
+    network::Network,
+    optim::Optimizer,
+    trainer::{BatchMetrics, Callback, Control, EpochMetrics},
+};
+
+use super::plot::plot_losses;
+
+// plots losses and errors of all batches each nth batch and at the end of each epoch
+pub struct LossesPlot {
+    dir: String,
+    each_nth_batch: usize,
+    losses: Vec<f64>,
+    errors_percents: Vec<f64>,
+}
+
+impl LossesPlot {
+    pub fn new(dir: &str, each_nth_batch: usize) -> Self {
+        LossesPlot {
+            dir: String::from(dir),
+            each_nth_batch,
+            losses: Vec::new(),
+            errors_percents: Vec::new(),
+        }
+    }
+}
+
+impl Callback for LossesPlot {
+    fn on_batch_end(
+        &mut self,
+        _net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        metrics: &BatchMetrics,
+    ) -> Result<Control, NetworkError> {
+        self.losses.push(metrics.loss);
+        self.errors_percents
+            .push(metrics.errors_percent().unwrap_or_default() / 100.0);
+
+        if metrics.batch > 0 && metrics.batch.checked_rem(self.each_nth_batch) == Some(0) {
+            plot_losses(&self.losses, &self.errors_percents, &self.dir);
+        }
+
+        Ok(Control::Continue)
+    }
+
+    fn on_epoch_end(
+        &mut self,
+        _net: &Network,
+        _optimizer: &mut dyn Optimizer,
+        _metrics: &EpochMetrics,
+    ) -> Result<Control, NetworkError> {
+        plot_losses(&self.losses, &self.errors_percents, &self.dir);
+
+        Ok(Control::Continue)
+    }
+}
@@ -0,0 +1,380 @@
+use std::time::Instant;
+
+use autograd::val::BVal;
+use network::{
+    layer::{activation::Activation, dense::Dense, Layer},
+    network::Network,
+    optim::{adam::Adam, Optimizer},
+    rng,
+};
+
+use crate::{image::create_grid, mnist::images_it::ImagesIt};
+
+pub struct VaeOptions<'a> {
+    pub images_file_path: &'a str,
+    pub images_dir: &'a str,
+    pub epochs: u32,
+    pub batches: u32,
+    pub batch_size: u32,
+    pub learning_rate: f64,
+    pub hidden_size: usize,
+    pub latent_size: usize,
+    // weight of kl divergence in the loss, values above 1 give more disentangled latent space
+    // at the cost of blurrier reconstructions
+    pub beta: f64,
+    // deviation of gaussian noise added to input images, so the model learns to denoise them
+    pub input_noise: f64,
+    // samples grid is of size x size images
+    pub samples_grid_size: u32,
+    // number of images between (and including) two interpolated digits
+    pub interpolation_steps: u32,
+    pub log_each_nth_batch: u32,
+}
+
+// variational autoencoder: encoder maps image to mean and log variance of gaussian in latent
+// space, decoder maps point of latent space back to image
+pub struct Vae {
+    pub encoder: Network,
+    pub decoder: Network,
+    latent_size: usize,
+}
+
+pub struct VaeLoss {
+    pub loss: BVal,
+    pub reconstruction: f64,
+    pub kl: f64,
+}
+
+impl Vae {
+    pub fn new(image_size: usize, hidden_size: usize, latent_size: usize) -> Self {
+        let encoder_layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(image_size, hidden_size)),
+            Box::new(Activation::tanh()),
+            // first half of outputs is mean, second one is log variance
+            Box::new(Dense::new(hidden_size, 2 * latent_size)),
+        ];
+
+        // pixels of decoded image are in range 0..1
+        let decoder_layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(latent_size, hidden_size)),
+            Box::new(Activation::tanh()),
+            Box::new(Dense::new(hidden_size, image_size)),
+            Box::new(Activation::sigmoid()),
+        ];
+
+        Self {
+            encoder: Network::from_layers(vec![image_size], encoder_layers)
+                .expect("failed to create encoder"),
+            decoder: Network::from_layers(vec![latent_size], decoder_layers)
+                .expect("failed to create decoder"),
+            latent_size,
+        }
+    }
+
+    pub fn latent_size(&self) -> usize {
+        self.latent_size
+    }
+
+    pub fn parameters(&self) -> Vec<BVal> {
+        let mut parameters = self.encoder.parameters().clone();
+        parameters.extend(self.decoder.parameters().iter().cloned());
+        parameters
+    }
+
+    pub fn reset_grad(&self) {
+        self.encoder.reset_grad();
+        self.decoder.reset_grad();
+    }
+
+    // mean and log variance of latent distribution of the image
+    pub fn encode(&self, image: &[f64]) -> (Vec<BVal>, Vec<BVal>) {
+        let mut mean = self.encoder.forward(image);
+        let log_var = mean.split_off(self.latent_size);
+
+        (mean, log_var)
+    }
+
+    pub fn decode(&self, latent: &[f64]) -> Vec<f64> {
+        values(&self.decoder.forward(latent))
+    }
+
+    // decodes mean of latent distribution, which removes noise the model was trained with
+    pub fn reconstruct(&self, image: &[f64]) -> Vec<f64> {
+        let (mean, _) = self.encode(image);
+        self.decode(&values(&mean))
+    }
+
+    // image is reconstructed from its noisy version into target one, the loss is sum of squared
+    // errors of pixels plus weighted kl divergence of latent distribution from standard normal
+    pub fn loss(&self, image: &[f64], target: &[f64], beta: f64) -> VaeLoss {
+        let (mean, log_var) = self.encode(image);
+        let latent = sample_latent(&mean, &log_var);
+        let output = self.decoder.forward_vals(latent);
+
+        let mut reconstruction = BVal::new(0.0);
+        for (out, expected) in output.iter().zip(target) {
+            reconstruction = &reconstruction + &(out - *expected).pow(2.0);
+        }
+
+        let kl = kl_divergence(&mean, &log_var);
+        let loss = &reconstruction + &(&kl * beta);
+
+        let reconstruction = reconstruction.borrow().d;
+        let kl = kl.borrow().d;
+
+        VaeLoss {
+            loss,
+            reconstruction,
+            kl,
+        }
+    }
+}
+
+// reparameterization trick: z = mean + std * eps, so randomness doesn't block gradients
+fn sample_latent(mean: &[BVal], log_var: &[BVal]) -> Vec<BVal> {
+    mean.iter()
+        .zip(log_var)
+        .map(|(mean, log_var)| {
+            let std = (log_var * 0.5).exp();
+            mean + &(&std * rng::gen_normal(1.0))
+        })
+        .collect()
+}
+
+// kl divergence of N(mean, exp(log_var)) from N(0, 1)
+fn kl_divergence(mean: &[BVal], log_var: &[BVal]) -> BVal {
+    let mut sum = BVal::new(0.0);
+
+    for (mean, log_var) in mean.iter().zip(log_var) {
+        let term = &(&(1.0 + log_var) - &mean.pow(2.0)) - &log_var.exp();
+        sum = &sum + &term;
+    }
+
+    &sum * -0.5
+}
+
+fn values(vals: &[BVal]) -> Vec<f64> {
+    vals.iter().map(|v| v.borrow().d).collect()
+}
+
+// mnist images are normalized to range -1..1, vae works with pixels in range 0..1
+fn to_unit_range(image: Vec<f64>) -> Vec<f64> {
+    image.into_iter().map(|v| (v + 1.0) / 2.0).collect()
+}
+
+fn add_noise(image: &[f64], deviation: f64) -> Vec<f64> {
+    if deviation == 0.0 {
+        return image.to_vec();
+    }
+
+    image
+        .iter()
+        .map(|v| (v + rng::gen_normal(deviation)).clamp(0.0, 1.0))
+        .collect()
+}
+
+fn lerp(from: &[f64], to: &[f64], t: f64) -> Vec<f64> {
+    from.iter().zip(to).map(|(a, b)| a + (b - a) * t).collect()
+}
+
+pub fn train_vae(options: &VaeOptions) -> Vae {
+    let images_it = ImagesIt::new(options.images_file_path);
+    let (image_width, image_height) = images_it.image_size();
+    let images_count = options.batches * options.batch_size;
+
+    assert!(
+        images_count <= images_it.images_count(),
+        "not enough images in input stream"
+    );
+
+    let vae = Vae::new(
+        (image_width * image_height) as usize,
+        options.hidden_size,
+        options.latent_size,
+    );
+    let parameters = vae.parameters();
+    let mut optimizer = Adam::new(options.learning_rate);
+
+    // same images are encoded each epoch, so progress of interpolations can be compared
+    let fixed_images: Vec<Vec<f64>> = ImagesIt::new(options.images_file_path)
+        .take(2 * options.samples_grid_size as usize)
+        .map(to_unit_range)
+        .collect();
+
+    for epoch in 0..options.epochs {
+        let epoch_start = Instant::now();
+        let mut images_it = ImagesIt::new(options.images_file_path)
+            .take(images_count as usize)
+            .map(to_unit_range);
+
+        let mut epoch_reconstruction = 0.0;
+        let mut epoch_kl = 0.0;
+
+        for batch_idx in 0..options.batches {
+            let batch: Vec<Vec<f64>> = images_it
+                .by_ref()
+                .take(options.batch_size as usize)
+                .collect();
+
+            vae.reset_grad();
+
+            let mut batch_loss = BVal::new(0.0);
+            let mut batch_reconstruction = 0.0;
+            let mut batch_kl = 0.0;
+
+            for image in &batch {
+                let loss = vae.loss(&add_noise(image, options.input_noise), image, options.beta);
+
+                batch_loss = &batch_loss + &loss.loss;
+                batch_reconstruction += loss.reconstruction;
+                batch_kl += loss.kl;
+            }
+
+            let batch_loss = &batch_loss / batch.len() as f64;
+            batch_loss.borrow_mut().grad = 1.0;
+            batch_loss.backward();
+
+            optimizer.step(&parameters);
+
+            batch_reconstruction /= batch.len() as f64;
+            batch_kl /= batch.len() as f64;
+            epoch_reconstruction += batch_reconstruction;
+            epoch_kl += batch_kl;
+
+            if (batch_idx + 1) % options.log_each_nth_batch == 0 {
+                println!(
+                    "epoch: {epoch}, batch: {batch_idx}, reconstruction: {batch_reconstruction:.4}, kl: {batch_kl:.4}"
+                );
+            }
+        }
+
+        println!(
+            "epoch: {epoch}, reconstruction: {:.4}, kl: {:.4}, duration: {:?}",
+            epoch_reconstruction / options.batches as f64,
+            epoch_kl / options.batches as f64,
+            epoch_start.elapsed()
+        );
+
+        save_images(
+            &vae,
+            options,
+            &fixed_images,
+            epoch,
+            image_width,
+            image_height,
+        );
+    }
+
+    vae
+}
+
+fn save_images(
+    vae: &Vae,
+    options: &VaeOptions,
+    fixed_images: &[Vec<f64>],
+    epoch: u32,
+    image_width: u32,
+    image_height: u32,
+) {
+    let grid_size = options.samples_grid_size;
+
+    // digits generated from points of latent space sampled from prior
+    let samples: Vec<Vec<f64>> = (0..grid_size * grid_size)
+        .map(|_| {
+            let latent: Vec<f64> = (0..vae.latent_size())
+                .map(|_| rng::gen_normal(1.0))
+                .collect();
+            vae.decode(&latent)
+        })
+        .collect();
+
+    create_grid(&samples, grid_size, image_width, image_height)
+        .save(format!("{}/vae-samples-{epoch}.png", options.images_dir))
+        .expect("failed to save image");
+
+    // each row morphs one digit into another through means of their latent distributions
+    let steps = options.interpolation_steps.max(2);
+    let mut interpolations = Vec::new();
+
+    for pair in fixed_images.chunks_exact(2) {
+        let (from, _) = vae.encode(&pair[0]);
+        let (to, _) = vae.encode(&pair[1]);
+        let (from, to) = (values(&from), values(&to));
+
+        for step in 0..steps {
+            let t = step as f64 / (steps - 1) as f64;
+            interpolations.push(vae.decode(&lerp(&from, &to, t)));
+        }
+    }
+
+    create_grid(&interpolations, steps, image_width, image_height)
+        .save(format!(
+            "{}/vae-interpolations-{epoch}.png",
+            options.images_dir
+        ))
+        .expect("failed to save image");
+
+    // columns of original, noisy and reconstructed images
+    let mut denoised = Vec::new();
+
+    for image in fixed_images.iter().take(grid_size as usize) {
+        let noisy = add_noise(image, options.input_noise);
+        let reconstructed = vae.reconstruct(&noisy);
+        denoised.extend([image.clone(), noisy, reconstructed]);
+    }
+
+    create_grid(&denoised, 3, image_width, image_height)
+        .save(format!("{}/vae-denoised-{epoch}.png", options.images_dir))
+        .expect("failed to save image");
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn kl_of_standard_normal() {
+        let mean = vec![BVal::new(0.0), BVal::new(0.0)];
+        let log_var = vec![BVal::new(0.0), BVal::new(0.0)];
+
+        assert_eq!(kl_divergence(&mean, &log_var).borrow().d, 0.0);
+
+        // 0.5 * (exp(log_var) + mean^2 - 1 - log_var)
+        let mean = vec![BVal::new(1.0)];
+        let log_var = vec![BVal::new(2.0_f64.ln())];
+
+        assert_approx_eq!(
+            f64,
+            kl_divergence(&mean, &log_var).borrow().d,
+            0.5 * (2.0 + 1.0 - 1.0 - 2.0_f64.ln())
+        );
+    }
+
+    #[test]
+    fn loss_gradients() {
+        let vae = Vae::new(4, 3, 2);
+        let image = vec![0.0, 0.5, 1.0, 0.25];
+
+        let loss = vae.loss(&image, &image, 1.0);
+
+        assert_eq!(vae.decode(&[0.0, 0.0]).len(), 4);
+        assert_approx_eq!(f64, loss.loss.borrow().d, loss.reconstruction + loss.kl);
+
+        loss.loss.borrow_mut().grad = 1.0;
+        loss.loss.backward();
+
+        // both networks are trained by the same loss
+        assert!(vae
+            .encoder
+            .parameters()
+            .iter()
+            .any(|p| p.borrow().grad != 0.0));
+        assert!(vae
+            .decoder
+            .parameters()
+            .iter()
+            .any(|p| p.borrow().grad != 0.0));
+    }
+}
@@ -0,0 +1,36 @@
+use std::env;
+
+use nn_train::compare::compare;
+
+// compares two models, e.g. checkpoints of different epochs:
+// cargo run --bin compare -- <base model file> <other model file> [test images] [test labels]
+const TEST_IMAGES_FILE_PATH: &str = "../../data/mnist/t10k-images-idx3-ubyte";
+const TEST_LABELS_FILE_PATH: &str = "../../data/mnist/t10k-labels-idx1-ubyte";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let (base_file_path, other_file_path) = match args.as_slice() {
+        [base, other, ..] => (base.as_str(), other.as_str()),
+        _ => {
+            println!(
+                "usage: compare <base model file> <other model file> [test images] [test labels]"
+            );
+            return;
+        }
+    };
+
+    let images_file_path = args
+        .get(2)
+        .map_or(TEST_IMAGES_FILE_PATH, |path| path.as_str());
+    let labels_file_path = args
+        .get(3)
+        .map_or(TEST_LABELS_FILE_PATH, |path| path.as_str());
+
+    compare(
+        base_file_path,
+        other_file_path,
+        images_file_path,
+        labels_file_path,
+    );
+}
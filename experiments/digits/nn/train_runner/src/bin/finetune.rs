@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use network::{
+    registry::{MetricGoal, ModelRegistry},
+    rng,
+};
+use nn_train::{
+    test::test,
+    train::{train, TrainOptions},
+};
+
+// fine-tunes the best model pretrained on mnist with hand-drawn canvas digits
+const TRAIN_IMAGES_FILE_PATH: &str = "../../data/canvas_digits/train-images-idx3-ubyte";
+const TRAIN_LABELS_FILE_PATH: &str = "../../data/canvas_digits/train-labels-idx1-ubyte";
+const TEST_IMAGES_FILE_PATH: &str = "../../data/canvas_digits/t10k-images-idx3-ubyte";
+const TEST_LABELS_FILE_PATH: &str = "../../data/canvas_digits/t10k-labels-idx1-ubyte";
+
+const MODEL_FILE_NAME_PREFIX: &str = "digits-canvas";
+const MODELS_DIR: &str = "./models";
+const REGISTRY_DIR: &str = "./models/registry";
+const PLOTS_DIR: &str = "./plots";
+const FAILED_IMAGES_DIR: &str = "./images";
+
+// last dense layer and its activation are trained, earlier ones are frozen
+const TRAINABLE_LAYERS: usize = 2;
+
+const EPOCHS: u32 = 20;
+const BATCHES: u32 = 10;
+const BATCH_SIZE: u32 = 10;
+const LEARNING_RATE: (f64, f64) = (0.01, 0.001);
+const THREADS: usize = 4;
+// canvas dataset is small, so it's shuffled entirely
+const SHUFFLE_BUFFER_SIZE: usize = 100;
+const SEED: Option<u64> = Some(0);
+
+fn main() {
+    if let Some(seed) = SEED {
+        rng::seed(seed);
+    }
+
+    let mut registry = ModelRegistry::open(REGISTRY_DIR).expect("failed to open model registry");
+
+    let parent = registry
+        .best_by("test_error", MetricGoal::Minimize)
+        .map(|entry| entry.id.clone())
+        .expect("failed to find pretrained model, run nn_train_runner first");
+
+    println!("loading model from registry: {parent}");
+    let mut net = registry.load(&parent).expect("failed to load model");
+
+    net.freeze();
+    for idx in net.layers.len().saturating_sub(TRAINABLE_LAYERS)..net.layers.len() {
+        net.set_trainable(idx, true);
+    }
+
+    println!("{net}");
+
+    let report = train(
+        &mut net,
+        &TrainOptions {
+            images_file_path: TRAIN_IMAGES_FILE_PATH,
+            labels_file_path: TRAIN_LABELS_FILE_PATH,
+            models_dir: MODELS_DIR,
+            model_file_name_prefix: MODEL_FILE_NAME_PREFIX,
+            plots_dir: PLOTS_DIR,
+            epochs: EPOCHS,
+            batches: BATCHES,
+            batch_size: BATCH_SIZE,
+            learning_rate: LEARNING_RATE,
+            regularization: None,
+            threads: THREADS,
+            shuffle_buffer_size: Some(SHUFFLE_BUFFER_SIZE),
+            pruning: None,
+            plot_losses_each_nth_batch: None,
+            serialize_model_each_nth_batch: None,
+            checkpoint_file_path: None,
+        },
+    );
+
+    let test_error = test(
+        &net,
+        TEST_IMAGES_FILE_PATH,
+        TEST_LABELS_FILE_PATH,
+        FAILED_IMAGES_DIR,
+        false,
+    );
+
+    let mut metrics = BTreeMap::from([(String::from("canvas_test_error"), test_error)]);
+
+    if let Some(last_epoch) = report.epochs.last() {
+        metrics.insert(String::from("loss"), last_epoch.loss);
+    }
+
+    let id = registry
+        .register(MODEL_FILE_NAME_PREFIX, &net, metrics, Some(&parent))
+        .expect("failed to register model");
+
+    println!("registered model: {id}");
+}
@@ -0,0 +1,145 @@
+use network::{
+    network::Network,
+    prune::{PruneMethod, PruningSchedule},
+    registry::{MetricGoal, ModelRegistry},
+    rng,
+};
+use nn_train::{
+    test::test,
+    train::{train, TrainOptions},
+};
+
+// prunes the best registered model to different sparsities, fine-tuning it while pruning, and
+// reports test error and model file size of each pruned network
+const TRAIN_IMAGES_FILE_PATH: &str = "../../data/mnist/train-images-idx3-ubyte";
+const TRAIN_LABELS_FILE_PATH: &str = "../../data/mnist/train-labels-idx1-ubyte";
+const TEST_IMAGES_FILE_PATH: &str = "../../data/mnist/t10k-images-idx3-ubyte";
+const TEST_LABELS_FILE_PATH: &str = "../../data/mnist/t10k-labels-idx1-ubyte";
+
+const MODEL_FILE_NAME_PREFIX: &str = "digits-pruned";
+const MODELS_DIR: &str = "./models";
+const REGISTRY_DIR: &str = "./models/registry";
+const PLOTS_DIR: &str = "./plots";
+const FAILED_IMAGES_DIR: &str = "./images";
+
+const METHODS: [PruneMethod; 2] = [PruneMethod::Unstructured, PruneMethod::Structured];
+const SPARSITIES: [f64; 4] = [0.5, 0.8, 0.9, 0.95];
+
+const EPOCHS: u32 = 1;
+const BATCHES: u32 = 1000;
+const BATCH_SIZE: u32 = 10;
+const LEARNING_RATE: (f64, f64) = (0.01, 0.001);
+const THREADS: usize = 4;
+const SHUFFLE_BUFFER_SIZE: usize = 1000;
+const SEED: Option<u64> = Some(0);
+// network is pruned gradually over the first part of fine-tuning and recovers during the rest
+const PRUNING_END_BATCH: usize = 600;
+const PRUNING_FREQUENCY: usize = 50;
+
+struct ReportRow {
+    method: Option<PruneMethod>,
+    sparsity: f64,
+    test_error: f64,
+    file_size: usize,
+}
+
+fn main() {
+    if let Some(seed) = SEED {
+        rng::seed(seed);
+    }
+
+    let registry = ModelRegistry::open(REGISTRY_DIR).expect("failed to open model registry");
+
+    let id = registry
+        .best_by("test_error", MetricGoal::Minimize)
+        .map(|entry| entry.id.clone())
+        .expect("failed to find trained model, run nn_train_runner first");
+
+    println!("loading model from registry: {id}");
+    let net = registry.load(&id).expect("failed to load model");
+
+    let mut rows = vec![evaluate(&net, None)];
+
+    for method in METHODS {
+        for sparsity in SPARSITIES {
+            println!("pruning: {method:?}, sparsity: {sparsity}");
+
+            let mut pruned = copy(&net);
+
+            train(
+                &mut pruned,
+                &TrainOptions {
+                    images_file_path: TRAIN_IMAGES_FILE_PATH,
+                    labels_file_path: TRAIN_LABELS_FILE_PATH,
+                    models_dir: MODELS_DIR,
+                    model_file_name_prefix: MODEL_FILE_NAME_PREFIX,
+                    plots_dir: PLOTS_DIR,
+                    epochs: EPOCHS,
+                    batches: BATCHES,
+                    batch_size: BATCH_SIZE,
+                    learning_rate: LEARNING_RATE,
+                    regularization: None,
+                    threads: THREADS,
+                    shuffle_buffer_size: Some(SHUFFLE_BUFFER_SIZE),
+                    pruning: Some(
+                        PruningSchedule::new(method, sparsity, 0, PRUNING_END_BATCH)
+                            .with_frequency(PRUNING_FREQUENCY),
+                    ),
+                    plot_losses_each_nth_batch: None,
+                    serialize_model_each_nth_batch: None,
+                    checkpoint_file_path: None,
+                },
+            );
+
+            rows.push(evaluate(&pruned, Some(method)));
+        }
+    }
+
+    println!(
+        "{:<14} {:>9} {:>11} {:>11}",
+        "method", "sparsity", "test error", "file size"
+    );
+
+    for row in rows {
+        let method = row
+            .method
+            .map_or(String::from("none"), |method| format!("{method:?}"));
+
+        println!(
+            "{:<14} {:>8.2}% {:>10.2}% {:>8} kb",
+            method.to_lowercase(),
+            row.sparsity * 100.0,
+            row.test_error * 100.0,
+            row.file_size / 1024
+        );
+    }
+}
+
+fn evaluate(net: &Network, method: Option<PruneMethod>) -> ReportRow {
+    let test_error = test(
+        net,
+        TEST_IMAGES_FILE_PATH,
+        TEST_LABELS_FILE_PATH,
+        FAILED_IMAGES_DIR,
+        false,
+    );
+
+    ReportRow {
+        method,
+        sparsity: net.sparsity(),
+        test_error,
+        file_size: serialize(net).len(),
+    }
+}
+
+fn serialize(net: &Network) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    net.serialize_to_writer(&mut bytes)
+        .expect("failed to serialize network");
+    bytes
+}
+
+fn copy(net: &Network) -> Network {
+    Network::deserialize_from_reader(serialize(net).as_slice())
+        .expect("failed to deserialize network")
+}
@@ -0,0 +1,47 @@
+use network::rng;
+use nn_train::vae::{train_vae, VaeOptions};
+
+// trains variational autoencoder on mnist, which generates new digits and denoises given ones
+const TRAIN_IMAGES_FILE_PATH: &str = "../../data/mnist/train-images-idx3-ubyte";
+const IMAGES_DIR: &str = "./images";
+
+const EPOCHS: u32 = 5;
+const BATCHES: u32 = 1000;
+const BATCH_SIZE: u32 = 10;
+const LEARNING_RATE: f64 = 0.001;
+const HIDDEN_SIZE: usize = 64;
+// two dimensional latent space makes interpolations easy to follow
+const LATENT_SIZE: usize = 2;
+const BETA: f64 = 1.0;
+const INPUT_NOISE: f64 = 0.2;
+const SAMPLES_GRID_SIZE: u32 = 8;
+const INTERPOLATION_STEPS: u32 = 10;
+const LOG_EACH_NTH_BATCH: u32 = 50;
+const SEED: Option<u64> = Some(0);
+
+fn main() {
+    if let Some(seed) = SEED {
+        rng::seed(seed);
+    }
+
+    let vae = train_vae(&VaeOptions {
+        images_file_path: TRAIN_IMAGES_FILE_PATH,
+        images_dir: IMAGES_DIR,
+        epochs: EPOCHS,
+        batches: BATCHES,
+        batch_size: BATCH_SIZE,
+        learning_rate: LEARNING_RATE,
+        hidden_size: HIDDEN_SIZE,
+        latent_size: LATENT_SIZE,
+        beta: BETA,
+        input_noise: INPUT_NOISE,
+        samples_grid_size: SAMPLES_GRID_SIZE,
+        interpolation_steps: INTERPOLATION_STEPS,
+        log_each_nth_batch: LOG_EACH_NTH_BATCH,
+    });
+
+    println!("encoder:\n{}", vae.encoder);
+    println!("decoder:\n{}", vae.decoder);
+
+    println!("sample grids, interpolations and denoised images are saved to {IMAGES_DIR}");
+}
@@ -1,5 +1,15 @@
-use network::network::Network;
-use nn_train::{test::test, train::train};
+use std::collections::BTreeMap;
+
+use network::{
+    network::Network,
+    registry::{MetricGoal, ModelRegistry},
+    regularization::Regularization,
+    rng,
+};
+use nn_train::{
+    test::test,
+    train::{train, TrainOptions},
+};
 
 const TRAIN_IMAGES_FILE_PATH: &str = "../../data/mnist/train-images-idx3-ubyte";
 const TRAIN_LABELS_FILE_PATH: &str = "../../data/mnist/train-labels-idx1-ubyte";
@@ -8,6 +18,8 @@ const TEST_LABELS_FILE_PATH: &str = "../../data/mnist/t10k-labels-idx1-ubyte";
 
 const MODEL_FILE_NAME_PREFIX: &str = "digits";
 const MODELS_DIR: &str = "./models";
+const REGISTRY_DIR: &str = "./models/registry";
+const CHECKPOINT_FILE_PATH: &str = "./models/checkpoint.nc";
 const PLOTS_DIR: &str = "./plots";
 const FAILED_IMAGES_DIR: &str = "./images";
 
@@ -15,36 +27,80 @@ const EPOCHS: u32 = 1;
 const BATCHES: u32 = 6000;
 const BATCH_SIZE: u32 = 10;
 const LEARNING_RATE: (f64, f64) = (0.01, 0.01);
+const REGULARIZATION: Option<Regularization> = None;
+const THREADS: usize = 4;
+// whole mnist train set doesn't fit in memory as f64 vectors, so it's shuffled partially
+const SHUFFLE_BUFFER_SIZE: usize = 1000;
+// makes network initialization and samples order reproducible, None for random ones
+const SEED: Option<u64> = Some(0);
 const PLOT_LOSSES_EACH_NTH_BATCH: u32 = 50;
 const SERIALIZE_MODEL_EACH_NTH_BATCH: u32 = 100;
 
 fn main() {
-    let mut net = Network::new_or_deserialize_from_file(
-        vec![784, 200, 80, 10],
-        MODELS_DIR,
-        MODEL_FILE_NAME_PREFIX,
-    );
+    if let Some(seed) = SEED {
+        rng::seed(seed);
+    }
+
+    let mut registry = ModelRegistry::open(REGISTRY_DIR).expect("failed to open model registry");
+
+    // continue training the best registered model, if there is one
+    let parent = registry
+        .best_by("test_error", MetricGoal::Minimize)
+        .map(|entry| entry.id.clone());
+
+    let mut net = match &parent {
+        Some(id) => {
+            println!("loading model from registry: {id}");
+            registry.load(id).expect("failed to load model")
+        }
+        None => Network::new_or_deserialize_from_file(
+            vec![784, 200, 80, 10],
+            MODELS_DIR,
+            MODEL_FILE_NAME_PREFIX,
+        ),
+    };
 
-    train(
+    println!("{net}");
+
+    let report = train(
         &mut net,
-        TRAIN_IMAGES_FILE_PATH,
-        TRAIN_LABELS_FILE_PATH,
-        MODELS_DIR,
-        MODEL_FILE_NAME_PREFIX,
-        PLOTS_DIR,
-        EPOCHS,
-        BATCHES,
-        BATCH_SIZE,
-        LEARNING_RATE,
-        Some(PLOT_LOSSES_EACH_NTH_BATCH),
-        Some(SERIALIZE_MODEL_EACH_NTH_BATCH),
+        &TrainOptions {
+            images_file_path: TRAIN_IMAGES_FILE_PATH,
+            labels_file_path: TRAIN_LABELS_FILE_PATH,
+            models_dir: MODELS_DIR,
+            model_file_name_prefix: MODEL_FILE_NAME_PREFIX,
+            plots_dir: PLOTS_DIR,
+            epochs: EPOCHS,
+            batches: BATCHES,
+            batch_size: BATCH_SIZE,
+            learning_rate: LEARNING_RATE,
+            regularization: REGULARIZATION,
+            threads: THREADS,
+            shuffle_buffer_size: Some(SHUFFLE_BUFFER_SIZE),
+            pruning: None,
+            plot_losses_each_nth_batch: Some(PLOT_LOSSES_EACH_NTH_BATCH),
+            serialize_model_each_nth_batch: Some(SERIALIZE_MODEL_EACH_NTH_BATCH),
+            checkpoint_file_path: Some(CHECKPOINT_FILE_PATH),
+        },
     );
 
-    test(
+    let test_error = test(
         &net,
         TEST_IMAGES_FILE_PATH,
         TEST_LABELS_FILE_PATH,
         FAILED_IMAGES_DIR,
         false,
     );
+
+    let mut metrics = BTreeMap::from([(String::from("test_error"), test_error)]);
+
+    if let Some(last_epoch) = report.epochs.last() {
+        metrics.insert(String::from("loss"), last_epoch.loss);
+    }
+
+    let id = registry
+        .register(MODEL_FILE_NAME_PREFIX, &net, metrics, parent.as_deref())
+        .expect("failed to register model");
+
+    println!("registered model: {id}");
 }